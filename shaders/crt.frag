@@ -0,0 +1,35 @@
+// IMPORTANT: This shader needs to be compiled out-of-band to SPIR-V
+// See: https://github.com/parasyte/pixels/issues/9
+
+#version 450
+
+layout(location = 0) in vec2 v_TexCoord;
+layout(location = 0) out vec4 outColor;
+layout(set = 0, binding = 0) uniform texture2D t_Color;
+layout(set = 0, binding = 1) uniform sampler s_Color;
+layout(set = 0, binding = 3) uniform Locals {
+    // x: scanline intensity, y: curvature, z: mask strength, w: texture height
+    vec4 u_Crt;
+};
+
+const float PI = 3.14159265;
+
+void main() {
+    // Bend the screen outward, like the glass of a picture tube
+    vec2 centered = v_TexCoord * 2.0 - 1.0;
+    centered *= 1.0 + u_Crt.y * centered.yx * centered.yx;
+    vec2 uv = centered * 0.5 + 0.5;
+    vec2 edge = step(vec2(0.0), uv) * step(uv, vec2(1.0));
+
+    vec4 color = texture(sampler2D(t_Color, s_Color), uv);
+
+    // Darken the gaps between rows of pixels
+    float scanline = sin(uv.y * u_Crt.w * PI);
+    float brightness = mix(1.0 - u_Crt.x, 1.0, scanline * scanline);
+
+    // Aperture grille: each column of the surface passes one color channel at full strength
+    float column = floor(mod(gl_FragCoord.x, 3.0));
+    vec3 mask = mix(vec3(1.0 - u_Crt.z), vec3(1.0), equal(vec3(column), vec3(0.0, 1.0, 2.0)));
+
+    outColor = vec4(color.rgb * brightness * mask * edge.x * edge.y, 1.0);
+}
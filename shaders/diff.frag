@@ -0,0 +1,21 @@
+// IMPORTANT: This shader needs to be compiled out-of-band to SPIR-V
+// See: https://github.com/parasyte/pixels/issues/9
+
+#version 450
+
+layout(location = 0) in vec2 v_TexCoord;
+layout(location = 0) out vec4 outColor;
+layout(set = 0, binding = 0) uniform texture2D t_Color;
+layout(set = 0, binding = 1) uniform sampler s_Color;
+layout(set = 0, binding = 3) uniform texture2D t_Previous;
+layout(set = 0, binding = 4) uniform Locals {
+    vec4 u_Highlight;
+};
+
+void main() {
+    vec4 current = texture(sampler2D(t_Color, s_Color), v_TexCoord);
+    vec4 previous = texture(sampler2D(t_Previous, s_Color), v_TexCoord);
+    float changed = any(notEqual(current, previous)) ? 1.0 : 0.0;
+
+    outColor = vec4(u_Highlight.rgb, u_Highlight.a * changed);
+}
@@ -0,0 +1,31 @@
+// IMPORTANT: This shader needs to be compiled out-of-band to SPIR-V
+// See: https://github.com/parasyte/pixels/issues/9
+
+#version 450
+
+layout(location = 0) in vec2 v_TexCoord;
+layout(location = 0) out vec4 outColor;
+layout(set = 0, binding = 0) uniform texture2D t_Color;
+layout(set = 0, binding = 1) uniform sampler s_Color;
+layout(set = 0, binding = 3) uniform Locals {
+    // x: strength, negative to blur and positive to sharpen
+    vec4 u_Filter;
+};
+
+void main() {
+    // One surface pixel in texture coordinates
+    vec2 texel = fwidth(v_TexCoord);
+    vec2 dx = vec2(texel.x, 0.0);
+    vec2 dy = vec2(0.0, texel.y);
+
+    vec4 center = texture(sampler2D(t_Color, s_Color), v_TexCoord);
+    vec4 blur = center * 4.0;
+    blur += texture(sampler2D(t_Color, s_Color), v_TexCoord + dx);
+    blur += texture(sampler2D(t_Color, s_Color), v_TexCoord - dx);
+    blur += texture(sampler2D(t_Color, s_Color), v_TexCoord + dy);
+    blur += texture(sampler2D(t_Color, s_Color), v_TexCoord - dy);
+    blur *= 0.125;
+
+    // Blurring moves toward the average of the neighbors, sharpening moves away from it
+    outColor = clamp(mix(center, blur, -u_Filter.x), 0.0, 1.0);
+}
@@ -0,0 +1,11 @@
+// IMPORTANT: This shader needs to be compiled out-of-band to SPIR-V
+// See: https://github.com/parasyte/pixels/issues/9
+
+#version 450
+
+layout(location = 0) in vec4 v_Color;
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    outColor = v_Color;
+}
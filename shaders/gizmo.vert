@@ -0,0 +1,22 @@
+// IMPORTANT: This shader needs to be compiled out-of-band to SPIR-V
+// See: https://github.com/parasyte/pixels/issues/9
+
+#version 450
+
+out gl_PerVertex {
+    vec4 gl_Position;
+};
+
+layout(location = 0) in vec2 a_Pos;
+layout(location = 1) in vec4 a_Color;
+
+layout(location = 0) out vec4 v_Color;
+
+layout(set = 0, binding = 0) uniform Locals {
+    mat4 u_Transform;
+};
+
+void main() {
+    v_Color = a_Color;
+    gl_Position = u_Transform * vec4(a_Pos, 0.0, 1.0);
+}
@@ -0,0 +1,37 @@
+// IMPORTANT: This shader needs to be compiled out-of-band to SPIR-V
+// See: https://github.com/parasyte/pixels/issues/9
+
+#version 450
+
+layout(location = 0) in vec2 v_TexCoord;
+layout(location = 0) out vec4 outColor;
+layout(set = 0, binding = 0) uniform texture2D t_Color;
+layout(set = 0, binding = 1) uniform sampler s_Color;
+layout(set = 0, binding = 3) uniform texture2D t_Backdrop;
+layout(set = 0, binding = 4) uniform Locals {
+    float u_SrgbTarget;
+};
+
+vec3 encode(vec3 linear) {
+    vec3 low = linear * 12.92;
+    vec3 high = pow(linear, vec3(1.0 / 2.4)) * 1.055 - 0.055;
+    return mix(high, low, lessThanEqual(linear, vec3(0.0031308)));
+}
+
+vec3 decode(vec3 srgb) {
+    vec3 low = srgb * (1.0 / 12.92);
+    vec3 high = pow((srgb + 0.055) * (1.0 / 1.055), vec3(2.4));
+    return mix(high, low, lessThanEqual(srgb, vec3(0.04045)));
+}
+
+void main() {
+    vec4 color = texture(sampler2D(t_Color, s_Color), v_TexCoord);
+    vec4 backdrop = texelFetch(sampler2D(t_Backdrop, s_Color), ivec2(gl_FragCoord.xy), 0);
+
+    // sRGB render targets hold linear colors, which are encoded before blending
+    vec3 dst = mix(backdrop.rgb, encode(backdrop.rgb), u_SrgbTarget);
+    vec3 blended = mix(dst, encode(color.rgb), color.a);
+    float alpha = color.a + backdrop.a * (1.0 - color.a);
+
+    outColor = vec4(mix(blended, decode(blended), u_SrgbTarget), alpha);
+}
@@ -0,0 +1,41 @@
+// IMPORTANT: This shader needs to be compiled out-of-band to SPIR-V
+// See: https://github.com/parasyte/pixels/issues/9
+
+#version 450
+
+layout(location = 0) in vec2 v_TexCoord;
+layout(location = 0) out vec4 outColor;
+layout(set = 0, binding = 0) uniform texture2D t_Color;
+layout(set = 0, binding = 1) uniform sampler s_Color;
+layout(set = 0, binding = 3) uniform Locals {
+    // rgb: outline color, a: opacity
+    vec4 u_Color;
+    // x: outline width in surface pixels, y: threshold, zw: texture size
+    vec4 u_Outline;
+};
+
+void main() {
+    vec2 size = u_Outline.zw;
+    vec2 position = v_TexCoord * size;
+    vec2 center = floor(position) + 0.5;
+
+    // Distance to the edges of the source pixel, in surface pixels
+    vec2 scale = 1.0 / fwidth(position);
+    vec2 near = fract(position) * scale;
+    vec2 far = (1.0 - fract(position)) * scale;
+    float half_width = u_Outline.x * 0.5;
+
+    vec4 color = texture(sampler2D(t_Color, s_Color), center / size);
+    vec4 left = texture(sampler2D(t_Color, s_Color), (center - vec2(1.0, 0.0)) / size);
+    vec4 right = texture(sampler2D(t_Color, s_Color), (center + vec2(1.0, 0.0)) / size);
+    vec4 up = texture(sampler2D(t_Color, s_Color), (center - vec2(0.0, 1.0)) / size);
+    vec4 down = texture(sampler2D(t_Color, s_Color), (center + vec2(0.0, 1.0)) / size);
+
+    // Outlines are centered on the edges between pixels which differ by at least the threshold
+    float edge = step(u_Outline.y, distance(color, left)) * step(near.x, half_width);
+    edge = max(edge, step(u_Outline.y, distance(color, right)) * step(far.x, half_width));
+    edge = max(edge, step(u_Outline.y, distance(color, up)) * step(near.y, half_width));
+    edge = max(edge, step(u_Outline.y, distance(color, down)) * step(far.y, half_width));
+
+    outColor = mix(color, vec4(u_Color.rgb, 1.0), edge * u_Color.a);
+}
@@ -0,0 +1,19 @@
+// IMPORTANT: This shader needs to be compiled out-of-band to SPIR-V
+// See: https://github.com/parasyte/pixels/issues/9
+
+#version 450
+
+layout(location = 0) in vec2 v_TexCoord;
+layout(location = 0) out vec4 outColor;
+layout(set = 0, binding = 0) uniform texture2D t_Color;
+layout(set = 0, binding = 1) uniform sampler s_Color;
+layout(set = 0, binding = 3) uniform Locals {
+    mat4 u_Swizzle;
+    vec4 u_Constant;
+};
+
+void main() {
+    vec4 color = texture(sampler2D(t_Color, s_Color), v_TexCoord);
+
+    outColor = u_Swizzle * color + u_Constant;
+}
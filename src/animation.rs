@@ -0,0 +1,111 @@
+use instant::Instant;
+use std::time::Duration;
+
+/// A sequence of frames uploaded to the GPU once, and cycled at a fixed rate.
+#[derive(Debug)]
+pub(crate) struct Animation {
+    textures: Vec<wgpu::Texture>,
+    interval: Duration,
+    start: Instant,
+    shown: Option<usize>,
+}
+
+impl Animation {
+    /// Upload all `frames` into textures with the same size and format as the pixel buffer. The
+    /// first frame is shown at `start`.
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        frames: &[&[u8]],
+        fps: f64,
+        format: wgpu::TextureFormat,
+        extent: wgpu::Extent3d,
+        bytes_per_row: u32,
+        start: Instant,
+    ) -> Animation {
+        let textures = frames
+            .iter()
+            .map(|frame| {
+                let texture = device.create_texture(&wgpu::TextureDescriptor {
+                    label: Some("pixels_animation_texture"),
+                    size: extent,
+                    array_layer_count: 1,
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage: wgpu::TextureUsage::COPY_SRC | wgpu::TextureUsage::COPY_DST,
+                });
+                let buffer = device.create_buffer_with_data(frame, wgpu::BufferUsage::COPY_SRC);
+
+                encoder.copy_buffer_to_texture(
+                    wgpu::BufferCopyView {
+                        buffer: &buffer,
+                        offset: 0,
+                        bytes_per_row,
+                        rows_per_image: extent.height,
+                    },
+                    wgpu::TextureCopyView {
+                        texture: &texture,
+                        mip_level: 0,
+                        array_layer: 0,
+                        origin: wgpu::Origin3d { x: 0, y: 0, z: 0 },
+                    },
+                    extent,
+                );
+
+                texture
+            })
+            .collect();
+
+        Animation {
+            textures,
+            interval: Duration::from_secs_f64(1.0 / fps),
+            start,
+            shown: None,
+        }
+    }
+
+    /// The index of the frame to show at `now`.
+    fn index(&self, now: Instant) -> usize {
+        let elapsed = now.saturating_duration_since(self.start);
+
+        (elapsed.as_nanos() / self.interval.as_nanos().max(1)) as usize % self.textures.len()
+    }
+
+    /// Returns `true` when the frame to show at `now` is not the frame on screen.
+    pub(crate) fn is_changed(&self, now: Instant) -> bool {
+        self.shown != Some(self.index(now))
+    }
+
+    /// Encode a copy of the frame to show at `now` into `texture`, if it is not already there.
+    pub(crate) fn update(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+        extent: wgpu::Extent3d,
+        now: Instant,
+    ) {
+        let index = self.index(now);
+        if self.shown == Some(index) {
+            return;
+        }
+
+        encoder.copy_texture_to_texture(
+            wgpu::TextureCopyView {
+                texture: &self.textures[index],
+                mip_level: 0,
+                array_layer: 0,
+                origin: wgpu::Origin3d { x: 0, y: 0, z: 0 },
+            },
+            wgpu::TextureCopyView {
+                texture,
+                mip_level: 0,
+                array_layer: 0,
+                origin: wgpu::Origin3d { x: 0, y: 0, z: 0 },
+            },
+            extent,
+        );
+        self.shown = Some(index);
+    }
+}
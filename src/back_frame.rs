@@ -0,0 +1,106 @@
+use std::mem;
+use std::sync::{Arc, Mutex};
+
+use crate::crash::lock;
+
+/// The back buffer of a double-buffered pixel buffer, for drawing the next frame while the
+/// current frame is uploaded and rendered.
+///
+/// Get the back buffer with [`Pixels::get_back_frame`], draw the next frame with
+/// [`BackFrame::draw`], and show it with [`Pixels::swap_buffers`]. Only frames which were drawn
+/// completely are swapped, so the pixel buffer never shows a torn frame.
+///
+/// Back frames are cheap to clone, and clones share the same buffer, so game logic can draw from
+/// another thread.
+///
+/// # Examples
+///
+/// ```no_run
+/// use pixels::Pixels;
+///
+/// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+/// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+/// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+///
+/// let back_frame = pixels.get_back_frame();
+/// std::thread::spawn(move || loop {
+///     back_frame.draw(|frame| {
+///         // ... Update the game and draw the next frame
+///     });
+/// });
+///
+/// loop {
+///     pixels.swap_buffers();
+///     pixels.render()?;
+/// }
+/// # Ok::<(), pixels::Error>(())
+/// ```
+///
+/// [`Pixels::get_back_frame`]: ./struct.Pixels.html#method.get_back_frame
+/// [`Pixels::swap_buffers`]: ./struct.Pixels.html#method.swap_buffers
+#[derive(Clone, Debug)]
+pub struct BackFrame {
+    shared: Arc<Mutex<BackState>>,
+}
+
+#[derive(Debug)]
+struct BackState {
+    frame: Vec<u8>,
+    // A frame was drawn since the last swap
+    complete: bool,
+}
+
+impl BackFrame {
+    pub(crate) fn new(frame: Vec<u8>) -> BackFrame {
+        BackFrame {
+            shared: Arc::new(Mutex::new(BackState {
+                frame,
+                complete: false,
+            })),
+        }
+    }
+
+    /// Draw the next frame into the back buffer.
+    ///
+    /// `draw` receives the back buffer, with the same layout as [`Pixels::get_frame`]. It holds
+    /// the frame which was shown before the most recent swap, or the frame drawn by the previous
+    /// call when there was no swap in between. The frame is swapped in by the next call to
+    /// [`Pixels::swap_buffers`] after `draw` returns, which waits while a frame is being drawn.
+    ///
+    /// [`Pixels::get_frame`]: ./struct.Pixels.html#method.get_frame
+    /// [`Pixels::swap_buffers`]: ./struct.Pixels.html#method.swap_buffers
+    pub fn draw<F>(&self, draw: F)
+    where
+        F: FnOnce(&mut [u8]),
+    {
+        let mut state = lock(&self.shared);
+        draw(&mut state.frame);
+        state.complete = true;
+    }
+
+    /// Returns `true` when a frame was drawn since the last swap.
+    pub fn is_complete(&self) -> bool {
+        lock(&self.shared).complete
+    }
+
+    /// Exchange `front` with the back buffer if a frame was drawn since the last swap.
+    pub(crate) fn swap(&self, front: &mut Vec<u8>) -> bool {
+        let mut state = lock(&self.shared);
+        if !state.complete {
+            return false;
+        }
+        mem::swap(&mut state.frame, front);
+        state.complete = false;
+
+        true
+    }
+
+    /// Replace the back buffer with a copy of `front`, discarding any frame which was not swapped,
+    /// e.g. after the pixel buffer was resized.
+    pub(crate) fn reset(&self, front: &[u8]) {
+        let mut state = lock(&self.shared);
+        state.frame.clear();
+        state.frame.extend_from_slice(front);
+        state.complete = false;
+    }
+}
@@ -0,0 +1,43 @@
+use instant::Instant;
+use std::time::Duration;
+
+/// How often a backgrounded pixel buffer is rendered.
+///
+/// Multi-window applications can mark unfocused or hidden views as backgrounded with
+/// [`Pixels::set_background`], so they stop competing with the focused view for GPU time. While
+/// backgrounded, calls to [`Pixels::render`] which are not allowed by the throttle return
+/// immediately, without uploading or presenting anything. Changes to the pixel buffer are kept,
+/// and uploaded by the next frame which is rendered.
+///
+/// Set the throttle with [`PixelsBuilder::background_throttle`].
+///
+/// [`Pixels::set_background`]: ./struct.Pixels.html#method.set_background
+/// [`Pixels::render`]: ./struct.Pixels.html#method.render
+/// [`PixelsBuilder::background_throttle`]: ./struct.PixelsBuilder.html#method.background_throttle
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum BackgroundThrottle {
+    /// Render at most one frame per `interval`. The default is 100 milliseconds, or 10 frames
+    /// per second.
+    Interval(Duration),
+    /// Do not render at all until the pixel buffer is no longer backgrounded.
+    Paused,
+}
+
+impl Default for BackgroundThrottle {
+    fn default() -> BackgroundThrottle {
+        BackgroundThrottle::Interval(Duration::from_millis(100))
+    }
+}
+
+impl BackgroundThrottle {
+    /// Returns `true` when a backgrounded pixel buffer which last rendered at `last_render` may
+    /// render again at `now`.
+    pub(crate) fn allows(self, last_render: Option<Instant>, now: Instant) -> bool {
+        match self {
+            BackgroundThrottle::Interval(interval) => last_render.map_or(true, |last_render| {
+                now.saturating_duration_since(last_render) >= interval
+            }),
+            BackgroundThrottle::Paused => false,
+        }
+    }
+}
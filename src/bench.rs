@@ -0,0 +1,191 @@
+//! Throughput measurements for the current device.
+//!
+//! Upload and render costs vary wildly between GPUs and drivers, so the best buffer size or
+//! [`FrameFormat`] on one machine can be a poor choice on another. [`UploadBench`] renders a
+//! pixel buffer offscreen for a number of frames and reports how long uploads and renders took,
+//! so applications can tune themselves on first run and cache the result.
+//!
+//! [`FrameFormat`]: ../enum.FrameFormat.html
+
+use instant::Instant;
+use std::time::Duration;
+
+use crate::{Error, FrameFormat, Pixels, PixelsBuilder, SurfaceTexture};
+
+/// Measures upload and render throughput for a pixel buffer configuration.
+///
+/// The pixel buffer is rendered with [`SurfaceTexture::offscreen`], so no window is needed and
+/// Vsync does not limit the frame rate. Each measured frame waits for the GPU to finish before
+/// the next one starts.
+///
+/// # Examples
+///
+/// ```no_run
+/// use pixels::bench::UploadBench;
+/// use pixels::FrameFormat;
+///
+/// let rgba = UploadBench::new(640, 480).run()?;
+/// let indexed = UploadBench::new(640, 480)
+///     .frame_format(FrameFormat::Indexed8)
+///     .run()?;
+///
+/// if indexed.frames_per_second() > rgba.frames_per_second() {
+///     // ... Prefer the indexed frame format on this device
+/// }
+/// # Ok::<(), pixels::Error>(())
+/// ```
+///
+/// [`SurfaceTexture::offscreen`]: ../struct.SurfaceTexture.html#method.offscreen
+#[derive(Clone, Debug)]
+pub struct UploadBench {
+    width: u32,
+    height: u32,
+    surface_size: (u32, u32),
+    frames: u32,
+    frame_format: FrameFormat,
+    texture_format: wgpu::TextureFormat,
+}
+
+/// The results of an [`UploadBench`] run.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BenchReport {
+    /// The number of frames measured for each phase.
+    pub frames: u32,
+    /// The size of one pixel buffer in bytes.
+    pub frame_bytes: usize,
+    /// The average time to upload one pixel buffer.
+    pub upload: Duration,
+    /// The average time to render one frame without uploading.
+    pub render: Duration,
+}
+
+impl UploadBench {
+    /// Create a benchmark for a pixel buffer of `width` by `height` pixels.
+    ///
+    /// By default, 120 frames are measured on an offscreen surface of the same size, with the
+    /// builder's default frame and texture formats.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `width` or `height` are 0.
+    pub fn new(width: u32, height: u32) -> UploadBench {
+        assert!(width > 0);
+        assert!(height > 0);
+
+        UploadBench {
+            width,
+            height,
+            surface_size: (width, height),
+            frames: 120,
+            frame_format: FrameFormat::Texture,
+            texture_format: wgpu::TextureFormat::Rgba8UnormSrgb,
+        }
+    }
+
+    /// Set the size of the offscreen surface that the pixel buffer is scaled to.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `width` or `height` are 0.
+    pub fn surface_size(mut self, width: u32, height: u32) -> UploadBench {
+        assert!(width > 0);
+        assert!(height > 0);
+
+        self.surface_size = (width, height);
+        self
+    }
+
+    /// Set the number of frames measured for each phase.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `frames` is 0.
+    pub fn frames(mut self, frames: u32) -> UploadBench {
+        assert!(frames > 0);
+
+        self.frames = frames;
+        self
+    }
+
+    /// Set the layout of the pixel buffer. See [`PixelsBuilder::frame_format`].
+    ///
+    /// [`PixelsBuilder::frame_format`]: ../struct.PixelsBuilder.html#method.frame_format
+    pub fn frame_format(mut self, frame_format: FrameFormat) -> UploadBench {
+        self.frame_format = frame_format;
+        self
+    }
+
+    /// Set the texture format. See [`PixelsBuilder::texture_format`].
+    ///
+    /// [`PixelsBuilder::texture_format`]: ../struct.PixelsBuilder.html#method.texture_format
+    pub fn texture_format(mut self, texture_format: wgpu::TextureFormat) -> UploadBench {
+        self.texture_format = texture_format;
+        self
+    }
+
+    /// Create the pixel buffer and measure it.
+    ///
+    /// Renders without uploading are measured first, then renders which upload the entire pixel
+    /// buffer. The difference between the two is reported as the upload time.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the pixel buffer cannot be created, or a frame cannot be rendered.
+    pub fn run(&self) -> Result<BenchReport, Error> {
+        let (surface_width, surface_height) = self.surface_size;
+        let mut pixels = PixelsBuilder::new(
+            self.width,
+            self.height,
+            SurfaceTexture::offscreen(surface_width, surface_height),
+        )
+        .enable_vsync(false)
+        .frame_format(self.frame_format)
+        .texture_format(self.texture_format)
+        .build()?;
+        let frame_bytes = pixels.get_frame().len();
+
+        // Warm up, so pipeline creation and the first upload are not measured
+        pixels.render()?;
+        pixels.wait_idle();
+
+        let render = self.measure(&mut pixels, |_| ())?;
+        let upload_and_render = self.measure(&mut pixels, |pixels| {
+            pixels.get_frame();
+        })?;
+
+        Ok(BenchReport {
+            frames: self.frames,
+            frame_bytes,
+            upload: upload_and_render.checked_sub(render).unwrap_or_default(),
+            render,
+        })
+    }
+
+    /// The average time to render a frame after calling `prepare`.
+    fn measure<F>(&self, pixels: &mut Pixels, prepare: F) -> Result<Duration, Error>
+    where
+        F: Fn(&mut Pixels),
+    {
+        let start = Instant::now();
+        for _ in 0..self.frames {
+            prepare(pixels);
+            pixels.render()?;
+            pixels.wait_idle();
+        }
+
+        Ok(start.elapsed() / self.frames)
+    }
+}
+
+impl BenchReport {
+    /// The average number of frames per second, when every frame uploads the entire pixel
+    /// buffer.
+    pub fn frames_per_second(&self) -> f64 {
+        1.0 / (self.upload + self.render).as_secs_f64()
+    }
+
+    /// The average number of bytes uploaded per second.
+    pub fn upload_bytes_per_second(&self) -> f64 {
+        self.frame_bytes as f64 / self.upload.as_secs_f64()
+    }
+}
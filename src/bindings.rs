@@ -0,0 +1,72 @@
+//! The bind group layout used by the built-in shaders.
+//!
+//! The scaling renderer, and the shaders in the `shaders` directory, read the pixel buffer
+//! through bind group 0 with the bindings below. Custom render passes which reuse those shaders,
+//! or mirror their interface, should use these constants instead of hardcoding the binding
+//! numbers and uniform sizes.
+//!
+//! | Binding       | Type                                            | Contents                  |
+//! |---------------|-------------------------------------------------|---------------------------|
+//! | [`TEXTURE`]   | `texture2D`                                     | The pixel buffer texture  |
+//! | [`SAMPLER`]   | `sampler`                                       | The input sampler         |
+//! | [`TRANSFORM`] | `uniform Locals { mat4 u_Transform; }`          | The scaling transform     |
+//! | [`SWIZZLE`]   | `uniform { mat4 u_Swizzle; vec4 u_Constant; }`  | The pixel buffer swizzle  |
+//! | [`USER_DATA`] | `uniform UserData { ... }`                      | The per-frame user data   |
+//!
+//! # Examples
+//!
+//! ```
+//! use pixels::bindings;
+//!
+//! fn create_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+//!     device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+//!         label: Some("my_pass_bind_group_layout"),
+//!         bindings: &[
+//!             wgpu::BindGroupLayoutEntry {
+//!                 binding: bindings::TEXTURE,
+//!                 visibility: wgpu::ShaderStage::FRAGMENT,
+//!                 ty: wgpu::BindingType::SampledTexture {
+//!                     component_type: wgpu::TextureComponentType::Float,
+//!                     multisampled: false,
+//!                     dimension: wgpu::TextureViewDimension::D2,
+//!                 },
+//!             },
+//!             wgpu::BindGroupLayoutEntry {
+//!                 binding: bindings::SAMPLER,
+//!                 visibility: wgpu::ShaderStage::FRAGMENT,
+//!                 ty: wgpu::BindingType::Sampler { comparison: false },
+//!             },
+//!             wgpu::BindGroupLayoutEntry {
+//!                 binding: bindings::TRANSFORM,
+//!                 visibility: wgpu::ShaderStage::VERTEX,
+//!                 ty: wgpu::BindingType::UniformBuffer { dynamic: false },
+//!             },
+//!         ],
+//!     })
+//! }
+//! ```
+
+/// The binding of the pixel buffer texture.
+pub const TEXTURE: u32 = 0;
+
+/// The binding of the sampler used to read [`TEXTURE`].
+pub const SAMPLER: u32 = 1;
+
+/// The binding of the uniform buffer holding the 4x4 transform matrix for the quad.
+pub const TRANSFORM: u32 = 2;
+
+/// The size of the [`TRANSFORM`] uniform in bytes.
+pub const TRANSFORM_SIZE: wgpu::BufferAddress = 64;
+
+/// The binding of the uniform buffer holding the swizzle matrix and constant vector.
+pub const SWIZZLE: u32 = 3;
+
+/// The size of the [`SWIZZLE`] uniform in bytes.
+pub const SWIZZLE_SIZE: wgpu::BufferAddress = 80;
+
+/// The binding suggested for the uniform buffer holding the per-frame user data.
+///
+/// The built-in shaders do not read the user data. See [`RenderPassOptions::user_data`].
+///
+/// [`RenderPassOptions::user_data`]: ../struct.RenderPassOptions.html#structfield.user_data
+pub const USER_DATA: u32 = 4;
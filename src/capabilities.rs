@@ -0,0 +1,107 @@
+use std::fmt;
+
+/// What the pixel buffer ended up using, and which requested options were downgraded.
+///
+/// Options that cannot be honored on the current machine are substituted silently by
+/// [`PixelsBuilder::build`], so the same application can behave differently across machines. Use
+/// [`Pixels::capabilities`] to inform users instead, e.g. in a settings menu or a log file.
+///
+/// # Examples
+///
+/// ```no_run
+/// use pixels::Pixels;
+///
+/// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+/// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+/// let pixels = Pixels::new(320, 240, surface_texture)?;
+///
+/// let capabilities = pixels.capabilities();
+/// println!("Rendering with {}", capabilities.adapter().name);
+/// for downgrade in capabilities.downgrades() {
+///     eprintln!("Warning: {}", downgrade);
+/// }
+/// # Ok::<(), pixels::Error>(())
+/// ```
+///
+/// [`PixelsBuilder::build`]: ./struct.PixelsBuilder.html#method.build
+/// [`Pixels::capabilities`]: ./struct.Pixels.html#method.capabilities
+#[derive(Clone, Debug)]
+pub struct CapabilitiesReport {
+    adapter: wgpu::AdapterInfo,
+    downgrades: Vec<Downgrade>,
+}
+
+/// A requested option which could not be honored.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Downgrade {
+    /// The present mode was replaced, e.g. because [`Priority::Background`] always waits for
+    /// Vsync.
+    ///
+    /// [`Priority::Background`]: ./enum.Priority.html#variant.Background
+    PresentMode {
+        /// The requested present mode.
+        requested: wgpu::PresentMode,
+        /// The present mode in use.
+        used: wgpu::PresentMode,
+    },
+    /// A high performance GPU was requested, but the adapter is not a discrete GPU.
+    PowerPreference {
+        /// The type of the adapter in use.
+        device_type: wgpu::DeviceType,
+    },
+    /// The adapter is a software renderer running on the CPU.
+    SoftwareAdapter,
+    /// Frame insertion was requested, but frames are not presented with Vsync, so none are
+    /// inserted.
+    FrameInsertionDisabled,
+}
+
+impl CapabilitiesReport {
+    pub(crate) fn new(adapter: wgpu::AdapterInfo) -> CapabilitiesReport {
+        CapabilitiesReport {
+            adapter,
+            downgrades: Vec::new(),
+        }
+    }
+
+    /// Record a downgraded option.
+    pub(crate) fn push(&mut self, downgrade: Downgrade) {
+        self.downgrades.push(downgrade);
+    }
+
+    /// Information about the adapter in use.
+    pub fn adapter(&self) -> &wgpu::AdapterInfo {
+        &self.adapter
+    }
+
+    /// All requested options which were downgraded.
+    pub fn downgrades(&self) -> &[Downgrade] {
+        &self.downgrades
+    }
+
+    /// Returns `true` when every requested option was honored.
+    pub fn is_complete(&self) -> bool {
+        self.downgrades.is_empty()
+    }
+}
+
+impl fmt::Display for Downgrade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Downgrade::PresentMode { requested, used } => write!(
+                f,
+                "Present mode {:?} was replaced with {:?}",
+                requested, used
+            ),
+            Downgrade::PowerPreference { device_type } => write!(
+                f,
+                "A high performance GPU was requested, but the adapter is {:?}",
+                device_type
+            ),
+            Downgrade::SoftwareAdapter => write!(f, "The adapter is a software renderer"),
+            Downgrade::FrameInsertionDisabled => {
+                write!(f, "Frame insertion is disabled without Vsync")
+            }
+        }
+    }
+}
@@ -0,0 +1,646 @@
+//! Recording frames to animated images, for sharing clips of pixel games.
+//!
+//! Start a recording with [`Pixels::start_recording`]. Every frame rendered by
+//! [`Pixels::render`] is added to the recording until it is stopped with
+//! [`Pixels::stop_recording`], and frames rendered while the recording is paused with
+//! [`Pixels::pause_recording`] are left out. Each frame is shown for the time until the next
+//! recorded frame, so the clip plays back at the speed it was rendered.
+//!
+//! Frames are encoded and written on a thread of their own, so color quantization and compression
+//! do not hold up rendering. When the thread falls behind by more than a few frames, recording a
+//! frame waits for it.
+//!
+//! This module is only available with the `capture` feature.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use pixels::capture::{RecordingFormat, RecordingSource};
+//! use pixels::Pixels;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+//! # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+//! let mut pixels = Pixels::new(320, 240, surface_texture)?;
+//!
+//! pixels.start_recording("clip.gif", RecordingFormat::Gif, RecordingSource::PixelBuffer)?;
+//! for _ in 0..300 {
+//!     // ... Draw the next frame
+//!     pixels.render()?;
+//! }
+//! pixels.stop_recording()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Pixels::start_recording`]: ../struct.Pixels.html#method.start_recording
+//! [`Pixels::render`]: ../struct.Pixels.html#method.render
+//! [`Pixels::stop_recording`]: ../struct.Pixels.html#method.stop_recording
+//! [`Pixels::pause_recording`]: ../struct.Pixels.html#method.pause_recording
+
+use gif::SetParameter;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::crash::lock;
+use crate::{Error, Screenshot};
+
+/// The PNG file signature.
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+
+/// Most GIF viewers show frames with shorter delays for a tenth of a second, so faster frames are
+/// merged to stay at 50 frames per second.
+const GIF_MIN_DELAY: Duration = Duration::from_millis(20);
+
+/// The number of frames which can wait for the writer thread before recording a frame blocks.
+const WRITE_QUEUE_SIZE: usize = 8;
+
+/// The file format of a recording.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum RecordingFormat {
+    /// An animated GIF, which plays everywhere. Colors are reduced to a palette of 256 colors for
+    /// each frame, which is lossless for most pixel art, and the frame rate is limited to 50
+    /// frames per second. The frames must be at most 65535 pixels wide and tall.
+    Gif,
+    /// An animated PNG, which keeps all colors and the alpha channel, at any frame rate.
+    Apng,
+    /// A directory of PNG images named `frame-00000.png`, `frame-00001.png`, etc., for video
+    /// editors and encoders like FFmpeg. The directory is created if it does not exist.
+    PngSequence,
+}
+
+/// Which image of each frame is recorded.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum RecordingSource {
+    /// The pixel buffer at its own size, as it was uploaded, without any scaling or render passes.
+    /// This is what most pixel games want to share, and it is cheap to record.
+    ///
+    /// Indexed frame formats are expanded with the palette. Other frame formats must use an 8-bit
+    /// `RGBA` or `BGRA` texture format.
+    PixelBuffer,
+    /// The rendered frame at the size of the surface, after all render passes, like a
+    /// [`Screenshot`]. Each frame is read back from the GPU, which costs an extra render of all
+    /// passes.
+    ///
+    /// [`Screenshot`]: ../struct.Screenshot.html
+    RenderedFrame,
+}
+
+/// A recording in progress, attached to a pixel buffer.
+#[derive(Debug)]
+pub(crate) struct Recording {
+    // Shared with the callbacks of frames which are being read back from the GPU
+    recorder: Arc<Mutex<Recorder>>,
+    source: RecordingSource,
+    paused: bool,
+}
+
+/// Times frames as they are recorded, and sends them to the writer thread.
+struct Recorder {
+    // `None` after the recording is finished, which lets the writer thread finish the file
+    frames: Option<SyncSender<FrameToWrite>>,
+    writer: Option<JoinHandle<io::Result<()>>>,
+    size: Option<(u32, u32)>,
+    // The most recent frame, written when the time until the next frame is known
+    pending: Option<PendingFrame>,
+    min_delay: Duration,
+    // The first error, after which all frames are dropped
+    error: Option<io::Error>,
+}
+
+struct PendingFrame {
+    rgba: Vec<u8>,
+    time: Duration,
+}
+
+/// A frame on its way to the writer thread.
+struct FrameToWrite {
+    rgba: Vec<u8>,
+    width: u32,
+    height: u32,
+    delay: Duration,
+}
+
+enum Encoder {
+    // The GIF and APNG headers are written with the size of the first frame
+    Unstarted(RecordingFormat, Option<File>),
+    Gif(gif::Encoder<BufWriter<File>>),
+    Apng(ApngWriter<BufWriter<File>>),
+    PngSequence { dir: PathBuf, next: u32 },
+}
+
+/// Writes an animated PNG, one frame at a time.
+struct ApngWriter<W: Write + Seek> {
+    writer: W,
+    width: u32,
+    height: u32,
+    frames: u32,
+    // Frame control and frame data chunks share one sequence
+    sequence: u32,
+    // The animation control chunk is rewritten with the number of frames when finished
+    animation_control: u64,
+}
+
+impl Recording {
+    /// Create the file or directory at `path` for a new recording.
+    pub(crate) fn create(
+        path: &Path,
+        format: RecordingFormat,
+        source: RecordingSource,
+    ) -> io::Result<Recording> {
+        let encoder = match format {
+            RecordingFormat::Gif | RecordingFormat::Apng => {
+                Encoder::Unstarted(format, Some(File::create(path)?))
+            }
+            RecordingFormat::PngSequence => {
+                fs::create_dir_all(path)?;
+                Encoder::PngSequence {
+                    dir: path.to_path_buf(),
+                    next: 0,
+                }
+            }
+        };
+        let min_delay = if format == RecordingFormat::Gif {
+            GIF_MIN_DELAY
+        } else {
+            Duration::from_secs(0)
+        };
+
+        let (frames, receiver) = mpsc::sync_channel(WRITE_QUEUE_SIZE);
+        let writer = thread::Builder::new()
+            .name("pixels-recording".to_string())
+            .spawn(move || write_frames(encoder, receiver))?;
+
+        Ok(Recording {
+            recorder: Arc::new(Mutex::new(Recorder {
+                frames: Some(frames),
+                writer: Some(writer),
+                size: None,
+                pending: None,
+                min_delay,
+                error: None,
+            })),
+            source,
+            paused: false,
+        })
+    }
+
+    pub(crate) fn source(&self) -> RecordingSource {
+        self.source
+    }
+
+    /// Returns `true` when frames from `source` are recorded right now.
+    pub(crate) fn is_recording(&self, source: RecordingSource) -> bool {
+        self.source == source && !self.paused
+    }
+
+    /// Pause or resume the recording. The frame shown when the recording is paused lasts until
+    /// `time`.
+    pub(crate) fn set_paused(&mut self, paused: bool, time: Duration) {
+        if paused && !self.paused {
+            lock(&self.recorder).flush(time);
+        }
+        self.paused = paused;
+    }
+
+    /// Add a frame of tightly packed `RGBA` pixels, rendered at `time`.
+    pub(crate) fn push(&self, rgba: Vec<u8>, width: u32, height: u32, time: Duration) {
+        lock(&self.recorder).push(rgba, width, height, time);
+    }
+
+    /// A screenshot callback which adds the frame rendered at `time`.
+    pub(crate) fn screenshot_callback(
+        &self,
+        time: Duration,
+    ) -> impl FnOnce(Result<Screenshot, Error>) + Send + 'static {
+        let recorder = self.recorder.clone();
+
+        move |result| {
+            let mut recorder = lock(&recorder);
+            match result {
+                Ok(screenshot) => {
+                    let (width, height) = (screenshot.width(), screenshot.height());
+                    recorder.push(screenshot.into_data(), width, height, time);
+                }
+                Err(err) => recorder.fail(io::Error::new(io::ErrorKind::Other, err)),
+            }
+        }
+    }
+
+    /// Write the last frame, which lasts until `time`, and finish the file.
+    ///
+    /// Returns the first error which happened during the recording.
+    pub(crate) fn finish(self, time: Duration) -> io::Result<()> {
+        lock(&self.recorder).finish(time)
+    }
+}
+
+impl Recorder {
+    fn push(&mut self, rgba: Vec<u8>, width: u32, height: u32, time: Duration) {
+        if self.error.is_some() || self.frames.is_none() {
+            return;
+        }
+        match self.size {
+            Some(size) if size != (width, height) => {
+                self.fail(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "the frame size changed during the recording",
+                ));
+                return;
+            }
+            _ => self.size = Some((width, height)),
+        }
+
+        // Frames faster than the format allows replace the image of the pending frame
+        if let Some(pending) = &mut self.pending {
+            if time.checked_sub(pending.time).unwrap_or_default() < self.min_delay {
+                pending.rgba = rgba;
+                return;
+            }
+        }
+
+        self.flush(time);
+        self.pending = Some(PendingFrame { rgba, time });
+    }
+
+    /// Send the pending frame, which lasts until `time`, to the writer thread.
+    fn flush(&mut self, time: Duration) {
+        let pending = match self.pending.take() {
+            Some(pending) => pending,
+            None => return,
+        };
+        let delay = time
+            .checked_sub(pending.time)
+            .unwrap_or_default()
+            .max(self.min_delay);
+
+        if let (Some(frames), Some((width, height))) = (&self.frames, self.size) {
+            let frame = FrameToWrite {
+                rgba: pending.rgba,
+                width,
+                height,
+                delay,
+            };
+
+            // The writer thread only stops early after an error, which `finish` returns
+            let _ = frames.send(frame);
+        }
+    }
+
+    fn fail(&mut self, err: io::Error) {
+        if self.error.is_none() {
+            self.error = Some(err);
+        }
+    }
+
+    fn finish(&mut self, time: Duration) -> io::Result<()> {
+        self.flush(time);
+
+        // Closing the channel lets the writer thread write the remaining frames and the end of
+        // the file
+        self.frames = None;
+        let written = match self.writer.take() {
+            Some(writer) => writer.join().unwrap_or_else(|_| {
+                Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "the recording thread panicked",
+                ))
+            }),
+            None => Ok(()),
+        };
+
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => written,
+        }
+    }
+}
+
+impl fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Recorder")
+            .field("size", &self.size)
+            .field("finished", &self.frames.is_none())
+            .field("error", &self.error)
+            .finish()
+    }
+}
+
+/// Write every frame received from `frames` with `encoder`, and finish the file when the channel
+/// is closed.
+///
+/// Returns at the first error, which closes the channel for the sender.
+fn write_frames(mut encoder: Encoder, frames: Receiver<FrameToWrite>) -> io::Result<()> {
+    for frame in frames.iter() {
+        encoder.write(frame)?;
+    }
+
+    encoder.finish()
+}
+
+impl Encoder {
+    fn write(&mut self, frame: FrameToWrite) -> io::Result<()> {
+        let FrameToWrite {
+            mut rgba,
+            width,
+            height,
+            delay,
+        } = frame;
+
+        if let Encoder::Unstarted(format, file) = self {
+            let format = *format;
+            let writer = BufWriter::new(file.take().expect("The recording was already started"));
+            *self = match format {
+                RecordingFormat::Gif => Encoder::Gif(start_gif(writer, width, height)?),
+                RecordingFormat::Apng => Encoder::Apng(ApngWriter::new(writer, width, height)?),
+                RecordingFormat::PngSequence => unreachable!(),
+            };
+        }
+
+        match self {
+            Encoder::Unstarted(..) => unreachable!(),
+            Encoder::Gif(gif) => {
+                let mut frame =
+                    gif::Frame::from_rgba_speed(width as u16, height as u16, &mut rgba, 10);
+                frame.delay = (delay.as_millis() / 10).min(u128::from(std::u16::MAX)) as u16;
+
+                gif.write_frame(&frame)
+            }
+            Encoder::Apng(apng) => apng.write_frame(&rgba, delay),
+            Encoder::PngSequence { dir, next } => {
+                let path = dir.join(format!("frame-{:05}.png", next));
+                *next += 1;
+
+                write_png(&path, width, height, &rgba)
+            }
+        }
+    }
+
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Encoder::Apng(apng) => apng.finish(),
+            // The GIF trailer is written when the encoder is dropped
+            Encoder::Gif(gif) => {
+                drop(gif);
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Create a looping GIF encoder.
+fn start_gif<W: Write>(writer: W, width: u32, height: u32) -> io::Result<gif::Encoder<W>> {
+    if width > u32::from(std::u16::MAX) || height > u32::from(std::u16::MAX) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "GIF frames must be at most 65535 pixels wide and tall",
+        ));
+    }
+
+    let mut encoder = gif::Encoder::new(writer, width as u16, height as u16, &[])?;
+    encoder.set(gif::Repeat::Infinite)?;
+
+    Ok(encoder)
+}
+
+impl<W: Write + Seek> ApngWriter<W> {
+    /// Write the PNG header for an animation of frames with the given size.
+    fn new(mut writer: W, width: u32, height: u32) -> io::Result<ApngWriter<W>> {
+        writer.write_all(&PNG_SIGNATURE)?;
+        write_chunk(&mut writer, b"IHDR", &image_header(width, height))?;
+        let animation_control = writer.seek(SeekFrom::Current(0))?;
+        write_chunk(&mut writer, b"acTL", &animation_control_data(0))?;
+
+        Ok(ApngWriter {
+            writer,
+            width,
+            height,
+            frames: 0,
+            sequence: 0,
+            animation_control,
+        })
+    }
+
+    /// Write a frame which is shown for `delay`.
+    fn write_frame(&mut self, rgba: &[u8], delay: Duration) -> io::Result<()> {
+        // Frames replace the entire image, with the delay in milliseconds
+        let delay = delay.as_millis().min(u128::from(std::u16::MAX)) as u16;
+        let mut frame_control = Vec::with_capacity(26);
+        frame_control.extend_from_slice(&self.next_sequence().to_be_bytes());
+        frame_control.extend_from_slice(&self.width.to_be_bytes());
+        frame_control.extend_from_slice(&self.height.to_be_bytes());
+        frame_control.extend_from_slice(&0u32.to_be_bytes());
+        frame_control.extend_from_slice(&0u32.to_be_bytes());
+        frame_control.extend_from_slice(&delay.to_be_bytes());
+        frame_control.extend_from_slice(&1000u16.to_be_bytes());
+        frame_control.extend_from_slice(&[0, 0]);
+        write_chunk(&mut self.writer, b"fcTL", &frame_control)?;
+
+        // The first frame is also the default image for viewers without APNG support
+        let image_data = compress(rgba, self.width);
+        if self.frames == 0 {
+            write_chunk(&mut self.writer, b"IDAT", &image_data)?;
+        } else {
+            let mut frame_data = Vec::with_capacity(4 + image_data.len());
+            frame_data.extend_from_slice(&self.next_sequence().to_be_bytes());
+            frame_data.extend_from_slice(&image_data);
+            write_chunk(&mut self.writer, b"fdAT", &frame_data)?;
+        }
+        self.frames += 1;
+
+        Ok(())
+    }
+
+    /// Write the end of the file, and the final number of frames.
+    fn finish(mut self) -> io::Result<()> {
+        write_chunk(&mut self.writer, b"IEND", &[])?;
+        self.writer.seek(SeekFrom::Start(self.animation_control))?;
+        write_chunk(
+            &mut self.writer,
+            b"acTL",
+            &animation_control_data(self.frames),
+        )?;
+
+        self.writer.flush()
+    }
+
+    fn next_sequence(&mut self) -> u32 {
+        let sequence = self.sequence;
+        self.sequence += 1;
+
+        sequence
+    }
+}
+
+/// Write a single `RGBA` image to `path` as a PNG file.
+fn write_png(path: &Path, width: u32, height: u32, rgba: &[u8]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(&PNG_SIGNATURE)?;
+    write_chunk(&mut writer, b"IHDR", &image_header(width, height))?;
+    write_chunk(&mut writer, b"IDAT", &compress(rgba, width))?;
+    write_chunk(&mut writer, b"IEND", &[])?;
+
+    writer.flush()
+}
+
+/// Write a PNG chunk with its length and checksum.
+fn write_chunk<W: Write>(writer: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    let mut crc = crc32fast::Hasher::new();
+    crc.update(kind);
+    crc.update(data);
+
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(kind)?;
+    writer.write_all(data)?;
+    writer.write_all(&crc.finalize().to_be_bytes())
+}
+
+/// The PNG image header for 8-bit `RGBA` images.
+fn image_header(width: u32, height: u32) -> [u8; 13] {
+    let mut header = [0; 13];
+    header[0..4].copy_from_slice(&width.to_be_bytes());
+    header[4..8].copy_from_slice(&height.to_be_bytes());
+    // Bit depth 8, truecolor with alpha, no interlacing
+    header[8] = 8;
+    header[9] = 6;
+
+    header
+}
+
+/// The APNG animation control data for `frames` frames, looping forever.
+fn animation_control_data(frames: u32) -> [u8; 8] {
+    let mut data = [0; 8];
+    data[0..4].copy_from_slice(&frames.to_be_bytes());
+
+    data
+}
+
+/// Compress `RGBA` pixels to PNG image data, without filtering.
+fn compress(rgba: &[u8], width: u32) -> Vec<u8> {
+    let row_size = width as usize * 4;
+    let mut filtered = Vec::with_capacity(rgba.len() + rgba.len() / row_size);
+    for row in rgba.chunks_exact(row_size) {
+        filtered.push(0);
+        filtered.extend_from_slice(row);
+    }
+
+    deflate::deflate_bytes_zlib(&filtered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Split a PNG file into its chunks, checking the signature and every checksum.
+    fn chunks(png: &[u8]) -> Vec<([u8; 4], Vec<u8>)> {
+        assert_eq!(&png[..8], &PNG_SIGNATURE[..]);
+
+        let mut chunks = Vec::new();
+        let mut rest = &png[8..];
+        while !rest.is_empty() {
+            let len = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+            let kind = [rest[4], rest[5], rest[6], rest[7]];
+            let data = &rest[8..8 + len];
+            let crc = &rest[8 + len..12 + len];
+
+            let mut hasher = crc32fast::Hasher::new();
+            hasher.update(&kind);
+            hasher.update(data);
+            assert_eq!(crc, &hasher.finalize().to_be_bytes()[..]);
+
+            chunks.push((kind, data.to_vec()));
+            rest = &rest[12 + len..];
+        }
+
+        chunks
+    }
+
+    fn be_u32(data: &[u8]) -> u32 {
+        u32::from_be_bytes([data[0], data[1], data[2], data[3]])
+    }
+
+    #[test]
+    fn chunk_crc() {
+        let mut chunk = Vec::new();
+        write_chunk(&mut chunk, b"IEND", &[]).unwrap();
+
+        assert_eq!(
+            chunk,
+            [0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]
+        );
+    }
+
+    #[test]
+    fn apng_sequence_numbers() {
+        let mut file = Cursor::new(Vec::new());
+        let mut apng = ApngWriter::new(&mut file, 2, 1).unwrap();
+        for delay in [10, 20, 30].iter() {
+            apng.write_frame(&[0xff; 8], Duration::from_millis(*delay))
+                .unwrap();
+        }
+        apng.finish().unwrap();
+
+        let chunks = chunks(file.get_ref());
+        let kinds: Vec<&[u8]> = chunks.iter().map(|(kind, _)| &kind[..]).collect();
+        assert_eq!(
+            kinds,
+            [
+                &b"IHDR"[..],
+                b"acTL",
+                b"fcTL",
+                b"IDAT",
+                b"fcTL",
+                b"fdAT",
+                b"fcTL",
+                b"fdAT",
+                b"IEND",
+            ]
+        );
+
+        // Frame control and frame data chunks share one sequence, which the default image skips
+        let sequences: Vec<u32> = chunks
+            .iter()
+            .filter(|(kind, _)| kind == b"fcTL" || kind == b"fdAT")
+            .map(|(_, data)| be_u32(data))
+            .collect();
+        assert_eq!(sequences, [0, 1, 2, 3, 4]);
+
+        let delays: Vec<u16> = chunks
+            .iter()
+            .filter(|(kind, _)| kind == b"fcTL")
+            .map(|(_, data)| u16::from_be_bytes([data[20], data[21]]))
+            .collect();
+        assert_eq!(delays, [10, 20, 30]);
+    }
+
+    #[test]
+    fn apng_frame_count_rewritten() {
+        let mut file = Cursor::new(Vec::new());
+        let mut apng = ApngWriter::new(&mut file, 1, 1).unwrap();
+        apng.write_frame(&[0; 4], Duration::from_millis(100))
+            .unwrap();
+        apng.write_frame(&[0; 4], Duration::from_millis(100))
+            .unwrap();
+        apng.finish().unwrap();
+
+        // The rewritten chunk replaces the placeholder instead of being appended
+        let chunks = chunks(file.get_ref());
+        let animation_controls: Vec<&Vec<u8>> = chunks
+            .iter()
+            .filter(|(kind, _)| kind == b"acTL")
+            .map(|(_, data)| data)
+            .collect();
+        assert_eq!(animation_controls.len(), 1);
+        assert_eq!(be_u32(animation_controls[0]), 2);
+        assert_eq!(be_u32(&animation_controls[0][4..]), 0);
+        assert_eq!(&chunks.last().unwrap().0, b"IEND");
+    }
+}
@@ -0,0 +1,61 @@
+use instant::Instant;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// The seed handed to render passes in deterministic mode.
+const DETERMINISTIC_SEED: u64 = 0x5049_5845_4c53_0001;
+
+/// The time source for animations, interpolation, and render passes.
+///
+/// In deterministic mode the clock only moves when it is advanced explicitly, so renders do not
+/// depend on how long the GPU or the test harness took.
+#[derive(Debug)]
+pub(crate) struct Clock {
+    start: Instant,
+    // The elapsed time when the clock is fixed
+    fixed: Option<Duration>,
+    seed: u64,
+}
+
+impl Clock {
+    pub(crate) fn new(deterministic: bool) -> Clock {
+        let (fixed, seed) = if deterministic {
+            (Some(Duration::from_secs(0)), DETERMINISTIC_SEED)
+        } else {
+            (None, RandomState::new().build_hasher().finish())
+        };
+
+        Clock {
+            start: Instant::now(),
+            fixed,
+            seed,
+        }
+    }
+
+    pub(crate) fn is_fixed(&self) -> bool {
+        self.fixed.is_some()
+    }
+
+    pub(crate) fn now(&self) -> Instant {
+        match self.fixed {
+            Some(elapsed) => self.start + elapsed,
+            None => Instant::now(),
+        }
+    }
+
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.now().saturating_duration_since(self.start)
+    }
+
+    /// Move a fixed clock forward. Has no effect on a realtime clock.
+    pub(crate) fn advance(&mut self, duration: Duration) {
+        if let Some(elapsed) = &mut self.fixed {
+            *elapsed += duration;
+        }
+    }
+
+    pub(crate) fn seed(&self) -> u64 {
+        self.seed
+    }
+}
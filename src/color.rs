@@ -0,0 +1,318 @@
+//! Color conversion utilities.
+//!
+//! The [`Color`] type is the common representation for all APIs in this crate which accept a
+//! color. It stores `sRGB` encoded components in the range `0.0..=1.0`, which is the same color
+//! space as the default [`wgpu::TextureFormat::Rgba8UnormSrgb`] pixel buffer and the values you
+//! get from popular image editing tools. Conversions to linear space are provided for blending
+//! math and for GPU APIs which expect linear values, like [`wgpu::Color`].
+
+/// An `RGBA` color with `sRGB` encoded components in the range `0.0..=1.0`.
+///
+/// # Examples
+///
+/// ```
+/// use pixels::color::Color;
+///
+/// let color = Color::from_rgba8(0x5e, 0x48, 0xe8, 0xff);
+/// assert_eq!(color.to_rgba8(), [0x5e, 0x48, 0xe8, 0xff]);
+///
+/// let red = Color::from_hsv(0.0, 1.0, 1.0, 1.0);
+/// assert_eq!(red.to_rgba8(), [0xff, 0x00, 0x00, 0xff]);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Color {
+    /// Red component.
+    pub r: f32,
+    /// Green component.
+    pub g: f32,
+    /// Blue component.
+    pub b: f32,
+    /// Alpha component. Alpha is always linear.
+    pub a: f32,
+}
+
+/// The color space in which translucent colors are blended. See [`Color::over`].
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum BlendSpace {
+    /// Blend the `sRGB` encoded components directly. This is the naive blend most software
+    /// renderers do, which makes antialiased edges of light shapes on dark backgrounds (and the
+    /// reverse) look too dark and thin.
+    Srgb,
+    /// Convert to linear space, blend, and convert back to `sRGB`. Antialiased edges keep their
+    /// perceived weight at small sizes, matching how the GPU blends into `sRGB` textures.
+    Linear,
+}
+
+impl Color {
+    /// Opaque black.
+    pub const BLACK: Color = Color::new(0.0, 0.0, 0.0, 1.0);
+
+    /// Opaque white.
+    pub const WHITE: Color = Color::new(1.0, 1.0, 1.0, 1.0);
+
+    /// Fully transparent black.
+    pub const TRANSPARENT: Color = Color::new(0.0, 0.0, 0.0, 0.0);
+
+    /// Create a color from `sRGB` encoded components.
+    pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Color {
+        Color { r, g, b, a }
+    }
+
+    /// Create a color from 8-bit `sRGB` encoded components.
+    pub fn from_rgba8(r: u8, g: u8, b: u8, a: u8) -> Color {
+        Color::new(u8_to_f32(r), u8_to_f32(g), u8_to_f32(b), u8_to_f32(a))
+    }
+
+    /// Convert the color to 8-bit `sRGB` encoded components, suitable for writing directly into
+    /// a pixel buffer with the default texture format.
+    pub fn to_rgba8(self) -> [u8; 4] {
+        [
+            f32_to_u8(self.r),
+            f32_to_u8(self.g),
+            f32_to_u8(self.b),
+            f32_to_u8(self.a),
+        ]
+    }
+
+    /// Create a color from linear components.
+    ///
+    /// ```
+    /// use pixels::color::Color;
+    ///
+    /// let gray = Color::from_linear(0.2159, 0.2159, 0.2159, 1.0);
+    /// assert_eq!(gray.to_rgba8(), [0x80, 0x80, 0x80, 0xff]);
+    /// ```
+    pub fn from_linear(r: f32, g: f32, b: f32, a: f32) -> Color {
+        Color::new(linear_to_srgb(r), linear_to_srgb(g), linear_to_srgb(b), a)
+    }
+
+    /// Convert the color to linear components in `RGBA` order.
+    pub fn to_linear(self) -> [f32; 4] {
+        [
+            srgb_to_linear(self.r),
+            srgb_to_linear(self.g),
+            srgb_to_linear(self.b),
+            self.a,
+        ]
+    }
+
+    /// Create a color from hue (in degrees), saturation, and value.
+    ///
+    /// The hue wraps around, so `360.0` and `-360.0` are both equivalent to `0.0`.
+    pub fn from_hsv(h: f32, s: f32, v: f32, a: f32) -> Color {
+        let [r, g, b] = hsv_to_rgb([h, s, v]);
+
+        Color::new(r, g, b, a)
+    }
+
+    /// Convert the color to hue (in degrees), saturation, and value.
+    ///
+    /// ```
+    /// use pixels::color::Color;
+    ///
+    /// let [h, s, v] = Color::new(0.0, 0.5, 0.5, 1.0).to_hsv();
+    /// assert_eq!((h, s, v), (180.0, 1.0, 0.5));
+    /// ```
+    pub fn to_hsv(self) -> [f32; 3] {
+        rgb_to_hsv([self.r, self.g, self.b])
+    }
+
+    /// Composite this color over `background` with its alpha, blending in `space`.
+    ///
+    /// Drawing code which computes the coverage of a pixel, like an antialiased circle or a
+    /// glyph from a font rasterizer, can multiply the coverage into the alpha and use this to
+    /// write the edge pixels.
+    ///
+    /// ```
+    /// use pixels::color::{BlendSpace, Color};
+    ///
+    /// let half_white = Color::new(1.0, 1.0, 1.0, 0.5);
+    ///
+    /// // The naive blend is darker than the perceived midpoint
+    /// let srgb = half_white.over(Color::BLACK, BlendSpace::Srgb);
+    /// assert_eq!(srgb.to_rgba8(), [0x80, 0x80, 0x80, 0xff]);
+    ///
+    /// let linear = half_white.over(Color::BLACK, BlendSpace::Linear);
+    /// assert_eq!(linear.to_rgba8(), [0xbc, 0xbc, 0xbc, 0xff]);
+    /// ```
+    pub fn over(self, background: Color, space: BlendSpace) -> Color {
+        let alpha = self.a + background.a * (1.0 - self.a);
+        if alpha <= 0.0 {
+            return Color::TRANSPARENT;
+        }
+
+        let blend =
+            |src: f32, dst: f32| (src * self.a + dst * background.a * (1.0 - self.a)) / alpha;
+        match space {
+            BlendSpace::Srgb => Color::new(
+                blend(self.r, background.r),
+                blend(self.g, background.g),
+                blend(self.b, background.b),
+                alpha,
+            ),
+            BlendSpace::Linear => {
+                let [sr, sg, sb, _] = self.to_linear();
+                let [dr, dg, db, _] = background.to_linear();
+
+                Color::from_linear(blend(sr, dr), blend(sg, dg), blend(sb, db), alpha)
+            }
+        }
+    }
+}
+
+impl Default for Color {
+    fn default() -> Color {
+        Color::BLACK
+    }
+}
+
+impl From<[u8; 4]> for Color {
+    fn from(rgba: [u8; 4]) -> Color {
+        Color::from_rgba8(rgba[0], rgba[1], rgba[2], rgba[3])
+    }
+}
+
+impl From<Color> for [u8; 4] {
+    fn from(color: Color) -> [u8; 4] {
+        color.to_rgba8()
+    }
+}
+
+/// Colors are converted to linear space, which is what `wgpu` expects for clear colors and
+/// blend constants.
+impl From<Color> for wgpu::Color {
+    fn from(color: Color) -> wgpu::Color {
+        let [r, g, b, a] = color.to_linear();
+
+        wgpu::Color {
+            r: f64::from(r),
+            g: f64::from(g),
+            b: f64::from(b),
+            a: f64::from(a),
+        }
+    }
+}
+
+/// Convert an `sRGB` encoded component to linear space.
+///
+/// ```
+/// use pixels::color::{linear_to_srgb, srgb_to_linear};
+///
+/// assert_eq!(srgb_to_linear(0.0), 0.0);
+/// assert_eq!(srgb_to_linear(1.0), 1.0);
+/// assert!((linear_to_srgb(srgb_to_linear(0.5)) - 0.5).abs() < 1e-6);
+/// ```
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Convert a linear component to `sRGB` encoding.
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Convert an 8-bit component to the range `0.0..=1.0`.
+pub fn u8_to_f32(value: u8) -> f32 {
+    f32::from(value) / 255.0
+}
+
+/// Convert a component in the range `0.0..=1.0` to 8 bits, rounding to the nearest value.
+///
+/// Values outside of the range are clamped.
+///
+/// ```
+/// use pixels::color::f32_to_u8;
+///
+/// assert_eq!(f32_to_u8(0.5), 0x80);
+/// assert_eq!(f32_to_u8(-1.0), 0x00);
+/// assert_eq!(f32_to_u8(2.0), 0xff);
+/// ```
+pub fn f32_to_u8(value: f32) -> u8 {
+    (value.max(0.0).min(1.0) * 255.0).round() as u8
+}
+
+/// Convert `RGB` components to hue (in degrees), saturation, and value.
+pub fn rgb_to_hsv(rgb: [f32; 3]) -> [f32; 3] {
+    let [r, g, b] = rgb;
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+
+    let h = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    let s = if max == 0.0 { 0.0 } else { delta / max };
+
+    [h, s, max]
+}
+
+/// Convert hue (in degrees), saturation, and value to `RGB` components.
+pub fn hsv_to_rgb(hsv: [f32; 3]) -> [f32; 3] {
+    let [h, s, v] = hsv;
+    let h = h.rem_euclid(360.0) / 60.0;
+    let c = v * s;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let m = v - c;
+
+    let (r, g, b) = match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+
+    [r + m, g + m, b + m]
+}
+
+/// Multiply the color components of 8-bit `RGBA` or `BGRA` pixels by their alpha component, in
+/// place.
+///
+/// With `srgb`, the components are multiplied in linear space, which is where the GPU blends
+/// `sRGB` textures. Otherwise they are multiplied as they are stored.
+///
+/// ```
+/// use pixels::color::premultiply_alpha;
+///
+/// let mut pixels = [0xff, 0x80, 0x00, 0x80, 0x12, 0x34, 0x56, 0xff];
+/// premultiply_alpha(&mut pixels, false);
+/// assert_eq!(pixels, [0x80, 0x40, 0x00, 0x80, 0x12, 0x34, 0x56, 0xff]);
+/// ```
+///
+/// # Panics
+///
+/// Panics when the length of `pixels` is not a multiple of 4.
+pub fn premultiply_alpha(pixels: &mut [u8], srgb: bool) {
+    assert_eq!(pixels.len() % 4, 0);
+
+    for pixel in pixels.chunks_exact_mut(4) {
+        let alpha = pixel[3];
+        if alpha == 0xff {
+            continue;
+        }
+
+        for component in pixel[..3].iter_mut() {
+            *component = if srgb {
+                let linear = srgb_to_linear(u8_to_f32(*component)) * u8_to_f32(alpha);
+                f32_to_u8(linear_to_srgb(linear))
+            } else {
+                ((u16::from(*component) * u16::from(alpha) + 127) / 255) as u8
+            };
+        }
+    }
+}
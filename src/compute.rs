@@ -0,0 +1,147 @@
+use std::fmt;
+use wgpu::Extent3d;
+
+use crate::render_pass::{Device, FrameContext, Queue};
+
+/// The boxed compute pass type for dynamic dispatch
+pub type BoxedComputePass = Box<dyn ComputePass>;
+
+pub(crate) type ComputePassFactory =
+    Box<dyn Fn(Device, Queue, &wgpu::Buffer, &Extent3d) -> BoxedComputePass + Send + Sync>;
+
+/// Objects that implement this trait fill the pixel buffer texture on the GPU, instead of the
+/// pixel buffer on the CPU.
+///
+/// Compute passes write to an output buffer which is copied into the texture before any render
+/// pass is executed. The buffer holds tightly packed rows from top to bottom, with pixels in the
+/// texture format: row `y` starts at byte `y * width * bytes_per_pixel`. Compute shaders usually
+/// bind it as a storage buffer of `uint`, with one `RGBA` pixel in each element.
+///
+/// Add compute passes with [`PixelsBuilder::add_compute_pass`].
+///
+/// Compute passes must be `Send`, because they are owned by the pixel buffer, which can be moved
+/// to a render thread.
+///
+/// [`PixelsBuilder::add_compute_pass`]: ./struct.PixelsBuilder.html#method.add_compute_pass
+pub trait ComputePass: Send {
+    /// Called once per frame to encode the compute pass which writes to the output buffer.
+    ///
+    /// # Arguments
+    /// * `encoder` - Command encoder for the compute pass
+    /// * `context` - The frame index and time
+    fn dispatch(&mut self, encoder: &mut wgpu::CommandEncoder, context: &FrameContext);
+
+    /// Called when the output buffer is recreated by [`Pixels::resize_buffer`], so the compute
+    /// pass can bind the new buffer.
+    ///
+    /// # Arguments
+    /// * `output` - The new output buffer
+    /// * `texture_size` - The new size of the pixel buffer texture
+    ///
+    /// [`Pixels::resize_buffer`]: ./struct.Pixels.html#method.resize_buffer
+    fn update_bindings(&mut self, output: &wgpu::Buffer, texture_size: &Extent3d);
+
+    /// This function implements [`Debug`](fmt::Debug) for trait objects.
+    ///
+    /// You are encouraged to override the default impl to provide better debug messages.
+    fn debug(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "dyn ComputePass")
+    }
+}
+
+impl fmt::Debug for dyn ComputePass + 'static {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.debug(f)
+    }
+}
+
+/// The compute passes and the buffer they write to.
+#[derive(Debug)]
+pub(crate) struct ComputeStage {
+    output: wgpu::Buffer,
+    bytes_per_row: u32,
+    passes: Vec<BoxedComputePass>,
+}
+
+impl ComputeStage {
+    pub(crate) fn new(
+        device: &Device,
+        queue: &Queue,
+        factories: &[ComputePassFactory],
+        texture_extent: Extent3d,
+        texture_format_size: u32,
+    ) -> ComputeStage {
+        let (output, bytes_per_row) =
+            create_output_buffer(device, texture_extent, texture_format_size);
+        let passes = factories
+            .iter()
+            .map(|factory| factory(device.clone(), queue.clone(), &output, &texture_extent))
+            .collect();
+
+        ComputeStage {
+            output,
+            bytes_per_row,
+            passes,
+        }
+    }
+
+    /// Recreate the output buffer for a new texture size, and rebind it in every compute pass.
+    pub(crate) fn resize(
+        &mut self,
+        device: &wgpu::Device,
+        texture_extent: Extent3d,
+        texture_format_size: u32,
+    ) {
+        let (output, bytes_per_row) =
+            create_output_buffer(device, texture_extent, texture_format_size);
+        for pass in self.passes.iter_mut() {
+            pass.update_bindings(&output, &texture_extent);
+        }
+        self.output = output;
+        self.bytes_per_row = bytes_per_row;
+    }
+
+    /// Execute all compute passes, and copy the output buffer into `texture`.
+    pub(crate) fn dispatch(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        context: &FrameContext,
+        texture: &wgpu::Texture,
+        texture_extent: Extent3d,
+    ) {
+        for pass in self.passes.iter_mut() {
+            pass.dispatch(encoder, context);
+        }
+
+        encoder.copy_buffer_to_texture(
+            wgpu::BufferCopyView {
+                buffer: &self.output,
+                offset: 0,
+                bytes_per_row: self.bytes_per_row,
+                rows_per_image: texture_extent.height,
+            },
+            wgpu::TextureCopyView {
+                texture,
+                mip_level: 0,
+                array_layer: 0,
+                origin: wgpu::Origin3d { x: 0, y: 0, z: 0 },
+            },
+            texture_extent,
+        );
+    }
+}
+
+fn create_output_buffer(
+    device: &wgpu::Device,
+    texture_extent: Extent3d,
+    texture_format_size: u32,
+) -> (wgpu::Buffer, u32) {
+    let bytes_per_row = texture_extent.width * texture_format_size;
+    let output = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("pixels_compute_output_buffer"),
+        size: u64::from(bytes_per_row * texture_extent.height),
+        usage: wgpu::BufferUsage::STORAGE | wgpu::BufferUsage::COPY_SRC,
+    });
+
+    (output, bytes_per_row)
+}
@@ -0,0 +1,491 @@
+use std::ops::Range;
+
+use crate::include_spv;
+use crate::palette::{Palette, MAX_COLORS};
+use crate::staging::StagingRing;
+
+/// The size of the shader locals: format, width, height, planes, and the range of rows to expand,
+/// padded to 16 bytes.
+const LOCALS_SIZE: u64 = 32;
+
+/// The offset of the range of rows to expand in the shader locals.
+const LOCALS_ROWS_OFFSET: u64 = 16;
+
+/// The layout of the pixel buffer returned by [`Pixels::get_frame`].
+///
+/// All formats other than [`FrameFormat::Texture`] are expanded to `RGBA` by a compute shader as
+/// part of the upload, which moves all format conversion off of the CPU. The indexed formats look
+/// up colors in the palette set with [`Pixels::set_palette`].
+///
+/// [`Pixels::get_frame`]: ./struct.Pixels.html#method.get_frame
+/// [`Pixels::set_palette`]: ./struct.Pixels.html#method.set_palette
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum FrameFormat {
+    /// Pixels are stored in the texture format and uploaded as they are. This is the default.
+    Texture,
+    /// One byte per pixel, each an index into the palette.
+    Indexed8,
+    /// Two bytes per pixel in little-endian order, with 5 bits of red in the most significant
+    /// bits, 6 bits of green, and 5 bits of blue.
+    Rgb565,
+    /// The given number of bitplanes (1 through 8), one after another. Each plane has one bit per
+    /// pixel, with rows padded to a whole number of bytes and the leftmost pixel in the most
+    /// significant bit. The first plane holds the least significant bit of the palette index.
+    Planar(u8),
+}
+
+/// A compute pipeline which expands frames in a [`FrameFormat`] into the `RGBA` texture.
+#[derive(Debug)]
+pub(crate) struct FrameConverter {
+    format: FrameFormat,
+    width: u32,
+    height: u32,
+    palette: Vec<[u8; 4]>,
+    palette_dirty: Option<Range<usize>>,
+    frame_buffer: wgpu::Buffer,
+    frame_buffer_size: u64,
+    palette_buffer: wgpu::Buffer,
+    output_buffer: wgpu::Buffer,
+    locals_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    pipeline: wgpu::ComputePipeline,
+}
+
+impl Default for FrameFormat {
+    fn default() -> FrameFormat {
+        FrameFormat::Texture
+    }
+}
+
+impl FrameFormat {
+    /// The size of a frame in bytes.
+    pub(crate) fn frame_size(self, width: u32, height: u32, texture_format_size: u32) -> usize {
+        let (width, height) = (width as usize, height as usize);
+
+        match self {
+            FrameFormat::Texture => width * height * texture_format_size as usize,
+            FrameFormat::Indexed8 => width * height,
+            FrameFormat::Rgb565 => width * height * 2,
+            FrameFormat::Planar(planes) => planes as usize * ((width + 7) / 8) * height,
+        }
+    }
+
+    /// The number of bytes per pixel, or `None` when pixels are not stored contiguously.
+    pub(crate) fn bytes_per_pixel(self, texture_format_size: u32) -> Option<u32> {
+        match self {
+            FrameFormat::Texture => Some(texture_format_size),
+            FrameFormat::Indexed8 => Some(1),
+            FrameFormat::Rgb565 => Some(2),
+            FrameFormat::Planar(_) => None,
+        }
+    }
+
+    /// Copy `frame` into `output` with the rows of each plane in reverse order.
+    pub(crate) fn flip_rows(self, frame: &[u8], output: &mut [u8], height: u32) {
+        let planes = match self {
+            FrameFormat::Planar(planes) => planes as usize,
+            _ => 1,
+        };
+        let plane_size = frame.len() / planes;
+        let row_size = plane_size / height as usize;
+
+        for (plane, output_plane) in frame
+            .chunks_exact(plane_size)
+            .zip(output.chunks_exact_mut(plane_size))
+        {
+            for (row, output_row) in plane
+                .chunks_exact(row_size)
+                .zip(output_plane.chunks_exact_mut(row_size).rev())
+            {
+                output_row.copy_from_slice(row);
+            }
+        }
+    }
+
+    /// The format identifier used by the compute shader.
+    fn shader_format(self) -> u32 {
+        match self {
+            FrameFormat::Texture => unreachable!(),
+            FrameFormat::Indexed8 => 0,
+            FrameFormat::Rgb565 => 1,
+            FrameFormat::Planar(_) => 2,
+        }
+    }
+}
+
+impl FrameConverter {
+    /// Create a frame converter for `format`.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `format` is [`FrameFormat::Texture`], or a planar format without 1 through 8
+    /// planes.
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        format: FrameFormat,
+        width: u32,
+        height: u32,
+    ) -> FrameConverter {
+        let planes = match format {
+            FrameFormat::Texture => panic!("Texture frames do not need to be converted"),
+            FrameFormat::Planar(planes) => {
+                assert!((1..=8).contains(&planes));
+                u32::from(planes)
+            }
+            _ => 0,
+        };
+
+        let cs_module = device.create_shader_module(include_spv!("../shaders/convert.spv"));
+
+        // The shader reads whole 32-bit words
+        let frame_buffer_size = ((format.frame_size(width, height, 4) + 3) & !3) as u64;
+        let frame_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("pixels_convert_frame_buffer"),
+            size: frame_buffer_size,
+            usage: wgpu::BufferUsage::STORAGE | wgpu::BufferUsage::COPY_DST,
+        });
+        let palette_size = (MAX_COLORS * 4) as u64;
+        let palette_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("pixels_convert_palette_buffer"),
+            size: palette_size,
+            usage: wgpu::BufferUsage::STORAGE | wgpu::BufferUsage::COPY_DST,
+        });
+        let output_size = u64::from(width * height * 4);
+        let output_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("pixels_convert_output_buffer"),
+            size: output_size,
+            usage: wgpu::BufferUsage::STORAGE | wgpu::BufferUsage::COPY_SRC,
+        });
+
+        // The range of rows to expand is written by every conversion
+        let mut locals = Vec::with_capacity(LOCALS_SIZE as usize);
+        for value in [
+            format.shader_format(),
+            width,
+            height,
+            planes,
+            0,
+            height,
+            0,
+            0,
+        ]
+        .iter()
+        {
+            locals.extend_from_slice(&value.to_ne_bytes());
+        }
+        let locals_buffer = device.create_buffer_with_data(
+            &locals,
+            wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+        );
+
+        // Create bind group
+        let storage_entry = |binding, readonly| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStage::COMPUTE,
+            ty: wgpu::BindingType::StorageBuffer {
+                dynamic: false,
+                readonly,
+            },
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("pixels_convert_bind_group_layout"),
+            bindings: &[
+                storage_entry(0, true),
+                storage_entry(1, true),
+                storage_entry(2, false),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStage::COMPUTE,
+                    ty: wgpu::BindingType::UniformBuffer { dynamic: false },
+                },
+            ],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("pixels_convert_bind_group"),
+            layout: &bind_group_layout,
+            bindings: &[
+                wgpu::Binding {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer {
+                        buffer: &frame_buffer,
+                        range: 0..frame_buffer_size,
+                    },
+                },
+                wgpu::Binding {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Buffer {
+                        buffer: &palette_buffer,
+                        range: 0..palette_size,
+                    },
+                },
+                wgpu::Binding {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Buffer {
+                        buffer: &output_buffer,
+                        range: 0..output_size,
+                    },
+                },
+                wgpu::Binding {
+                    binding: 3,
+                    resource: wgpu::BindingResource::Buffer {
+                        buffer: &locals_buffer,
+                        range: 0..LOCALS_SIZE,
+                    },
+                },
+            ],
+        });
+
+        // Create pipeline
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            bind_group_layouts: &[&bind_group_layout],
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            layout: &pipeline_layout,
+            compute_stage: wgpu::ProgrammableStageDescriptor {
+                module: &cs_module,
+                entry_point: "main",
+            },
+        });
+
+        FrameConverter {
+            format,
+            width,
+            height,
+            palette: vec![[0; 4]; MAX_COLORS],
+            palette_dirty: Some(0..MAX_COLORS),
+            frame_buffer,
+            frame_buffer_size,
+            palette_buffer,
+            output_buffer,
+            locals_buffer,
+            bind_group,
+            pipeline,
+        }
+    }
+
+    /// Create a frame converter for a new frame size, keeping the format and palette.
+    pub(crate) fn resized(&self, device: &wgpu::Device, width: u32, height: u32) -> FrameConverter {
+        let mut converter = FrameConverter::new(device, self.format, width, height);
+        converter.set_colors(0, &self.palette);
+
+        converter
+    }
+
+    /// Replace the palette used by the indexed formats. It is uploaded with the next frame.
+    pub(crate) fn set_palette(&mut self, palette: &Palette) {
+        self.set_colors(0, &palette.to_array());
+    }
+
+    /// Replace palette entries beginning at `start`. Only the changed entries are uploaded with
+    /// the next frame.
+    pub(crate) fn set_colors(&mut self, start: usize, colors: &[[u8; 4]]) {
+        let end = start + colors.len();
+        self.palette[start..end].copy_from_slice(colors);
+
+        self.palette_dirty = Some(match self.palette_dirty.take() {
+            Some(dirty) => dirty.start.min(start)..dirty.end.max(end),
+            None => start..end,
+        });
+    }
+
+    /// Encode an upload of the frame rows in `rows` and their expansion into `texture`.
+    ///
+    /// Only those rows are uploaded, expanded, and copied into the texture. The other rows keep
+    /// the colors of the palette they were expanded with, which is what palette changes between
+    /// scanlines rely on.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn convert(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        staging: &mut StagingRing,
+        frame: &[u8],
+        texture: &wgpu::Texture,
+        bottom_up_rows: bool,
+        rows: Range<u32>,
+    ) {
+        if rows.start == rows.end {
+            return;
+        }
+
+        if let Some(dirty) = self.palette_dirty.take() {
+            let mut colors = Vec::with_capacity(dirty.len() * 4);
+            for color in self.palette[dirty.clone()].iter() {
+                colors.extend_from_slice(color);
+            }
+            let staging = device.create_buffer_with_data(&colors, wgpu::BufferUsage::COPY_SRC);
+            encoder.copy_buffer_to_buffer(
+                &staging,
+                0,
+                &self.palette_buffer,
+                dirty.start as u64 * 4,
+                colors.len() as u64,
+            );
+        }
+
+        // The expanded frame is top-down, so bottom-up rows are at the mirrored position
+        let expanded = if bottom_up_rows {
+            self.height - rows.end..self.height - rows.start
+        } else {
+            rows
+        };
+
+        // Copies between buffers must be aligned to 4 bytes, so the rows of each plane are widened
+        // to whole words, which includes a few bytes of the neighboring rows
+        let (planes, plane_size, bytes_per_row) = self.layout();
+        let ranges: Vec<Range<usize>> = (0..planes)
+            .map(|plane| {
+                let start = plane * plane_size + expanded.start as usize * bytes_per_row;
+                let end = plane * plane_size + expanded.end as usize * bytes_per_row;
+                (start & !3)..((end + 3) & !3)
+            })
+            .collect();
+        let size: usize = ranges.iter().map(|range| range.len()).sum();
+
+        let buffer = staging.write(device, size as u64, |data| {
+            let mut offset = 0;
+            for range in ranges.iter() {
+                let output = &mut data[offset..offset + range.len()];
+                self.copy_frame_bytes(frame, range.clone(), bottom_up_rows, output);
+                offset += range.len();
+            }
+        });
+        let mut offset = 0;
+        for range in ranges.iter() {
+            encoder.copy_buffer_to_buffer(
+                buffer,
+                offset,
+                &self.frame_buffer,
+                range.start as u64,
+                range.len() as u64,
+            );
+            offset += range.len() as u64;
+        }
+
+        let mut locals = Vec::with_capacity(8);
+        locals.extend_from_slice(&expanded.start.to_ne_bytes());
+        locals.extend_from_slice(&expanded.end.to_ne_bytes());
+        let locals = device.create_buffer_with_data(&locals, wgpu::BufferUsage::COPY_SRC);
+        encoder.copy_buffer_to_buffer(&locals, 0, &self.locals_buffer, LOCALS_ROWS_OFFSET, 8);
+
+        let row_count = expanded.end - expanded.start;
+        {
+            let mut cpass = encoder.begin_compute_pass();
+            cpass.set_pipeline(&self.pipeline);
+            cpass.set_bind_group(0, &self.bind_group, &[]);
+            cpass.dispatch((self.width + 7) / 8, (row_count + 7) / 8, 1);
+        }
+
+        let bytes_per_row = self.width * 4;
+        encoder.copy_buffer_to_texture(
+            wgpu::BufferCopyView {
+                buffer: &self.output_buffer,
+                offset: u64::from(expanded.start * bytes_per_row),
+                bytes_per_row,
+                rows_per_image: row_count,
+            },
+            wgpu::TextureCopyView {
+                texture,
+                mip_level: 0,
+                array_layer: 0,
+                origin: wgpu::Origin3d {
+                    x: 0,
+                    y: expanded.start,
+                    z: 0,
+                },
+            },
+            wgpu::Extent3d {
+                width: self.width,
+                height: row_count,
+                depth: 1,
+            },
+        );
+    }
+
+    /// The number of planes, and the size of each plane and each row in bytes.
+    fn layout(&self) -> (usize, usize, usize) {
+        let width = self.width as usize;
+        let (planes, bytes_per_row) = match self.format {
+            FrameFormat::Texture => unreachable!(),
+            FrameFormat::Indexed8 => (1, width),
+            FrameFormat::Rgb565 => (1, width * 2),
+            FrameFormat::Planar(planes) => (planes as usize, (width + 7) / 8),
+        };
+
+        (planes, bytes_per_row * self.height as usize, bytes_per_row)
+    }
+
+    /// Copy the bytes in `range` of the frame buffer into `output`, reading them from `frame`
+    /// with the rows of each plane in reverse order when `flip` is set.
+    ///
+    /// Bytes past the end of the frame, which pad the frame buffer to whole words, are zero.
+    fn copy_frame_bytes(&self, frame: &[u8], range: Range<usize>, flip: bool, output: &mut [u8]) {
+        let (planes, plane_size, bytes_per_row) = self.layout();
+        let end = range.end.min(planes * plane_size);
+        let height = self.height as usize;
+
+        let mut offset = range.start;
+        while offset < end {
+            let plane = offset / plane_size;
+            let row = offset % plane_size / bytes_per_row;
+            let column = offset % bytes_per_row;
+            let row = if flip { height - 1 - row } else { row };
+            let source = plane * plane_size + row * bytes_per_row + column;
+
+            let len = (bytes_per_row - column).min(end - offset);
+            output[offset - range.start..][..len].copy_from_slice(&frame[source..source + len]);
+            offset += len;
+        }
+        for byte in output[offset - range.start..].iter_mut() {
+            *byte = 0;
+        }
+    }
+
+    /// Expand `frame` to `RGBA` on the CPU, exactly like the compute shader.
+    ///
+    /// This is only used for debugging features which keep copies of rendered frames.
+    pub(crate) fn expand(&self, frame: &[u8]) -> Vec<u8> {
+        let width = self.width as usize;
+        let height = self.height as usize;
+        let palette = &self.palette;
+
+        let mut pixels = Vec::with_capacity(width * height * 4);
+        for y in 0..height {
+            for x in 0..width {
+                let i = y * width + x;
+                let color = match self.format {
+                    FrameFormat::Texture => unreachable!(),
+                    FrameFormat::Indexed8 => palette[frame[i] as usize],
+                    FrameFormat::Rgb565 => {
+                        let value = u16::from_le_bytes([frame[i * 2], frame[i * 2 + 1]]);
+                        let r = (value >> 11) as u8;
+                        let g = (value >> 5) as u8 & 0x3f;
+                        let b = value as u8 & 0x1f;
+
+                        [
+                            (r << 3) | (r >> 2),
+                            (g << 2) | (g >> 4),
+                            (b << 3) | (b >> 2),
+                            0xff,
+                        ]
+                    }
+                    FrameFormat::Planar(planes) => {
+                        let bytes_per_row = (width + 7) / 8;
+                        let plane_size = bytes_per_row * height;
+                        let offset = y * bytes_per_row + x / 8;
+                        let shift = 7 - (x & 7);
+
+                        let index = (0..planes as usize).fold(0, |index, plane| {
+                            let bit = (frame[plane * plane_size + offset] >> shift) & 1;
+                            index | (bit << plane)
+                        });
+                        palette[index as usize]
+                    }
+                };
+                pixels.extend_from_slice(&color);
+            }
+        }
+
+        pixels
+    }
+}
@@ -0,0 +1,133 @@
+use std::io;
+use std::panic;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard, TryLockError};
+
+use crate::history::{write_pam, FrameHistory};
+
+/// A handle for writing the last rendered frame to disk when the application crashes or exits.
+///
+/// Created by [`Pixels::install_crash_dump`]. The handle is cheap to clone and can be sent to
+/// other threads, e.g. a signal handler or the shutdown path of your application.
+///
+/// [`Pixels::install_crash_dump`]: ./struct.Pixels.html#method.install_crash_dump
+#[derive(Clone, Debug)]
+pub struct CrashDump {
+    state: Arc<Mutex<CrashState>>,
+}
+
+#[derive(Debug)]
+struct CrashState {
+    dir: PathBuf,
+    width: u32,
+    height: u32,
+    texture_format_size: u32,
+    frame: Vec<u8>,
+    history: Option<Arc<Mutex<FrameHistory>>>,
+}
+
+impl CrashDump {
+    /// Create a crash dump handle and register it as a panic hook.
+    ///
+    /// The previously registered panic hook is called after the frame has been written.
+    pub(crate) fn install(
+        dir: PathBuf,
+        width: u32,
+        height: u32,
+        texture_format_size: u32,
+        history: Option<Arc<Mutex<FrameHistory>>>,
+    ) -> CrashDump {
+        let crash_dump = CrashDump {
+            state: Arc::new(Mutex::new(CrashState {
+                dir,
+                width,
+                height,
+                texture_format_size,
+                frame: Vec::new(),
+                history,
+            })),
+        };
+
+        let hook = crash_dump.clone();
+        let previous_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            // There is nothing useful to do with an error while panicking
+            let _ = hook.dump();
+            previous_hook(info);
+        }));
+
+        crash_dump
+    }
+
+    /// Record the frame that was just rendered.
+    pub(crate) fn update(&self, frame: &[u8]) {
+        let mut state = lock(&self.state);
+        state.frame.clear();
+        state.frame.extend_from_slice(frame);
+    }
+
+    /// Update the frame size after the pixel buffer is resized. The last rendered frame is
+    /// discarded, because it no longer has the right size.
+    pub(crate) fn resize(&self, width: u32, height: u32) {
+        let mut state = lock(&self.state);
+        state.width = width;
+        state.height = height;
+        state.frame.clear();
+    }
+
+    /// Write the last rendered frame (and the frame history, if requested) to disk.
+    ///
+    /// The final frame is written to `final-frame.pam` and the frame history to the `history`
+    /// subdirectory. Nothing is written if no frame has been rendered yet.
+    ///
+    /// This never blocks; if the state is in use by the render thread, it is skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the directory or any of the files cannot be written.
+    pub fn dump(&self) -> io::Result<()> {
+        let state = match try_lock(&self.state) {
+            Some(state) => state,
+            None => return Ok(()),
+        };
+        if state.frame.is_empty() {
+            return Ok(());
+        }
+
+        std::fs::create_dir_all(&state.dir)?;
+        write_pam(
+            &state.dir.join("final-frame.pam"),
+            &state.frame,
+            state.width,
+            state.height,
+            state.texture_format_size,
+        )?;
+
+        if let Some(history) = state.history.as_ref().and_then(|h| try_lock(h)) {
+            history.dump(
+                &state.dir.join("history"),
+                state.width,
+                state.height,
+                state.texture_format_size,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Lock a mutex, ignoring poisoning. A poisoned frame buffer is still worth looking at.
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Like [`lock`], but gives up instead of blocking.
+fn try_lock<T>(mutex: &Mutex<T>) -> Option<MutexGuard<'_, T>> {
+    match mutex.try_lock() {
+        Ok(guard) => Some(guard),
+        Err(TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner()),
+        Err(TryLockError::WouldBlock) => None,
+    }
+}
@@ -0,0 +1,340 @@
+//! Render passes for debugging applications built on `pixels`.
+
+use std::fmt;
+use wgpu::{Extent3d, TextureView};
+
+use crate::bindings;
+use crate::color::Color;
+use crate::include_spv;
+use crate::pipeline::{create_pipeline, Blending};
+use crate::render_pass::{
+    BoxedRenderPass, Device, InputSampler, PassInputs, Queue, RenderPass, SurfaceResize,
+};
+use crate::renderers::ScalingMatrix;
+
+/// A render pass that highlights pixels which changed since the previous frame.
+///
+/// The highlight is blended over the output of the previous render passes, so this pass should be
+/// added last. It is a useful tool for finding unnecessary redraws and verifying dirty rectangle
+/// logic in emulators and games.
+///
+/// The previous frame is compared exactly, so the input texture is expected to have 8 bits per
+/// channel, like the default [`wgpu::TextureFormat::Rgba8UnormSrgb`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use pixels::color::Color;
+/// use pixels::debug::FrameDiffPass;
+/// use pixels::PixelsBuilder;
+///
+/// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+/// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+/// let mut pixels = PixelsBuilder::new(320, 240, surface_texture)
+///     .add_render_pass(FrameDiffPass::with_highlight(Color::new(0.0, 1.0, 0.0, 0.5)))
+///     .build()?;
+/// # Ok::<(), pixels::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct FrameDiffPass {
+    resources: DiffResources,
+    bindings: DiffBindings,
+    diff_pipeline: wgpu::RenderPipeline,
+    copy_pipeline: wgpu::RenderPipeline,
+}
+
+/// The resources of a frame diff pass which do not depend on the input texture.
+#[derive(Debug)]
+struct DiffResources {
+    device: Device,
+    sampler: wgpu::Sampler,
+    transform_buffer: wgpu::Buffer,
+    copy_transform_buffer: wgpu::Buffer,
+    highlight_buffer: wgpu::Buffer,
+    copy_bind_group_layout: wgpu::BindGroupLayout,
+    diff_bind_group_layout: wgpu::BindGroupLayout,
+}
+
+/// The previous frame and the bind groups which sample the input texture, recreated whenever the
+/// input texture changes.
+#[derive(Debug)]
+struct DiffBindings {
+    previous_view: TextureView,
+    copy_bind_group: wgpu::BindGroup,
+    diff_bind_group: wgpu::BindGroup,
+}
+
+impl FrameDiffPass {
+    /// The default highlight color, a translucent magenta.
+    pub const DEFAULT_HIGHLIGHT: Color = Color::new(1.0, 0.0, 1.0, 0.75);
+
+    /// Factory function for generating a frame diff pass with the default highlight color.
+    pub fn factory(device: Device, queue: Queue, inputs: &PassInputs<'_>) -> BoxedRenderPass {
+        Box::new(FrameDiffPass::new(
+            device,
+            queue,
+            inputs,
+            FrameDiffPass::DEFAULT_HIGHLIGHT,
+        ))
+    }
+
+    /// Create a factory function for generating a frame diff pass with a custom highlight color.
+    ///
+    /// The alpha component of `highlight` controls the opacity of the highlight.
+    pub fn with_highlight(
+        highlight: Color,
+    ) -> impl Fn(Device, Queue, &PassInputs<'_>) -> BoxedRenderPass + 'static {
+        move |device, queue, inputs| Box::new(FrameDiffPass::new(device, queue, inputs, highlight))
+    }
+
+    fn new(
+        device: Device,
+        _queue: Queue,
+        inputs: &PassInputs<'_>,
+        highlight: Color,
+    ) -> FrameDiffPass {
+        let texture_size = inputs.texture_size;
+        let vs_module = device.create_shader_module(include_spv!("../shaders/vert.spv"));
+        let fs_copy_module = device.create_shader_module(include_spv!("../shaders/frag.spv"));
+        let fs_diff_module = device.create_shader_module(include_spv!("../shaders/diff.spv"));
+
+        // Create a texture sampler with nearest neighbor
+        let sampler = InputSampler::default().create(&device);
+
+        // Create uniform buffers
+        let size = (texture_size.width as f32, texture_size.height as f32);
+        let matrix = ScalingMatrix::new(size, size);
+        let transform_buffer = device.create_buffer_with_data(
+            matrix.as_bytes(),
+            wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+        );
+        let copy_transform_buffer =
+            device.create_buffer_with_data(matrix.as_bytes(), wgpu::BufferUsage::UNIFORM);
+        let highlight_bytes: Vec<u8> = highlight
+            .to_linear()
+            .iter()
+            .flat_map(|component| component.to_ne_bytes().to_vec())
+            .collect();
+        let highlight_buffer =
+            device.create_buffer_with_data(&highlight_bytes, wgpu::BufferUsage::UNIFORM);
+
+        // Create bind group layouts
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStage::FRAGMENT,
+            ty: wgpu::BindingType::SampledTexture {
+                component_type: wgpu::TextureComponentType::Float,
+                multisampled: false,
+                dimension: wgpu::TextureViewDimension::D2,
+            },
+        };
+        let sampler_entry = || wgpu::BindGroupLayoutEntry {
+            binding: bindings::SAMPLER,
+            visibility: wgpu::ShaderStage::FRAGMENT,
+            ty: wgpu::BindingType::Sampler { comparison: false },
+        };
+        let uniform_entry = |binding, visibility| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility,
+            ty: wgpu::BindingType::UniformBuffer { dynamic: false },
+        };
+        let copy_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("pixels_frame_diff_copy_layout"),
+                bindings: &[
+                    texture_entry(bindings::TEXTURE),
+                    sampler_entry(),
+                    uniform_entry(bindings::TRANSFORM, wgpu::ShaderStage::VERTEX),
+                ],
+            });
+        let diff_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("pixels_frame_diff_layout"),
+                bindings: &[
+                    texture_entry(bindings::TEXTURE),
+                    sampler_entry(),
+                    uniform_entry(bindings::TRANSFORM, wgpu::ShaderStage::VERTEX),
+                    texture_entry(3),
+                    uniform_entry(4, wgpu::ShaderStage::FRAGMENT),
+                ],
+            });
+
+        // Create pipelines
+        let copy_pipeline = create_pipeline(
+            &device,
+            &copy_bind_group_layout,
+            &vs_module,
+            &fs_copy_module,
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+            Blending::Replace,
+        );
+        let diff_pipeline = create_pipeline(
+            &device,
+            &diff_bind_group_layout,
+            &vs_module,
+            &fs_diff_module,
+            inputs.surface_format,
+            Blending::Alpha,
+        );
+
+        let resources = DiffResources {
+            device,
+            sampler,
+            transform_buffer,
+            copy_transform_buffer,
+            highlight_buffer,
+            copy_bind_group_layout,
+            diff_bind_group_layout,
+        };
+        let bindings = resources.bind(inputs.texture, texture_size);
+
+        FrameDiffPass {
+            resources,
+            bindings,
+            diff_pipeline,
+            copy_pipeline,
+        }
+    }
+}
+
+impl DiffResources {
+    /// Create a previous frame with the size of the input texture, and bind both textures.
+    fn bind(&self, texture_view: &TextureView, texture_size: &Extent3d) -> DiffBindings {
+        let device = &self.device;
+
+        // Create a texture to hold the previous frame
+        let previous_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("pixels_frame_diff_previous"),
+            size: *texture_size,
+            array_layer_count: 1,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+        });
+        let previous_view = previous_texture.create_default_view();
+
+        // Create bind groups
+        let copy_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("pixels_frame_diff_copy_bind_group"),
+            layout: &self.copy_bind_group_layout,
+            bindings: &[
+                wgpu::Binding {
+                    binding: bindings::TEXTURE,
+                    resource: wgpu::BindingResource::TextureView(texture_view),
+                },
+                wgpu::Binding {
+                    binding: bindings::SAMPLER,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+                wgpu::Binding {
+                    binding: bindings::TRANSFORM,
+                    resource: wgpu::BindingResource::Buffer {
+                        buffer: &self.copy_transform_buffer,
+                        range: 0..bindings::TRANSFORM_SIZE,
+                    },
+                },
+            ],
+        });
+        let diff_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("pixels_frame_diff_bind_group"),
+            layout: &self.diff_bind_group_layout,
+            bindings: &[
+                wgpu::Binding {
+                    binding: bindings::TEXTURE,
+                    resource: wgpu::BindingResource::TextureView(texture_view),
+                },
+                wgpu::Binding {
+                    binding: bindings::SAMPLER,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+                wgpu::Binding {
+                    binding: bindings::TRANSFORM,
+                    resource: wgpu::BindingResource::Buffer {
+                        buffer: &self.transform_buffer,
+                        range: 0..bindings::TRANSFORM_SIZE,
+                    },
+                },
+                wgpu::Binding {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(&previous_view),
+                },
+                wgpu::Binding {
+                    binding: 4,
+                    resource: wgpu::BindingResource::Buffer {
+                        buffer: &self.highlight_buffer,
+                        range: 0..16,
+                    },
+                },
+            ],
+        });
+
+        DiffBindings {
+            previous_view,
+            copy_bind_group,
+            diff_bind_group,
+        }
+    }
+}
+
+impl RenderPass for FrameDiffPass {
+    fn render(&self, encoder: &mut wgpu::CommandEncoder, render_target: &TextureView) {
+        // Highlight the differences on top of the render target
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                    attachment: render_target,
+                    resolve_target: None,
+                    load_op: wgpu::LoadOp::Load,
+                    store_op: wgpu::StoreOp::Store,
+                    clear_color: wgpu::Color::TRANSPARENT,
+                }],
+                depth_stencil_attachment: None,
+            });
+            rpass.set_pipeline(&self.diff_pipeline);
+            rpass.set_bind_group(0, &self.bindings.diff_bind_group, &[]);
+            rpass.draw(0..6, 0..1);
+        }
+
+        // Keep a copy of this frame for the next comparison
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                attachment: &self.bindings.previous_view,
+                resolve_target: None,
+                load_op: wgpu::LoadOp::Clear,
+                store_op: wgpu::StoreOp::Store,
+                clear_color: wgpu::Color::TRANSPARENT,
+            }],
+            depth_stencil_attachment: None,
+        });
+        rpass.set_pipeline(&self.copy_pipeline);
+        rpass.set_bind_group(0, &self.bindings.copy_bind_group, &[]);
+        rpass.draw(0..6, 0..1);
+    }
+
+    fn surface_resized(&mut self, encoder: &mut wgpu::CommandEncoder, resize: &SurfaceResize) {
+        // Highlight the pixels where the default scaling pass draws them
+        let (width, height) = resize.new_size;
+        let matrix = ScalingMatrix::from_destination(
+            (width as f32, height as f32),
+            resize.scale,
+            resize.destination,
+        );
+        let temp_buf = self
+            .resources
+            .device
+            .create_buffer_with_data(matrix.as_bytes(), wgpu::BufferUsage::COPY_SRC);
+        encoder.copy_buffer_to_buffer(&temp_buf, 0, &self.resources.transform_buffer, 0, 64);
+    }
+
+    // The previous frame is recreated with the new size, so the first frame after a change to the
+    // input texture highlights every pixel that is not transparent black.
+    fn update_bindings(&mut self, input_texture: &TextureView, input_texture_size: &Extent3d) {
+        self.bindings = self.resources.bind(input_texture, input_texture_size);
+    }
+
+    fn debug(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
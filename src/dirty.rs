@@ -0,0 +1,200 @@
+use std::ops::Range;
+
+/// The band of rows in the pixel buffer which have changed since the last upload.
+#[derive(Debug)]
+pub(crate) struct DirtyRows {
+    rows: Option<Range<u32>>,
+}
+
+/// A view of the pixel buffer which records the rows written to.
+///
+/// Created by [`Pixels::get_tracked_frame`]. When all writes go through this wrapper,
+/// [`Pixels::render`] only uploads the band of rows which actually changed, instead of the entire
+/// pixel buffer.
+///
+/// [`Pixels::get_tracked_frame`]: ./struct.Pixels.html#method.get_tracked_frame
+/// [`Pixels::render`]: ./struct.Pixels.html#method.render
+#[derive(Debug)]
+pub struct TrackedFrame<'a> {
+    pixels: &'a mut [u8],
+    dirty: &'a mut DirtyRows,
+    width: u32,
+    height: u32,
+    bytes_per_pixel: u32,
+}
+
+impl DirtyRows {
+    /// Create a dirty row tracker with all `height` rows marked dirty.
+    pub(crate) fn new(height: u32) -> DirtyRows {
+        DirtyRows {
+            rows: Some(0..height),
+        }
+    }
+
+    /// Mark the rows in `rows` as dirty.
+    pub(crate) fn mark(&mut self, rows: Range<u32>) {
+        if rows.start >= rows.end {
+            return;
+        }
+
+        self.rows = Some(match self.rows.take() {
+            Some(dirty) => dirty.start.min(rows.start)..dirty.end.max(rows.end),
+            None => rows,
+        });
+    }
+
+    /// Returns `true` when any rows are dirty.
+    pub(crate) fn is_dirty(&self) -> bool {
+        self.rows.is_some()
+    }
+
+    /// Take the dirty rows, leaving all rows clean.
+    pub(crate) fn take(&mut self) -> Option<Range<u32>> {
+        self.rows.take()
+    }
+}
+
+impl<'a> TrackedFrame<'a> {
+    pub(crate) fn new(
+        pixels: &'a mut [u8],
+        dirty: &'a mut DirtyRows,
+        width: u32,
+        height: u32,
+        bytes_per_pixel: u32,
+    ) -> TrackedFrame<'a> {
+        TrackedFrame {
+            pixels,
+            dirty,
+            width,
+            height,
+            bytes_per_pixel,
+        }
+    }
+
+    /// The width of the pixel buffer in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of the pixel buffer in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Get a read-only byte slice for the entire pixel buffer. This does not mark anything dirty.
+    pub fn as_slice(&self) -> &[u8] {
+        self.pixels
+    }
+
+    /// Get the bytes for the pixel at `(x, y)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the position is outside of the pixel buffer.
+    pub fn pixel(&self, x: u32, y: u32) -> &[u8] {
+        let range = self.pixel_range(x, y);
+
+        &self.pixels[range]
+    }
+
+    /// Set the bytes for the pixel at `(x, y)` and mark its row dirty.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the position is outside of the pixel buffer, or when the length of `pixel`
+    /// does not match the texture format size.
+    pub fn set_pixel(&mut self, x: u32, y: u32, pixel: &[u8]) {
+        let range = self.pixel_range(x, y);
+        self.dirty.mark(y..y + 1);
+
+        self.pixels[range].copy_from_slice(pixel);
+    }
+
+    /// Get a mutable byte slice for row `y` and mark it dirty.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `y` is outside of the pixel buffer.
+    pub fn row_mut(&mut self, y: u32) -> &mut [u8] {
+        self.rows_mut(y..y + 1)
+    }
+
+    /// Get a mutable byte slice for a contiguous band of rows and mark them all dirty.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `rows` extends outside of the pixel buffer.
+    pub fn rows_mut(&mut self, rows: Range<u32>) -> &mut [u8] {
+        assert!(rows.start <= rows.end && rows.end <= self.height);
+
+        let bytes_per_row = (self.width * self.bytes_per_pixel) as usize;
+        let start = rows.start as usize * bytes_per_row;
+        let end = rows.end as usize * bytes_per_row;
+        self.dirty.mark(rows);
+
+        &mut self.pixels[start..end]
+    }
+
+    /// Fill the entire pixel buffer with `pixel` and mark all rows dirty.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the length of `pixel` does not match the texture format size.
+    pub fn fill(&mut self, pixel: &[u8]) {
+        assert_eq!(pixel.len(), self.bytes_per_pixel as usize);
+
+        let height = self.height;
+        for chunk in self.rows_mut(0..height).chunks_exact_mut(pixel.len()) {
+            chunk.copy_from_slice(pixel);
+        }
+    }
+
+    fn pixel_range(&self, x: u32, y: u32) -> Range<usize> {
+        assert!(x < self.width && y < self.height);
+
+        let start = ((y * self.width + x) * self.bytes_per_pixel) as usize;
+
+        start..start + self.bytes_per_pixel as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starts_dirty() {
+        let mut dirty = DirtyRows::new(240);
+
+        assert!(dirty.is_dirty());
+        assert_eq!(dirty.take(), Some(0..240));
+        assert!(!dirty.is_dirty());
+        assert_eq!(dirty.take(), None);
+    }
+
+    #[test]
+    fn marks_one_band() {
+        let mut dirty = DirtyRows::new(240);
+        dirty.take();
+
+        dirty.mark(10..20);
+        dirty.mark(100..101);
+        dirty.mark(15..30);
+
+        // Rows between the marked ones are included
+        assert_eq!(dirty.take(), Some(10..101));
+    }
+
+    #[test]
+    fn empty_ranges_are_ignored() {
+        let mut dirty = DirtyRows::new(240);
+        dirty.take();
+
+        dirty.mark(20..20);
+        assert!(!dirty.is_dirty());
+
+        dirty.mark(5..6);
+        dirty.mark(50..40);
+        assert_eq!(dirty.take(), Some(5..6));
+    }
+}
@@ -0,0 +1,659 @@
+//! Built-in render passes for stylizing the pixel buffer.
+//!
+//! This module is only available with the `effects` feature.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use wgpu::{Extent3d, TextureView};
+
+use crate::bindings;
+use crate::crash::lock;
+use crate::include_spv;
+use crate::pipeline::{create_pipeline, Blending};
+use crate::render_pass::{
+    BoxedRenderPass, Device, FrameContext, InputSampler, PassInputs, Queue, RenderPass,
+    SurfaceResize,
+};
+use crate::renderers::ScalingMatrix;
+use crate::Rect;
+
+/// Configuration for a [`CrtPass`].
+///
+/// All values are in the range `0.0..=1.0`, where `0.0` disables the effect.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CrtSettings {
+    /// How much the gaps between rows of pixels are darkened.
+    pub scanline_intensity: f32,
+    /// How far the image bulges outward, like the glass of a picture tube.
+    pub curvature: f32,
+    /// How strongly the aperture grille mask filters each column of the surface down to a single
+    /// color channel.
+    pub mask_strength: f32,
+}
+
+impl Default for CrtSettings {
+    fn default() -> CrtSettings {
+        CrtSettings {
+            scanline_intensity: 0.5,
+            curvature: 0.05,
+            mask_strength: 0.25,
+        }
+    }
+}
+
+/// A render pass that draws the pixel buffer like a CRT display, with scanlines, screen
+/// curvature, and an aperture grille mask.
+///
+/// The pass draws the pixel buffer itself, scaled like the default scaling pass, and replaces
+/// everything drawn by earlier render passes. It should be added before any passes which draw
+/// over the image.
+///
+/// The curvature is taken into account by [`Pixels::window_pos_to_pixel`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use pixels::effects::{CrtPass, CrtSettings};
+/// use pixels::PixelsBuilder;
+///
+/// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+/// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+/// let settings = CrtSettings {
+///     scanline_intensity: 0.75,
+///     ..CrtSettings::default()
+/// };
+/// let mut pixels = PixelsBuilder::new(320, 240, surface_texture)
+///     .add_render_pass(CrtPass::with_settings(settings))
+///     .build()?;
+/// # Ok::<(), pixels::Error>(())
+/// ```
+///
+/// [`Pixels::window_pos_to_pixel`]: ../struct.Pixels.html#method.window_pos_to_pixel
+#[derive(Debug)]
+pub struct CrtPass {
+    settings: CrtSettings,
+    effect: FullscreenEffect,
+}
+
+impl CrtPass {
+    /// Factory function for generating a CRT pass with the default settings.
+    pub fn factory(device: Device, queue: Queue, inputs: &PassInputs<'_>) -> BoxedRenderPass {
+        Box::new(CrtPass::new(device, queue, inputs, CrtSettings::default()))
+    }
+
+    /// Create a factory function for generating a CRT pass with custom settings.
+    pub fn with_settings(
+        settings: CrtSettings,
+    ) -> impl Fn(Device, Queue, &PassInputs<'_>) -> BoxedRenderPass + 'static {
+        move |device, queue, inputs| Box::new(CrtPass::new(device, queue, inputs, settings))
+    }
+
+    fn new(
+        device: Device,
+        _queue: Queue,
+        inputs: &PassInputs<'_>,
+        settings: CrtSettings,
+    ) -> CrtPass {
+        let fs_module = device.create_shader_module(include_spv!("../shaders/crt.spv"));
+        let effect = FullscreenEffect::new(
+            device,
+            inputs,
+            &fs_module,
+            "pixels_crt",
+            &crt_locals(settings, inputs.texture_size),
+        );
+
+        CrtPass { settings, effect }
+    }
+}
+
+impl RenderPass for CrtPass {
+    fn render(&self, encoder: &mut wgpu::CommandEncoder, render_target: &TextureView) {
+        self.effect.render(encoder, render_target);
+    }
+
+    fn surface_resized(&mut self, encoder: &mut wgpu::CommandEncoder, resize: &SurfaceResize) {
+        self.effect.surface_resized(encoder, resize);
+    }
+
+    fn update_bindings(&mut self, input_texture: &TextureView, input_texture_size: &Extent3d) {
+        self.effect.update_bindings(
+            input_texture,
+            &crt_locals(self.settings, input_texture_size),
+        );
+    }
+
+    // Apply the same curvature as the shader. The shader bends the sample position rather than
+    // the geometry, so the forward mapping finds the undistorted position.
+    fn unwarp_position(&self, position: (f32, f32)) -> (f32, f32) {
+        let Rect {
+            x,
+            y,
+            width,
+            height,
+        } = self.effect.destination;
+        if width <= 0.0 || height <= 0.0 {
+            return position;
+        }
+
+        let cx = (position.0 - x) / width * 2.0 - 1.0;
+        let cy = (position.1 - y) / height * 2.0 - 1.0;
+        let curvature = self.settings.curvature;
+        let bx = cx * (1.0 + curvature * cy * cy);
+        let by = cy * (1.0 + curvature * cx * cx);
+
+        ((bx + 1.0) / 2.0 * width + x, (by + 1.0) / 2.0 * height + y)
+    }
+
+    fn debug(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Configuration for an [`OutlinePass`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct OutlineSettings {
+    /// The outline color as linear RGBA. The alpha component is the opacity of the outline.
+    pub color: [f32; 4],
+    /// The outline width in surface pixels. Outlines stay the same width at any scale.
+    pub width: f32,
+    /// How far apart two neighboring pixels must be to draw an outline between them, measured as
+    /// the distance between their RGBA colors. Values are in the range `0.0..=2.0`, where `0.0`
+    /// outlines every pixel.
+    pub threshold: f32,
+}
+
+impl Default for OutlineSettings {
+    fn default() -> OutlineSettings {
+        OutlineSettings {
+            color: [0.0, 0.0, 0.0, 1.0],
+            width: 1.0,
+            threshold: 0.1,
+        }
+    }
+}
+
+/// A render pass that draws outlines along the edges between differing pixels.
+///
+/// Edges are detected in the pixel buffer, but the outlines are drawn at the resolution of the
+/// surface, so they can be thinner than a single scaled pixel. This gives pixel art a crisp
+/// contour without changing the pixel buffer.
+///
+/// Like [`CrtPass`], the pass draws the pixel buffer itself, scaled like the default scaling
+/// pass, and replaces everything drawn by earlier render passes.
+///
+/// # Examples
+///
+/// ```no_run
+/// use pixels::effects::{OutlinePass, OutlineSettings};
+/// use pixels::PixelsBuilder;
+///
+/// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+/// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+/// let settings = OutlineSettings {
+///     color: [1.0, 1.0, 1.0, 0.5],
+///     width: 2.0,
+///     ..OutlineSettings::default()
+/// };
+/// let mut pixels = PixelsBuilder::new(320, 240, surface_texture)
+///     .add_render_pass(OutlinePass::with_settings(settings))
+///     .build()?;
+/// # Ok::<(), pixels::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct OutlinePass {
+    settings: OutlineSettings,
+    effect: FullscreenEffect,
+}
+
+impl OutlinePass {
+    /// Factory function for generating an outline pass with the default settings.
+    pub fn factory(device: Device, queue: Queue, inputs: &PassInputs<'_>) -> BoxedRenderPass {
+        Box::new(OutlinePass::new(
+            device,
+            queue,
+            inputs,
+            OutlineSettings::default(),
+        ))
+    }
+
+    /// Create a factory function for generating an outline pass with custom settings.
+    pub fn with_settings(
+        settings: OutlineSettings,
+    ) -> impl Fn(Device, Queue, &PassInputs<'_>) -> BoxedRenderPass + 'static {
+        move |device, queue, inputs| Box::new(OutlinePass::new(device, queue, inputs, settings))
+    }
+
+    fn new(
+        device: Device,
+        _queue: Queue,
+        inputs: &PassInputs<'_>,
+        settings: OutlineSettings,
+    ) -> OutlinePass {
+        let fs_module = device.create_shader_module(include_spv!("../shaders/outline.spv"));
+        let effect = FullscreenEffect::new(
+            device,
+            inputs,
+            &fs_module,
+            "pixels_outline",
+            &outline_locals(settings, inputs.texture_size),
+        );
+
+        OutlinePass { settings, effect }
+    }
+}
+
+impl RenderPass for OutlinePass {
+    fn render(&self, encoder: &mut wgpu::CommandEncoder, render_target: &TextureView) {
+        self.effect.render(encoder, render_target);
+    }
+
+    fn surface_resized(&mut self, encoder: &mut wgpu::CommandEncoder, resize: &SurfaceResize) {
+        self.effect.surface_resized(encoder, resize);
+    }
+
+    fn update_bindings(&mut self, input_texture: &TextureView, input_texture_size: &Extent3d) {
+        self.effect.update_bindings(
+            input_texture,
+            &outline_locals(self.settings, input_texture_size),
+        );
+    }
+
+    fn debug(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// The strength of a [`FilterPass`], which can be changed at any time, e.g. from a settings
+/// menu.
+///
+/// The strength is in the range `-1.0..=1.0`. Negative values blur the image, positive values
+/// sharpen it, and `0.0` leaves it unchanged. Values outside of the range are clamped.
+///
+/// Handles are cheap to clone and can be sent to other threads. Changes are applied in the next
+/// call to [`Pixels::render`].
+///
+/// [`Pixels::render`]: ../struct.Pixels.html#method.render
+#[derive(Clone, Debug, Default)]
+pub struct FilterStrength {
+    strength: Arc<Mutex<f32>>,
+}
+
+impl FilterStrength {
+    /// Create a handle with the given strength.
+    pub fn new(strength: f32) -> FilterStrength {
+        let handle = FilterStrength::default();
+        handle.set(strength);
+
+        handle
+    }
+
+    /// Change the strength.
+    pub fn set(&self, strength: f32) {
+        *lock(&self.strength) = strength.max(-1.0).min(1.0);
+    }
+
+    /// The current strength.
+    pub fn get(&self) -> f32 {
+        *lock(&self.strength)
+    }
+}
+
+/// A render pass that sharpens or blurs the scaled pixel buffer with a single strength
+/// parameter.
+///
+/// The filter works at the resolution of the surface, so blurring softens the edges of scaled
+/// pixels without smearing the pixels themselves. Sharpening counteracts the softening applied
+/// by displays which rescale the image, like many TVs.
+///
+/// Like [`CrtPass`], the pass draws the pixel buffer itself, scaled like the default scaling
+/// pass, and replaces everything drawn by earlier render passes.
+///
+/// # Examples
+///
+/// ```no_run
+/// use pixels::effects::{FilterPass, FilterStrength};
+/// use pixels::PixelsBuilder;
+///
+/// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+/// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+/// let strength = FilterStrength::new(0.5);
+/// let mut pixels = PixelsBuilder::new(320, 240, surface_texture)
+///     .add_render_pass(FilterPass::with_strength(&strength))
+///     .build()?;
+///
+/// // ... Later, when the player moves the slider in the settings menu
+/// strength.set(-0.25);
+/// # Ok::<(), pixels::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct FilterPass {
+    strength: FilterStrength,
+    // The strength in the locals buffer
+    applied: f32,
+    effect: FullscreenEffect,
+}
+
+impl FilterPass {
+    /// Create a factory function for generating a filter pass which follows the strength of
+    /// the given handle.
+    pub fn with_strength(
+        strength: &FilterStrength,
+    ) -> impl Fn(Device, Queue, &PassInputs<'_>) -> BoxedRenderPass + 'static {
+        let strength = strength.clone();
+
+        move |device, queue, inputs| {
+            Box::new(FilterPass::new(device, queue, inputs, strength.clone()))
+        }
+    }
+
+    fn new(
+        device: Device,
+        _queue: Queue,
+        inputs: &PassInputs<'_>,
+        strength: FilterStrength,
+    ) -> FilterPass {
+        let fs_module = device.create_shader_module(include_spv!("../shaders/filter.spv"));
+        let applied = strength.get();
+        let effect = FullscreenEffect::new(
+            device,
+            inputs,
+            &fs_module,
+            "pixels_filter",
+            &filter_locals(applied),
+        );
+
+        FilterPass {
+            strength,
+            applied,
+            effect,
+        }
+    }
+}
+
+impl RenderPass for FilterPass {
+    fn render(&self, encoder: &mut wgpu::CommandEncoder, render_target: &TextureView) {
+        self.effect.render(encoder, render_target);
+    }
+
+    fn update(&mut self, encoder: &mut wgpu::CommandEncoder, _context: &FrameContext) {
+        // Only upload the strength when it was changed through the handle
+        let strength = self.strength.get();
+        if strength == self.applied {
+            return;
+        }
+
+        self.effect.update_locals(encoder, &filter_locals(strength));
+        self.applied = strength;
+    }
+
+    fn surface_resized(&mut self, encoder: &mut wgpu::CommandEncoder, resize: &SurfaceResize) {
+        self.effect.surface_resized(encoder, resize);
+    }
+
+    fn update_bindings(&mut self, input_texture: &TextureView, _input_texture_size: &Extent3d) {
+        self.effect
+            .update_bindings(input_texture, &filter_locals(self.applied));
+    }
+
+    fn debug(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// The resources shared by the effects, which draw the pixel buffer over the whole surface with
+/// a single fragment shader, scaled like the default scaling pass.
+///
+/// Each effect only brings its fragment shader and the uniform block for its settings, which is
+/// bound at binding 3.
+#[derive(Debug)]
+struct FullscreenEffect {
+    device: Device,
+    label: &'static str,
+    sampler: wgpu::Sampler,
+    transform_buffer: wgpu::Buffer,
+    locals_buffer: wgpu::Buffer,
+    locals_size: wgpu::BufferAddress,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+    pipeline: wgpu::RenderPipeline,
+    destination: Rect,
+}
+
+impl FullscreenEffect {
+    fn new(
+        device: Device,
+        inputs: &PassInputs<'_>,
+        fs_module: &wgpu::ShaderModule,
+        label: &'static str,
+        locals: &[f32],
+    ) -> FullscreenEffect {
+        let vs_module = device.create_shader_module(include_spv!("../shaders/vert.spv"));
+
+        // Create a texture sampler with nearest neighbor
+        let sampler = InputSampler::default().create(&device);
+
+        // Create uniform buffers
+        let size = (
+            inputs.texture_size.width as f32,
+            inputs.texture_size.height as f32,
+        );
+        let matrix = ScalingMatrix::new(size, size);
+        let transform_buffer = device.create_buffer_with_data(
+            matrix.as_bytes(),
+            wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+        );
+        let (locals_buffer, locals_size) = create_locals_buffer(&device, locals);
+
+        // Create bind group
+        let bind_group_layout = create_bind_group_layout(&device, label);
+        let bind_group = create_bind_group(
+            &device,
+            &bind_group_layout,
+            inputs.texture,
+            &sampler,
+            &transform_buffer,
+            (&locals_buffer, locals_size),
+            label,
+        );
+
+        // Create pipeline
+        let pipeline = create_pipeline(
+            &device,
+            &bind_group_layout,
+            &vs_module,
+            fs_module,
+            inputs.surface_format,
+            Blending::Replace,
+        );
+
+        FullscreenEffect {
+            device,
+            label,
+            sampler,
+            transform_buffer,
+            locals_buffer,
+            locals_size,
+            bind_group_layout,
+            bind_group,
+            pipeline,
+            destination: matrix.destination,
+        }
+    }
+
+    fn render(&self, encoder: &mut wgpu::CommandEncoder, render_target: &TextureView) {
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                attachment: render_target,
+                resolve_target: None,
+                load_op: wgpu::LoadOp::Clear,
+                store_op: wgpu::StoreOp::Store,
+                clear_color: wgpu::Color::BLACK,
+            }],
+            depth_stencil_attachment: None,
+        });
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, &self.bind_group, &[]);
+        rpass.draw(0..6, 0..1);
+    }
+
+    fn surface_resized(&mut self, encoder: &mut wgpu::CommandEncoder, resize: &SurfaceResize) {
+        // Follow the scaling mode of the default scaling pass
+        let (width, height) = resize.new_size;
+        let matrix = ScalingMatrix::from_destination(
+            (width as f32, height as f32),
+            resize.scale,
+            resize.destination,
+        );
+        let temp_buf = self
+            .device
+            .create_buffer_with_data(matrix.as_bytes(), wgpu::BufferUsage::COPY_SRC);
+        encoder.copy_buffer_to_buffer(&temp_buf, 0, &self.transform_buffer, 0, 64);
+
+        self.destination = resize.destination;
+    }
+
+    /// Bind a new input texture, with the locals for its size.
+    fn update_bindings(&mut self, input_texture: &TextureView, locals: &[f32]) {
+        let (locals_buffer, locals_size) = create_locals_buffer(&self.device, locals);
+        self.bind_group = create_bind_group(
+            &self.device,
+            &self.bind_group_layout,
+            input_texture,
+            &self.sampler,
+            &self.transform_buffer,
+            (&locals_buffer, locals_size),
+            self.label,
+        );
+        self.locals_buffer = locals_buffer;
+        self.locals_size = locals_size;
+    }
+
+    /// Upload new locals of the same size, e.g. when a setting is changed at runtime.
+    fn update_locals(&self, encoder: &mut wgpu::CommandEncoder, locals: &[f32]) {
+        let temp_buf = self
+            .device
+            .create_buffer_with_data(&locals_bytes(locals), wgpu::BufferUsage::COPY_SRC);
+        encoder.copy_buffer_to_buffer(&temp_buf, 0, &self.locals_buffer, 0, self.locals_size);
+    }
+}
+
+// The shader needs the texture height to place one scanline on each row of pixels
+fn crt_locals(settings: CrtSettings, texture_size: &Extent3d) -> [f32; 4] {
+    [
+        settings.scanline_intensity,
+        settings.curvature,
+        settings.mask_strength,
+        texture_size.height as f32,
+    ]
+}
+
+// The shader needs the texture size to find the neighbors of each source pixel
+fn outline_locals(settings: OutlineSettings, texture_size: &Extent3d) -> [f32; 8] {
+    let [r, g, b, a] = settings.color;
+    [
+        r,
+        g,
+        b,
+        a,
+        settings.width,
+        settings.threshold,
+        texture_size.width as f32,
+        texture_size.height as f32,
+    ]
+}
+
+fn filter_locals(strength: f32) -> [f32; 4] {
+    [strength, 0.0, 0.0, 0.0]
+}
+
+fn locals_bytes(locals: &[f32]) -> Vec<u8> {
+    locals
+        .iter()
+        .flat_map(|value| value.to_ne_bytes().to_vec())
+        .collect()
+}
+
+fn create_locals_buffer(
+    device: &wgpu::Device,
+    locals: &[f32],
+) -> (wgpu::Buffer, wgpu::BufferAddress) {
+    let bytes = locals_bytes(locals);
+    let buffer = device.create_buffer_with_data(
+        &bytes,
+        wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+    );
+
+    (buffer, bytes.len() as wgpu::BufferAddress)
+}
+
+fn create_bind_group_layout(device: &wgpu::Device, label: &str) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some(&format!("{}_layout", label)),
+        bindings: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: bindings::TEXTURE,
+                visibility: wgpu::ShaderStage::FRAGMENT,
+                ty: wgpu::BindingType::SampledTexture {
+                    component_type: wgpu::TextureComponentType::Float,
+                    multisampled: false,
+                    dimension: wgpu::TextureViewDimension::D2,
+                },
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: bindings::SAMPLER,
+                visibility: wgpu::ShaderStage::FRAGMENT,
+                ty: wgpu::BindingType::Sampler { comparison: false },
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: bindings::TRANSFORM,
+                visibility: wgpu::ShaderStage::VERTEX,
+                ty: wgpu::BindingType::UniformBuffer { dynamic: false },
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 3,
+                visibility: wgpu::ShaderStage::FRAGMENT,
+                ty: wgpu::BindingType::UniformBuffer { dynamic: false },
+            },
+        ],
+    })
+}
+
+fn create_bind_group(
+    device: &wgpu::Device,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    texture_view: &TextureView,
+    sampler: &wgpu::Sampler,
+    transform_buffer: &wgpu::Buffer,
+    (locals_buffer, locals_size): (&wgpu::Buffer, wgpu::BufferAddress),
+    label: &str,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some(&format!("{}_bind_group", label)),
+        layout: bind_group_layout,
+        bindings: &[
+            wgpu::Binding {
+                binding: bindings::TEXTURE,
+                resource: wgpu::BindingResource::TextureView(texture_view),
+            },
+            wgpu::Binding {
+                binding: bindings::SAMPLER,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+            wgpu::Binding {
+                binding: bindings::TRANSFORM,
+                resource: wgpu::BindingResource::Buffer {
+                    buffer: transform_buffer,
+                    range: 0..bindings::TRANSFORM_SIZE,
+                },
+            },
+            wgpu::Binding {
+                binding: 3,
+                resource: wgpu::BindingResource::Buffer {
+                    buffer: locals_buffer,
+                    range: 0..locals_size,
+                },
+            },
+        ],
+    })
+}
@@ -0,0 +1,117 @@
+use std::collections::VecDeque;
+
+use crate::Rect;
+
+/// The number of events kept until they are polled. The oldest events are dropped first, so an
+/// application which never polls does not grow the queue without bounds.
+const CAPACITY: usize = 64;
+
+/// A change to the internal state of a pixel buffer, reported by [`Pixels::poll_events`].
+///
+/// Applications which keep their own size-dependent resources, like GUI render targets or
+/// mouse mapping tables, can react to these instead of comparing getters every frame.
+///
+/// [`Pixels::poll_events`]: ./struct.Pixels.html#method.poll_events
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PixelsEvent {
+    /// The swap chain, or the texture of an offscreen surface, was recreated with a new size by
+    /// [`Pixels::resize`], or the swap chain was recreated during rendering because it became
+    /// outdated.
+    ///
+    /// [`Pixels::resize`]: ./struct.Pixels.html#method.resize
+    SwapChainRecreated {
+        /// The surface width in physical pixel units.
+        width: u32,
+        /// The surface height in physical pixel units.
+        height: u32,
+    },
+    /// The pixel buffer was resized by [`Pixels::resize_buffer`].
+    ///
+    /// [`Pixels::resize_buffer`]: ./struct.Pixels.html#method.resize_buffer
+    BufferResized {
+        /// The pixel buffer width.
+        width: u32,
+        /// The pixel buffer height.
+        height: u32,
+    },
+    /// The scale or position of the pixel buffer on the surface changed, e.g. after a resize or
+    /// a change to the scaling mode.
+    ScaleChanged {
+        /// The factor by which the pixel buffer is scaled, horizontally and vertically.
+        scale: (f32, f32),
+        /// The rectangle on the surface where the pixel buffer is drawn.
+        destination: Rect,
+    },
+    /// A render pass was enabled or disabled with [`Pixels::set_pass_enabled`].
+    ///
+    /// [`Pixels::set_pass_enabled`]: ./struct.Pixels.html#method.set_pass_enabled
+    PassToggled {
+        /// The index of the render pass.
+        index: usize,
+        /// Whether the render pass is now enabled.
+        enabled: bool,
+    },
+    /// A render pass was added with [`Pixels::add_render_pass`] or
+    /// [`Pixels::add_render_pass_with`].
+    ///
+    /// [`Pixels::add_render_pass`]: ./struct.Pixels.html#method.add_render_pass
+    /// [`Pixels::add_render_pass_with`]: ./struct.Pixels.html#method.add_render_pass_with
+    PassAdded {
+        /// The index of the new render pass.
+        index: usize,
+    },
+    /// A render pass was removed with [`Pixels::remove_render_pass`]. The render passes after it
+    /// moved down by one index.
+    ///
+    /// [`Pixels::remove_render_pass`]: ./struct.Pixels.html#method.remove_render_pass
+    PassRemoved {
+        /// The index of the removed render pass.
+        index: usize,
+    },
+    /// A render pass was moved with [`Pixels::move_render_pass`].
+    ///
+    /// [`Pixels::move_render_pass`]: ./struct.Pixels.html#method.move_render_pass
+    PassMoved {
+        /// The index of the render pass before the move.
+        from: usize,
+        /// The index of the render pass after the move.
+        to: usize,
+    },
+}
+
+/// Events which have not been polled yet.
+#[derive(Debug)]
+pub(crate) struct EventQueue {
+    events: VecDeque<PixelsEvent>,
+    // The most recently reported scale and destination, for reporting only actual changes
+    scaling: ((f32, f32), Rect),
+}
+
+impl EventQueue {
+    /// Create an empty queue for a pixel buffer which was built with the given scaling.
+    pub(crate) fn new(scale: (f32, f32), destination: Rect) -> EventQueue {
+        EventQueue {
+            events: VecDeque::with_capacity(CAPACITY),
+            scaling: (scale, destination),
+        }
+    }
+
+    pub(crate) fn push(&mut self, event: PixelsEvent) {
+        if self.events.len() == CAPACITY {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+    }
+
+    /// Report a [`PixelsEvent::ScaleChanged`] when the scaling differs from the last report.
+    pub(crate) fn scaling_updated(&mut self, scale: (f32, f32), destination: Rect) {
+        if self.scaling != (scale, destination) {
+            self.scaling = (scale, destination);
+            self.push(PixelsEvent::ScaleChanged { scale, destination });
+        }
+    }
+
+    pub(crate) fn drain(&mut self) -> impl Iterator<Item = PixelsEvent> + '_ {
+        self.events.drain(..)
+    }
+}
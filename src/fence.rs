@@ -0,0 +1,101 @@
+use futures_util::task::noop_waker_ref;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+type MapFuture =
+    Pin<Box<dyn Future<Output = Result<wgpu::BufferReadMapping, wgpu::BufferAsyncErr>> + Send>>;
+
+/// A marker in the GPU queue, signaled when all work submitted before it has completed.
+///
+/// `wgpu` does not expose submission indices, so the fence is a tiny buffer copy submitted after
+/// the work it tracks, which is mapped for reading once the GPU has executed it. Work submitted
+/// later to the same queue is always ordered after the fence.
+///
+/// The fence only makes progress while the device is polled, e.g. with
+/// `device.poll(wgpu::Maintain::Poll)` or by calling [`Pixels::render`]. It is also a
+/// [`Future`], for awaiting in applications which poll the device from an executor.
+///
+/// Create a fence with [`Pixels::submission_fence`].
+///
+/// [`Pixels::render`]: ./struct.Pixels.html#method.render
+/// [`Pixels::submission_fence`]: ./struct.Pixels.html#method.submission_fence
+pub struct SubmissionFence {
+    // Keeps the mapped buffer alive until the fence is dropped
+    _buffer: wgpu::Buffer,
+    future: MapFuture,
+    signaled: bool,
+}
+
+impl SubmissionFence {
+    /// Submit a fence after all work submitted to `queue` so far.
+    pub(crate) fn new(device: &wgpu::Device, queue: &mut wgpu::Queue) -> SubmissionFence {
+        let source = device.create_buffer_with_data(&[0; 4], wgpu::BufferUsage::COPY_SRC);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("pixels_fence_buffer"),
+            size: 4,
+            usage: wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("pixels_fence"),
+        });
+        encoder.copy_buffer_to_buffer(&source, 0, &buffer, 0, 4);
+        queue.submit(&[encoder.finish()]);
+        let future = Box::pin(buffer.map_read(0, 4));
+
+        SubmissionFence {
+            _buffer: buffer,
+            future,
+            signaled: false,
+        }
+    }
+
+    /// Returns `true` when the GPU has completed all work submitted before the fence, without
+    /// blocking.
+    pub fn is_signaled(&mut self) -> bool {
+        if !self.signaled {
+            let mut context = Context::from_waker(noop_waker_ref());
+            self.signaled = self.future.as_mut().poll(&mut context).is_ready();
+        }
+
+        self.signaled
+    }
+
+    /// Block until the GPU has completed all work submitted before the fence.
+    ///
+    /// `device` must be the device that the fence was created with.
+    pub fn wait(mut self, device: &wgpu::Device) {
+        while !self.is_signaled() {
+            device.poll(wgpu::Maintain::Wait);
+        }
+    }
+}
+
+impl Future for SubmissionFence {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<()> {
+        if self.signaled {
+            return Poll::Ready(());
+        }
+
+        // A failed mapping means the device is gone, so there is nothing left to wait for
+        match self.future.as_mut().poll(context) {
+            Poll::Ready(_) => {
+                self.signaled = true;
+                Poll::Ready(())
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl fmt::Debug for SubmissionFence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SubmissionFence")
+            .field("signaled", &self.signaled)
+            .finish()
+    }
+}
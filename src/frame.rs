@@ -0,0 +1,245 @@
+use std::marker::PhantomData;
+
+use crate::color::{BlendSpace, Color};
+
+/// A pixel type which can be read from and written to the pixel buffer.
+///
+/// Implemented for byte arrays of every texture format size, and for [`Color`], which is stored
+/// as 4 bytes of RGBA.
+///
+/// [`Color`]: ./color/struct.Color.html
+pub trait Pixel: Copy {
+    /// The size of one pixel in bytes.
+    const SIZE: usize;
+
+    /// Read a pixel from exactly [`Pixel::SIZE`] bytes.
+    fn from_bytes(bytes: &[u8]) -> Self;
+
+    /// Write the pixel to exactly [`Pixel::SIZE`] bytes.
+    fn write_bytes(self, bytes: &mut [u8]);
+}
+
+/// A typed view of the pixel buffer.
+///
+/// Created by [`Pixels::get_frame_pixels`]. Positions are checked with debug assertions, so
+/// per-pixel code which walks off the edge of a row is caught in debug builds instead of silently
+/// writing to the next row.
+///
+/// [`Pixels::get_frame_pixels`]: ./struct.Pixels.html#method.get_frame_pixels
+#[derive(Debug)]
+pub struct Frame<'a, P: Pixel> {
+    pixels: &'a mut [u8],
+    width: u32,
+    height: u32,
+    pixel: PhantomData<P>,
+}
+
+/// A typed view of one row of the pixel buffer.
+///
+/// Created by [`Frame::rows_mut`].
+#[derive(Debug)]
+pub struct RowMut<'a, P: Pixel> {
+    pixels: &'a mut [u8],
+    width: u32,
+    pixel: PhantomData<P>,
+}
+
+impl<'a, P: Pixel> Frame<'a, P> {
+    pub(crate) fn new(pixels: &'a mut [u8], width: u32, height: u32) -> Frame<'a, P> {
+        debug_assert_eq!(pixels.len(), width as usize * height as usize * P::SIZE);
+
+        Frame {
+            pixels,
+            width,
+            height,
+            pixel: PhantomData,
+        }
+    }
+
+    /// The width of the pixel buffer in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of the pixel buffer in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Get the pixel at `(x, y)`.
+    pub fn get(&self, x: u32, y: u32) -> P {
+        let start = self.offset(x, y);
+
+        P::from_bytes(&self.pixels[start..start + P::SIZE])
+    }
+
+    /// Set the pixel at `(x, y)`.
+    pub fn set(&mut self, x: u32, y: u32, pixel: P) {
+        let start = self.offset(x, y);
+
+        pixel.write_bytes(&mut self.pixels[start..start + P::SIZE]);
+    }
+
+    /// Fill the entire pixel buffer with `pixel`.
+    pub fn fill(&mut self, pixel: P) {
+        for chunk in self.pixels.chunks_exact_mut(P::SIZE) {
+            pixel.write_bytes(chunk);
+        }
+    }
+
+    /// Iterate over all rows, from top to bottom.
+    pub fn rows_mut(&mut self) -> impl Iterator<Item = RowMut<'_, P>> {
+        let width = self.width;
+
+        self.pixels
+            .chunks_exact_mut(width as usize * P::SIZE)
+            .map(move |pixels| RowMut {
+                pixels,
+                width,
+                pixel: PhantomData,
+            })
+    }
+
+    /// Get the raw bytes of the pixel buffer.
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        self.pixels
+    }
+
+    fn offset(&self, x: u32, y: u32) -> usize {
+        debug_assert!(
+            x < self.width && y < self.height,
+            "pixel ({}, {}) is outside of the {}x{} pixel buffer",
+            x,
+            y,
+            self.width,
+            self.height
+        );
+
+        (y as usize * self.width as usize + x as usize) * P::SIZE
+    }
+}
+
+impl<'a, P: Pixel> RowMut<'a, P> {
+    /// The width of the row in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Get the pixel in column `x`.
+    pub fn get(&self, x: u32) -> P {
+        let start = self.offset(x);
+
+        P::from_bytes(&self.pixels[start..start + P::SIZE])
+    }
+
+    /// Set the pixel in column `x`.
+    pub fn set(&mut self, x: u32, pixel: P) {
+        let start = self.offset(x);
+
+        pixel.write_bytes(&mut self.pixels[start..start + P::SIZE]);
+    }
+
+    /// Get the raw bytes of the row.
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        self.pixels
+    }
+
+    fn offset(&self, x: u32) -> usize {
+        debug_assert!(
+            x < self.width,
+            "column {} is outside of the {} pixel wide row",
+            x,
+            self.width
+        );
+
+        x as usize * P::SIZE
+    }
+}
+
+impl<'a> Frame<'a, Color> {
+    /// Composite `color` over the pixel at `(x, y)` with its alpha, blending in `space`.
+    ///
+    /// Use [`BlendSpace::Linear`] with the default `sRGB` texture format so antialiased edges
+    /// drawn on the CPU match what the GPU would draw. See [`Color::over`].
+    ///
+    /// [`BlendSpace::Linear`]: ./color/enum.BlendSpace.html#variant.Linear
+    /// [`Color::over`]: ./color/struct.Color.html#method.over
+    pub fn blend(&mut self, x: u32, y: u32, color: Color, space: BlendSpace) {
+        let background = self.get(x, y);
+
+        self.set(x, y, color.over(background, space));
+    }
+
+    /// Get the pixel at `(x, y)` as linear components in `RGBA` order.
+    ///
+    /// The pixel is decoded from `sRGB`, which matches the default texture format. See
+    /// [`Color::to_linear`].
+    ///
+    /// [`Color::to_linear`]: ./color/struct.Color.html#method.to_linear
+    pub fn get_linear(&self, x: u32, y: u32) -> [f32; 4] {
+        self.get(x, y).to_linear()
+    }
+
+    /// Set the pixel at `(x, y)` from linear components in `RGBA` order.
+    ///
+    /// Lighting and other color math is only correct in linear space, so this is where it should
+    /// be done. The components are encoded as `sRGB` for the default texture format, like the GPU
+    /// would encode them. Components outside of the range `0.0..=1.0` are clamped.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use pixels::color::Color;
+    /// use pixels::Pixels;
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    /// let mut frame = pixels.get_frame_pixels::<Color>();
+    ///
+    /// // Half of the light of a white pixel, which is brighter than 0x80
+    /// let [r, g, b, a] = frame.get_linear(0, 0);
+    /// frame.set_linear(0, 0, [r * 0.5, g * 0.5, b * 0.5, a]);
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn set_linear(&mut self, x: u32, y: u32, linear: [f32; 4]) {
+        let [r, g, b, a] = linear;
+
+        self.set(x, y, Color::from_linear(r, g, b, a));
+    }
+}
+
+macro_rules! impl_pixel_for_array {
+    ($($size:expr),*) => {
+        $(
+            impl Pixel for [u8; $size] {
+                const SIZE: usize = $size;
+
+                fn from_bytes(bytes: &[u8]) -> Self {
+                    let mut pixel = [0; $size];
+                    pixel.copy_from_slice(bytes);
+
+                    pixel
+                }
+
+                fn write_bytes(self, bytes: &mut [u8]) {
+                    bytes.copy_from_slice(&self);
+                }
+            }
+        )*
+    };
+}
+
+impl_pixel_for_array!(1, 2, 4, 8, 16);
+
+impl Pixel for Color {
+    const SIZE: usize = 4;
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        Color::from_rgba8(bytes[0], bytes[1], bytes[2], bytes[3])
+    }
+
+    fn write_bytes(self, bytes: &mut [u8]) {
+        bytes.copy_from_slice(&self.to_rgba8());
+    }
+}
@@ -0,0 +1,227 @@
+use instant::Instant;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::crash::lock;
+
+/// A queue of timestamped frames from a producer running at its own rate, like an emulator core
+/// running at 59.94 Hz.
+///
+/// Frames are presented by [`Pixels::render_from_queue`] against the display clock: each present
+/// shows the newest frame whose timestamp is due at the next vblank. Frames are shown more than
+/// once when the display is faster than the producer, and skipped when it is slower, without the
+/// stutter of presenting frames as soon as they arrive.
+///
+/// The queue delays frames by `latency` to absorb jitter in the producer. When the producer
+/// drifts too far ahead of or behind the display, the queue resynchronizes on the newest frame.
+///
+/// Queues are cheap to clone, and clones share the same frames, so the producer can push frames
+/// from another thread.
+///
+/// # Examples
+///
+/// ```no_run
+/// use pixels::{FrameQueue, Pixels};
+/// use std::time::Duration;
+///
+/// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+/// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+/// let mut pixels = Pixels::new(256, 240, surface_texture)?;
+/// let queue = FrameQueue::new(4, Duration::from_millis(20));
+///
+/// let producer = queue.clone();
+/// std::thread::spawn(move || {
+///     for frame_number in 0.. {
+///         let frame = vec![0; 256 * 240 * 4];
+///         // ... Emulate one frame
+///         producer.push(Duration::from_secs_f64(frame_number as f64 / 59.94), frame);
+///     }
+/// });
+///
+/// loop {
+///     pixels.render_from_queue(&queue)?;
+/// }
+/// # Ok::<(), pixels::Error>(())
+/// ```
+///
+/// [`Pixels::render_from_queue`]: ./struct.Pixels.html#method.render_from_queue
+#[derive(Clone, Debug)]
+pub struct FrameQueue {
+    timeline: Arc<Mutex<Timeline>>,
+}
+
+/// Maps producer timestamps to the display clock.
+#[derive(Debug)]
+struct Timeline {
+    frames: VecDeque<(Duration, Vec<u8>)>,
+    capacity: usize,
+    latency: Duration,
+    // The display time at which a producer timestamp is due
+    origin: Option<(Instant, Duration)>,
+}
+
+impl FrameQueue {
+    /// Create a queue holding up to `capacity` frames, presented `latency` after they are due.
+    ///
+    /// A latency of one or two producer frames is usually enough to hide jitter.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `capacity` is 0.
+    pub fn new(capacity: usize, latency: Duration) -> FrameQueue {
+        assert!(capacity > 0);
+
+        FrameQueue {
+            timeline: Arc::new(Mutex::new(Timeline {
+                frames: VecDeque::with_capacity(capacity),
+                capacity,
+                latency,
+                origin: None,
+            })),
+        }
+    }
+
+    /// Add a frame with its `timestamp` in the producer's clock.
+    ///
+    /// Timestamps are expected to increase. A timestamp earlier than the newest frame means the
+    /// producer restarted, and the queue is cleared. When the queue is full, the oldest frame is
+    /// dropped.
+    pub fn push(&self, timestamp: Duration, frame: Vec<u8>) {
+        let mut timeline = lock(&self.timeline);
+
+        let restarted = timeline
+            .frames
+            .back()
+            .map_or(false, |(newest, _)| timestamp < *newest);
+        if restarted {
+            timeline.frames.clear();
+            timeline.origin = None;
+        }
+        if timeline.frames.len() == timeline.capacity {
+            timeline.frames.pop_front();
+        }
+
+        timeline.frames.push_back((timestamp, frame));
+    }
+
+    /// The number of frames waiting to be presented.
+    pub fn len(&self) -> usize {
+        lock(&self.timeline).frames.len()
+    }
+
+    /// Returns `true` when no frames are waiting to be presented.
+    pub fn is_empty(&self) -> bool {
+        lock(&self.timeline).frames.is_empty()
+    }
+
+    /// Discard all frames, and resynchronize with the next frame pushed.
+    pub fn clear(&self) {
+        let mut timeline = lock(&self.timeline);
+
+        timeline.frames.clear();
+        timeline.origin = None;
+    }
+
+    /// Take the frame to show at `display_time`, dropping any older frames.
+    ///
+    /// Returns `None` when the frame on screen is still the right one.
+    pub(crate) fn take(&self, display_time: Instant) -> Option<Vec<u8>> {
+        let mut timeline = lock(&self.timeline);
+        let newest = timeline.frames.back()?.0;
+        let oldest = timeline.frames.front()?.0;
+        let latency = timeline.latency;
+
+        // Start `latency` behind the newest frame, to leave room for jitter
+        let resync = (
+            display_time,
+            newest.checked_sub(latency).unwrap_or(oldest).max(oldest),
+        );
+        let (origin_time, origin_timestamp) = *timeline.origin.get_or_insert(resync);
+        let mut due = origin_timestamp + display_time.saturating_duration_since(origin_time);
+
+        // Resynchronize when the producer stalled, or ran too far ahead
+        if due > newest + latency || newest > due + latency * 2 {
+            timeline.origin = Some(resync);
+            due = resync.1;
+        }
+
+        let mut frame = None;
+        while timeline
+            .frames
+            .front()
+            .map_or(false, |(timestamp, _)| *timestamp <= due)
+        {
+            frame = timeline.frames.pop_front().map(|(_, frame)| frame);
+        }
+
+        frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    fn queue(timestamps: &[u64]) -> FrameQueue {
+        let queue = FrameQueue::new(8, ms(20));
+        for &timestamp in timestamps {
+            queue.push(ms(timestamp), vec![timestamp as u8]);
+        }
+
+        queue
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(queue(&[]).take(Instant::now()), None);
+    }
+
+    #[test]
+    fn starts_latency_behind_the_newest_frame() {
+        let queue = queue(&[0, 10, 20, 30, 40]);
+        let start = Instant::now();
+
+        // Older frames are dropped
+        assert_eq!(queue.take(start), Some(vec![20]));
+        assert_eq!(queue.len(), 2);
+
+        assert_eq!(queue.take(start + ms(10)), Some(vec![30]));
+        assert_eq!(queue.take(start + ms(15)), None);
+        assert_eq!(queue.take(start + ms(20)), Some(vec![40]));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn resynchronizes_when_the_producer_runs_ahead() {
+        let queue = queue(&[0, 10, 20]);
+        let start = Instant::now();
+        assert_eq!(queue.take(start), Some(vec![0]));
+        assert_eq!(queue.take(start + ms(20)), Some(vec![20]));
+
+        // Far more than twice the latency ahead of the display clock
+        queue.push(ms(200), vec![200]);
+        assert_eq!(queue.take(start + ms(30)), Some(vec![200]));
+    }
+
+    #[test]
+    fn restarted_producer_clears_the_queue() {
+        let queue = queue(&[100, 110]);
+        queue.push(ms(0), vec![0]);
+
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.take(Instant::now()), Some(vec![0]));
+    }
+
+    #[test]
+    fn full_queue_drops_the_oldest_frame() {
+        let queue = queue(&[0, 10, 20, 30, 40, 50, 60, 70, 80]);
+
+        assert_eq!(queue.len(), 8);
+        assert_eq!(queue.take(Instant::now()), Some(vec![60]));
+    }
+}
@@ -0,0 +1,200 @@
+use crate::color::Color;
+use crate::include_spv;
+use crate::pipeline::{create_pipeline_with_vertices, Blending};
+use crate::renderers::ScalingMatrix;
+use wgpu::TextureView;
+
+/// Size of one vertex: a position followed by a linear `RGBA` color.
+const VERTEX_SIZE: u64 = 6 * 4;
+
+/// Debug shapes drawn over the pixel buffer, like hitboxes and collision normals.
+///
+/// Shapes are specified in pixel buffer coordinates, but drawn by the GPU after all render passes
+/// on the surface, at the full resolution of the window. They never modify the pixel buffer, and
+/// are cleared after each frame is rendered, so add them again for every frame that should show
+/// them.
+///
+/// Use [`Pixels::gizmos`] to access the gizmos for the next frame.
+///
+/// # Examples
+///
+/// ```no_run
+/// use pixels::color::Color;
+/// use pixels::Pixels;
+///
+/// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+/// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+/// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+/// let red = Color::new(1.0, 0.0, 0.0, 1.0);
+///
+/// pixels
+///     .gizmos()
+///     .rect(16.0, 24.0, 32.0, 32.0, red)
+///     .line(32.0, 40.0, 64.0, 40.0, Color::WHITE);
+/// pixels.render()?;
+/// # Ok::<(), pixels::Error>(())
+/// ```
+///
+/// [`Pixels::gizmos`]: ./struct.Pixels.html#method.gizmos
+#[derive(Debug, Default)]
+pub struct Gizmos {
+    // Line list vertices: `x`, `y` in pixel coordinates, and a linear color
+    vertices: Vec<[f32; 6]>,
+}
+
+/// Draws [`Gizmos`] over the render target.
+#[derive(Debug)]
+pub(crate) struct GizmoRenderer {
+    transform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    pipeline: wgpu::RenderPipeline,
+    width: f32,
+    height: f32,
+}
+
+impl Gizmos {
+    /// Draw a line from `(x0, y0)` to `(x1, y1)`.
+    pub fn line(&mut self, x0: f32, y0: f32, x1: f32, y1: f32, color: Color) -> &mut Gizmos {
+        let [r, g, b, a] = color.to_linear();
+        self.vertices.push([x0, y0, r, g, b, a]);
+        self.vertices.push([x1, y1, r, g, b, a]);
+        self
+    }
+
+    /// Draw the outline of a rectangle with its top-left corner at `(x, y)`.
+    ///
+    /// The outline is drawn along the outer edges of the pixels covered by the rectangle, so
+    /// `rect(0.0, 0.0, 8.0, 8.0, color)` surrounds the first 8x8 pixels of the buffer.
+    pub fn rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: Color) -> &mut Gizmos {
+        let (right, bottom) = (x + width, y + height);
+
+        self.line(x, y, right, y, color)
+            .line(right, y, right, bottom, color)
+            .line(right, bottom, x, bottom, color)
+            .line(x, bottom, x, y, color)
+    }
+
+    /// Remove all shapes. This happens automatically after each frame is rendered.
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+    }
+
+    /// Returns `true` when there are no shapes to draw.
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+}
+
+impl GizmoRenderer {
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        texture_size: &wgpu::Extent3d,
+        format: wgpu::TextureFormat,
+    ) -> GizmoRenderer {
+        let vs_module = device.create_shader_module(include_spv!("../shaders/gizmo_vert.spv"));
+        let fs_module = device.create_shader_module(include_spv!("../shaders/gizmo_frag.spv"));
+
+        // The transform is updated for the current surface size on each draw
+        let transform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("pixels_gizmo_transform_buffer"),
+            size: 64,
+            usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+        });
+
+        // Create bind group
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("pixels_gizmo_bind_group_layout"),
+            bindings: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStage::VERTEX,
+                ty: wgpu::BindingType::UniformBuffer { dynamic: false },
+            }],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("pixels_gizmo_bind_group"),
+            layout: &bind_group_layout,
+            bindings: &[wgpu::Binding {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer {
+                    buffer: &transform_buffer,
+                    range: 0..64,
+                },
+            }],
+        });
+
+        // Create pipeline
+        let pipeline = create_pipeline_with_vertices(
+            device,
+            &bind_group_layout,
+            &vs_module,
+            &fs_module,
+            format,
+            Blending::Alpha,
+            wgpu::PrimitiveTopology::LineList,
+            &[wgpu::VertexBufferDescriptor {
+                stride: VERTEX_SIZE,
+                step_mode: wgpu::InputStepMode::Vertex,
+                attributes: &[
+                    wgpu::VertexAttributeDescriptor {
+                        offset: 0,
+                        format: wgpu::VertexFormat::Float2,
+                        shader_location: 0,
+                    },
+                    wgpu::VertexAttributeDescriptor {
+                        offset: 2 * 4,
+                        format: wgpu::VertexFormat::Float4,
+                        shader_location: 1,
+                    },
+                ],
+            }],
+        );
+
+        GizmoRenderer {
+            transform_buffer,
+            bind_group,
+            pipeline,
+            width: texture_size.width as f32,
+            height: texture_size.height as f32,
+        }
+    }
+
+    /// Encode a pass which draws `gizmos` over `render_target`.
+    pub(crate) fn render(
+        &self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        render_target: &TextureView,
+        scaling_matrix: &ScalingMatrix,
+        gizmos: &Gizmos,
+    ) {
+        let temp_buf =
+            device.create_buffer_with_data(scaling_matrix.as_bytes(), wgpu::BufferUsage::COPY_SRC);
+        encoder.copy_buffer_to_buffer(&temp_buf, 0, &self.transform_buffer, 0, 64);
+
+        // Pixel coordinates are mapped to the same space as the scaling renderer's quad
+        let mut vertices = Vec::with_capacity(gizmos.vertices.len() * VERTEX_SIZE as usize);
+        for vertex in gizmos.vertices.iter() {
+            let x = vertex[0] / self.width * 2.0 - 1.0;
+            let y = vertex[1] / self.height * 2.0 - 1.0;
+            for value in [x, y].iter().chain(vertex[2..].iter()) {
+                vertices.extend_from_slice(&value.to_ne_bytes());
+            }
+        }
+        let vertex_buffer = device.create_buffer_with_data(&vertices, wgpu::BufferUsage::VERTEX);
+
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                attachment: render_target,
+                resolve_target: None,
+                load_op: wgpu::LoadOp::Load,
+                store_op: wgpu::StoreOp::Store,
+                clear_color: wgpu::Color::TRANSPARENT,
+            }],
+            depth_stencil_attachment: None,
+        });
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, &self.bind_group, &[]);
+        rpass.set_vertex_buffer(0, &vertex_buffer, 0, 0);
+        rpass.draw(0..gizmos.vertices.len() as u32, 0..1);
+    }
+}
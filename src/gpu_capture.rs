@@ -0,0 +1,39 @@
+use renderdoc::{RenderDoc, V110};
+use std::fmt;
+
+/// A connection to the RenderDoc in-application API.
+///
+/// The connection only succeeds when the application was launched by RenderDoc, or when the
+/// RenderDoc library was otherwise injected into the process.
+pub(crate) struct GpuCapture {
+    renderdoc: Option<RenderDoc<V110>>,
+}
+
+impl GpuCapture {
+    /// Connect to RenderDoc. This must happen before any graphics API state is created, so that
+    /// RenderDoc has a chance to hook it.
+    pub(crate) fn new() -> GpuCapture {
+        GpuCapture {
+            renderdoc: RenderDoc::new().ok(),
+        }
+    }
+
+    /// Capture the next presented frame. Returns `false` when RenderDoc is not attached.
+    pub(crate) fn trigger(&mut self) -> bool {
+        match self.renderdoc.as_mut() {
+            Some(renderdoc) => {
+                renderdoc.trigger_capture();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl fmt::Debug for GpuCapture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GpuCapture")
+            .field("attached", &self.renderdoc.is_some())
+            .finish()
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// A ring buffer holding copies of the most recently rendered pixel buffers.
+#[derive(Debug)]
+pub(crate) struct FrameHistory {
+    capacity: usize,
+    frames: VecDeque<Vec<u8>>,
+}
+
+impl FrameHistory {
+    /// Create a frame history that retains up to `capacity` frames.
+    ///
+    /// A `capacity` of 0 disables the history entirely.
+    pub(crate) fn new(capacity: usize) -> FrameHistory {
+        FrameHistory {
+            capacity,
+            frames: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Returns `true` when frames are retained.
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Record a copy of `frame`, evicting the oldest frame when the history is full.
+    pub(crate) fn push(&mut self, frame: &[u8]) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        // Reuse the allocation of the evicted frame when possible
+        let mut buffer = if self.frames.len() == self.capacity {
+            self.frames.pop_front().unwrap_or_default()
+        } else {
+            Vec::with_capacity(frame.len())
+        };
+        buffer.clear();
+        buffer.extend_from_slice(frame);
+
+        self.frames.push_back(buffer);
+    }
+
+    /// Iterate over all retained frames, oldest first.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &[u8]> {
+        self.frames.iter().map(Vec::as_slice)
+    }
+
+    /// Discard all retained frames.
+    pub(crate) fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    /// Write all retained frames into `dir`, oldest first.
+    ///
+    /// Files are named `frame-0000.pam`, `frame-0001.pam`, etc.
+    pub(crate) fn dump(
+        &self,
+        dir: &Path,
+        width: u32,
+        height: u32,
+        texture_format_size: u32,
+    ) -> io::Result<()> {
+        std::fs::create_dir_all(dir)?;
+
+        for (i, frame) in self.iter().enumerate() {
+            let path = dir.join(format!("frame-{:04}.pam", i));
+            write_pam(&path, frame, width, height, texture_format_size)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Write a raw frame to `path` as a Portable Arbitrary Map.
+///
+/// PAM is trivial to produce and can be opened by most image tools (GIMP, ImageMagick, etc.) The
+/// depth is the number of bytes per pixel, so frames in 8-bit-per-channel formats round-trip
+/// exactly. Frames in wider formats are written verbatim and must be reinterpreted by the reader.
+pub(crate) fn write_pam(
+    path: &Path,
+    frame: &[u8],
+    width: u32,
+    height: u32,
+    texture_format_size: u32,
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    write!(
+        writer,
+        "P7\nWIDTH {}\nHEIGHT {}\nDEPTH {}\nMAXVAL 255\n",
+        width, height, texture_format_size,
+    )?;
+    if texture_format_size == 4 {
+        writeln!(writer, "TUPLTYPE RGB_ALPHA")?;
+    }
+    writeln!(writer, "ENDHDR")?;
+    writer.write_all(frame)?;
+
+    writer.flush()
+}
@@ -0,0 +1,194 @@
+use ultraviolet::Mat4;
+use wgpu::TextureView;
+
+use crate::bindings;
+use crate::include_spv;
+use crate::pipeline::{create_pipeline, Blending};
+use crate::render_pass::InputSampler;
+
+/// Frames inserted after each rendered frame to reduce sample-and-hold motion blur.
+///
+/// Displays hold each frame for the entire refresh interval, which the eye perceives as blur
+/// when tracking moving objects, like smoothly scrolling pixel art. Showing a black (or dimmed)
+/// frame between rendered frames shortens the time each frame is visible, at the cost of
+/// brightness. This works best on high refresh rate displays, where the inserted frames do not
+/// cause visible flicker.
+///
+/// Inserted frames are presented synchronously by [`Pixels::render`], one per vblank, so
+/// insertion is only performed when Vsync is enabled. Without Vsync there is no cadence to
+/// coordinate with, and insertion would just flicker.
+///
+/// [`Pixels::render`]: ./struct.Pixels.html#method.render
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FrameInsertion {
+    /// No frames are inserted. This is the default.
+    Disabled,
+    /// Insert `frames` black frames after each rendered frame.
+    ///
+    /// On a 120 Hz display, one black frame gives a 60 Hz application half of the persistence
+    /// (and half of the brightness) of a normal frame.
+    Black {
+        /// The number of black frames inserted.
+        frames: u32,
+    },
+    /// Insert `frames` copies of each rendered frame, dimmed to `brightness`.
+    ///
+    /// This preserves more brightness than black frames, and reduces flicker, in exchange for
+    /// less reduction in motion blur. `brightness` is a linear multiplier in the range
+    /// `0.0..=1.0`.
+    ///
+    /// The copies are made from the presented frame, including gizmos and overlays. Render
+    /// passes are not executed for inserted frames, so animated passes only advance once per
+    /// rendered frame.
+    Dim {
+        /// The number of dimmed frames inserted.
+        frames: u32,
+        /// The brightness of the inserted frames.
+        brightness: f32,
+    },
+}
+
+/// Multiplies the contents of the render target by a constant brightness.
+#[derive(Debug)]
+pub(crate) struct Dimmer {
+    bind_group: wgpu::BindGroup,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl Default for FrameInsertion {
+    fn default() -> FrameInsertion {
+        FrameInsertion::Disabled
+    }
+}
+
+impl FrameInsertion {
+    /// The number of frames inserted after each rendered frame.
+    pub(crate) fn frames(self) -> u32 {
+        match self {
+            FrameInsertion::Disabled => 0,
+            FrameInsertion::Black { frames } | FrameInsertion::Dim { frames, .. } => frames,
+        }
+    }
+}
+
+impl Dimmer {
+    pub(crate) fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Dimmer {
+        let vs_module = device.create_shader_module(include_spv!("../shaders/vert.spv"));
+        let fs_module = device.create_shader_module(include_spv!("../shaders/frag.spv"));
+
+        // The fragment shader output is ignored by the blend state, but it still needs bindings,
+        // which get a texture of their own instead of the pixel buffer
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("pixels_dimmer_texture"),
+            size: wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth: 1,
+            },
+            array_layer_count: 1,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsage::SAMPLED,
+        });
+        let texture_view = texture.create_default_view();
+        let sampler = InputSampler::default().create(device);
+
+        // Cover the entire render target
+        let transform = Mat4::identity();
+        let transform_buffer =
+            device.create_buffer_with_data(transform.as_byte_slice(), wgpu::BufferUsage::UNIFORM);
+
+        // Create bind group
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("pixels_dimmer_bind_group_layout"),
+            bindings: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: bindings::TEXTURE,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::SampledTexture {
+                        component_type: wgpu::TextureComponentType::Uint,
+                        multisampled: false,
+                        dimension: wgpu::TextureViewDimension::D2,
+                    },
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: bindings::SAMPLER,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler { comparison: false },
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: bindings::TRANSFORM,
+                    visibility: wgpu::ShaderStage::VERTEX,
+                    ty: wgpu::BindingType::UniformBuffer { dynamic: false },
+                },
+            ],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("pixels_dimmer_bind_group"),
+            layout: &bind_group_layout,
+            bindings: &[
+                wgpu::Binding {
+                    binding: bindings::TEXTURE,
+                    resource: wgpu::BindingResource::TextureView(&texture_view),
+                },
+                wgpu::Binding {
+                    binding: bindings::SAMPLER,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+                wgpu::Binding {
+                    binding: bindings::TRANSFORM,
+                    resource: wgpu::BindingResource::Buffer {
+                        buffer: &transform_buffer,
+                        range: 0..bindings::TRANSFORM_SIZE,
+                    },
+                },
+            ],
+        });
+
+        // Create pipeline which multiplies the render target by the blend color
+        let pipeline = create_pipeline(
+            device,
+            &bind_group_layout,
+            &vs_module,
+            &fs_module,
+            format,
+            Blending::MultiplyByBlendColor,
+        );
+
+        Dimmer {
+            bind_group,
+            pipeline,
+        }
+    }
+
+    /// Encode a pass which multiplies the contents of `render_target` by `brightness`.
+    pub(crate) fn render(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        render_target: &TextureView,
+        brightness: f32,
+    ) {
+        let brightness = f64::from(brightness);
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                attachment: render_target,
+                resolve_target: None,
+                load_op: wgpu::LoadOp::Load,
+                store_op: wgpu::StoreOp::Store,
+                clear_color: wgpu::Color::BLACK,
+            }],
+            depth_stencil_attachment: None,
+        });
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, &self.bind_group, &[]);
+        rpass.set_blend_color(wgpu::Color {
+            r: brightness,
+            g: brightness,
+            b: brightness,
+            a: 1.0,
+        });
+        rpass.draw(0..6, 0..1);
+    }
+}
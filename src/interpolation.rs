@@ -0,0 +1,251 @@
+use instant::Instant;
+use std::time::Duration;
+use ultraviolet::Mat4;
+use wgpu::TextureView;
+
+use crate::bindings;
+use crate::include_spv;
+use crate::pipeline::{create_pipeline, Blending};
+
+/// Source frames further apart than this are not blended, so a static image which changes after
+/// a pause is shown immediately instead of fading in.
+const MAX_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Blends the two most recent source frames, weighted by the time since the newest one arrived.
+///
+/// The blended frame is drawn into a texture with the same size and format as the pixel buffer,
+/// which all render passes sample instead of the pixel buffer texture.
+#[derive(Debug)]
+pub(crate) struct Interpolator {
+    previous: wgpu::Texture,
+    blended: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+    pipeline: wgpu::RenderPipeline,
+    // Upload times of the previous and the current source frames
+    previous_time: Option<Instant>,
+    current_time: Option<Instant>,
+}
+
+impl Interpolator {
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        extent: wgpu::Extent3d,
+    ) -> Interpolator {
+        let vs_module = device.create_shader_module(include_spv!("../shaders/vert.spv"));
+        let fs_module = device.create_shader_module(include_spv!("../shaders/frag.spv"));
+
+        let previous = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("pixels_interpolation_previous_texture"),
+            size: extent,
+            array_layer_count: 1,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
+        });
+        let blended = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("pixels_interpolation_blended_texture"),
+            size: extent,
+            array_layer_count: 1,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsage::SAMPLED
+                | wgpu::TextureUsage::OUTPUT_ATTACHMENT
+                | wgpu::TextureUsage::COPY_DST,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            lod_min_clamp: 0.0,
+            lod_max_clamp: 1.0,
+            compare: wgpu::CompareFunction::Always,
+        });
+
+        // Cover the entire texture, flipped so that the top row of the source stays on top
+        #[rustfmt::skip]
+        let transform = Mat4::from([
+            1.0,  0.0, 0.0, 0.0,
+            0.0, -1.0, 0.0, 0.0,
+            0.0,  0.0, 1.0, 0.0,
+            0.0,  0.0, 0.0, 1.0,
+        ]);
+        let transform_buffer =
+            device.create_buffer_with_data(transform.as_byte_slice(), wgpu::BufferUsage::UNIFORM);
+
+        // Create bind group
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("pixels_interpolation_bind_group_layout"),
+            bindings: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: bindings::TEXTURE,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::SampledTexture {
+                        component_type: wgpu::TextureComponentType::Uint,
+                        multisampled: false,
+                        dimension: wgpu::TextureViewDimension::D2,
+                    },
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: bindings::SAMPLER,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler { comparison: false },
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: bindings::TRANSFORM,
+                    visibility: wgpu::ShaderStage::VERTEX,
+                    ty: wgpu::BindingType::UniformBuffer { dynamic: false },
+                },
+            ],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("pixels_interpolation_bind_group"),
+            layout: &bind_group_layout,
+            bindings: &[
+                wgpu::Binding {
+                    binding: bindings::TEXTURE,
+                    resource: wgpu::BindingResource::TextureView(&previous.create_default_view()),
+                },
+                wgpu::Binding {
+                    binding: bindings::SAMPLER,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+                wgpu::Binding {
+                    binding: bindings::TRANSFORM,
+                    resource: wgpu::BindingResource::Buffer {
+                        buffer: &transform_buffer,
+                        range: 0..bindings::TRANSFORM_SIZE,
+                    },
+                },
+            ],
+        });
+
+        // Create pipeline which mixes the previous frame into the current frame by the blend color
+        let pipeline = create_pipeline(
+            device,
+            &bind_group_layout,
+            &vs_module,
+            &fs_module,
+            format,
+            Blending::MixByBlendColor,
+        );
+
+        Interpolator {
+            previous,
+            blended,
+            bind_group,
+            pipeline,
+            previous_time: None,
+            current_time: None,
+        }
+    }
+
+    /// A view of the blended frame, for binding to render passes.
+    pub(crate) fn view(&self) -> TextureView {
+        self.blended.create_default_view()
+    }
+
+    /// Encode a copy of the current frame in `texture`, before a new source frame is uploaded.
+    pub(crate) fn frame_uploaded(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+        extent: wgpu::Extent3d,
+        now: Instant,
+    ) {
+        if self.current_time.is_some() {
+            copy_texture(encoder, texture, &self.previous, extent);
+        }
+        self.previous_time = self.current_time;
+        self.current_time = Some(now);
+    }
+
+    /// The weight of the current frame at `now`, from `0.0` (only the previous frame) to `1.0`
+    /// (only the current frame).
+    fn weight(&self, now: Instant) -> f64 {
+        let (previous_time, current_time) = match (self.previous_time, self.current_time) {
+            (Some(previous_time), Some(current_time)) => (previous_time, current_time),
+            _ => return 1.0,
+        };
+        let interval = current_time.saturating_duration_since(previous_time);
+        if interval.as_nanos() == 0 || interval > MAX_INTERVAL {
+            return 1.0;
+        }
+        let elapsed = now.saturating_duration_since(current_time);
+
+        (elapsed.as_secs_f64() / interval.as_secs_f64()).min(1.0)
+    }
+
+    /// Returns `true` while the frames are still being blended at `now`.
+    pub(crate) fn is_blending(&self, now: Instant) -> bool {
+        self.weight(now) < 1.0
+    }
+
+    /// Encode the blend of the previous frame and the current frame in `texture` at `now`.
+    pub(crate) fn render(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+        extent: wgpu::Extent3d,
+        now: Instant,
+    ) {
+        copy_texture(encoder, texture, &self.blended, extent);
+
+        let weight = self.weight(now);
+        if weight >= 1.0 {
+            return;
+        }
+
+        let blended = self.view();
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                attachment: &blended,
+                resolve_target: None,
+                load_op: wgpu::LoadOp::Load,
+                store_op: wgpu::StoreOp::Store,
+                clear_color: wgpu::Color::BLACK,
+            }],
+            depth_stencil_attachment: None,
+        });
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, &self.bind_group, &[]);
+        rpass.set_blend_color(wgpu::Color {
+            r: weight,
+            g: weight,
+            b: weight,
+            a: weight,
+        });
+        rpass.draw(0..6, 0..1);
+    }
+}
+
+fn copy_texture(
+    encoder: &mut wgpu::CommandEncoder,
+    source: &wgpu::Texture,
+    destination: &wgpu::Texture,
+    extent: wgpu::Extent3d,
+) {
+    encoder.copy_texture_to_texture(
+        wgpu::TextureCopyView {
+            texture: source,
+            mip_level: 0,
+            array_layer: 0,
+            origin: wgpu::Origin3d { x: 0, y: 0, z: 0 },
+        },
+        wgpu::TextureCopyView {
+            texture: destination,
+            mip_level: 0,
+            array_layer: 0,
+            origin: wgpu::Origin3d { x: 0, y: 0, z: 0 },
+        },
+        extent,
+    );
+}
@@ -0,0 +1,527 @@
+use wgpu::TextureView;
+
+use crate::bindings;
+use crate::include_spv;
+use crate::is_srgb;
+use crate::pipeline::{create_pipeline, Blending};
+use crate::render_pass::InputSampler;
+use crate::renderers::Scaling;
+
+/// A handle to a layer created with [`Pixels::add_layer`].
+///
+/// [`Pixels::add_layer`]: ./struct.Pixels.html#method.add_layer
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct LayerId(usize);
+
+/// An RGBA pixel buffer composited over the main pixel buffer.
+#[derive(Debug)]
+struct Layer {
+    texture: wgpu::Texture,
+    view: TextureView,
+    extent: wgpu::Extent3d,
+    pixels: Vec<u8>,
+    dirty: bool,
+    visible: bool,
+    // One transform and bind group for each image drawn, like the scaling renderer
+    uniform_buffers: Vec<wgpu::Buffer>,
+    bind_groups: Vec<wgpu::BindGroup>,
+}
+
+/// All layers, and the pipeline which composites them onto the render target.
+#[derive(Debug)]
+pub(crate) struct Layers {
+    // Removed layers leave an empty slot, so stale handles are never reused
+    layers: Vec<Option<Layer>>,
+    sampler: wgpu::Sampler,
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::RenderPipeline,
+
+    // Legacy blending in sRGB space, which reads a copy of the render target in the shader
+    srgb_blending: bool,
+    format: wgpu::TextureFormat,
+    srgb_bind_group_layout: wgpu::BindGroupLayout,
+    srgb_pipeline: wgpu::RenderPipeline,
+    srgb_locals: wgpu::Buffer,
+    backdrop: Option<Backdrop>,
+}
+
+/// A copy of the render target, which is read by the shader blending in sRGB space.
+#[derive(Debug)]
+struct Backdrop {
+    texture: wgpu::Texture,
+    view: TextureView,
+    extent: wgpu::Extent3d,
+}
+
+impl Layers {
+    pub(crate) fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Layers {
+        let vs_module = device.create_shader_module(include_spv!("../shaders/vert.spv"));
+        let fs_module = device.create_shader_module(include_spv!("../shaders/frag.spv"));
+        let sampler = InputSampler::default().create(device);
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("pixels_layer_bind_group_layout"),
+            bindings: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: bindings::TEXTURE,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::SampledTexture {
+                        component_type: wgpu::TextureComponentType::Uint,
+                        multisampled: false,
+                        dimension: wgpu::TextureViewDimension::D2,
+                    },
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: bindings::SAMPLER,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler { comparison: false },
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: bindings::TRANSFORM,
+                    visibility: wgpu::ShaderStage::VERTEX,
+                    ty: wgpu::BindingType::UniformBuffer { dynamic: false },
+                },
+            ],
+        });
+
+        // Create pipeline which blends each layer over everything drawn before it. The sRGB
+        // render target makes the GPU blend in linear space.
+        let pipeline = create_pipeline(
+            device,
+            &bind_group_layout,
+            &vs_module,
+            &fs_module,
+            format,
+            Blending::Alpha,
+        );
+
+        // Create pipeline which replaces the render target with the layer blended over its copy
+        let srgb_fs_module = device.create_shader_module(include_spv!("../shaders/layer_srgb.spv"));
+        let srgb_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("pixels_layer_srgb_bind_group_layout"),
+                bindings: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: bindings::TEXTURE,
+                        visibility: wgpu::ShaderStage::FRAGMENT,
+                        ty: wgpu::BindingType::SampledTexture {
+                            component_type: wgpu::TextureComponentType::Float,
+                            multisampled: false,
+                            dimension: wgpu::TextureViewDimension::D2,
+                        },
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: bindings::SAMPLER,
+                        visibility: wgpu::ShaderStage::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler { comparison: false },
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: bindings::TRANSFORM,
+                        visibility: wgpu::ShaderStage::VERTEX,
+                        ty: wgpu::BindingType::UniformBuffer { dynamic: false },
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStage::FRAGMENT,
+                        ty: wgpu::BindingType::SampledTexture {
+                            component_type: wgpu::TextureComponentType::Float,
+                            multisampled: false,
+                            dimension: wgpu::TextureViewDimension::D2,
+                        },
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: wgpu::ShaderStage::FRAGMENT,
+                        ty: wgpu::BindingType::UniformBuffer { dynamic: false },
+                    },
+                ],
+            });
+        let srgb_pipeline = create_pipeline(
+            device,
+            &srgb_bind_group_layout,
+            &vs_module,
+            &srgb_fs_module,
+            format,
+            Blending::Replace,
+        );
+
+        // The shader converts between linear and sRGB colors on render targets with sRGB encoding
+        let srgb_target: f32 = if is_srgb(format) { 1.0 } else { 0.0 };
+        let locals: Vec<u8> = [srgb_target, 0.0, 0.0, 0.0]
+            .iter()
+            .flat_map(|value| value.to_ne_bytes().to_vec())
+            .collect();
+        let srgb_locals = device.create_buffer_with_data(&locals, wgpu::BufferUsage::UNIFORM);
+
+        Layers {
+            layers: Vec::new(),
+            sampler,
+            bind_group_layout,
+            pipeline,
+            srgb_blending: false,
+            format,
+            srgb_bind_group_layout,
+            srgb_pipeline,
+            srgb_locals,
+            backdrop: None,
+        }
+    }
+
+    /// Create a transparent layer of `width` by `height` pixels, drawn over all existing layers.
+    pub(crate) fn add(
+        &mut self,
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        eyes: usize,
+    ) -> LayerId {
+        let extent = wgpu::Extent3d {
+            width,
+            height,
+            depth: 1,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("pixels_layer_texture"),
+            size: extent,
+            array_layer_count: 1,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
+        });
+        let view = texture.create_default_view();
+
+        let uniform_buffers: Vec<_> = (0..eyes)
+            .map(|_| {
+                device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("pixels_layer_transform_buffer"),
+                    size: bindings::TRANSFORM_SIZE,
+                    usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+                })
+            })
+            .collect();
+        let bind_groups = uniform_buffers
+            .iter()
+            .map(|uniform_buffer| {
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("pixels_layer_bind_group"),
+                    layout: &self.bind_group_layout,
+                    bindings: &[
+                        wgpu::Binding {
+                            binding: bindings::TEXTURE,
+                            resource: wgpu::BindingResource::TextureView(&view),
+                        },
+                        wgpu::Binding {
+                            binding: bindings::SAMPLER,
+                            resource: wgpu::BindingResource::Sampler(&self.sampler),
+                        },
+                        wgpu::Binding {
+                            binding: bindings::TRANSFORM,
+                            resource: wgpu::BindingResource::Buffer {
+                                buffer: uniform_buffer,
+                                range: 0..bindings::TRANSFORM_SIZE,
+                            },
+                        },
+                    ],
+                })
+            })
+            .collect();
+
+        self.layers.push(Some(Layer {
+            texture,
+            view,
+            extent,
+            pixels: vec![0; (width * height * 4) as usize],
+            dirty: true,
+            visible: true,
+            uniform_buffers,
+            bind_groups,
+        }));
+
+        LayerId(self.layers.len() - 1)
+    }
+
+    pub(crate) fn remove(&mut self, id: LayerId) {
+        assert!(self.layers[id.0].take().is_some(), "Layer was removed");
+    }
+
+    /// The RGBA pixels of layer `id`, which is uploaded with the next render.
+    pub(crate) fn frame_mut(&mut self, id: LayerId) -> &mut [u8] {
+        let layer = self.get_mut(id);
+        layer.dirty = true;
+
+        &mut layer.pixels
+    }
+
+    pub(crate) fn set_visible(&mut self, id: LayerId, visible: bool) {
+        self.get_mut(id).visible = visible;
+    }
+
+    /// Returns `true` when any layer is composited over the pixel buffer.
+    pub(crate) fn is_visible(&self) -> bool {
+        self.layers.iter().flatten().any(|layer| layer.visible)
+    }
+
+    /// Blend layers in sRGB space instead of linear space, for a render target of `width` by
+    /// `height` pixels.
+    pub(crate) fn set_srgb_blending(
+        &mut self,
+        device: &wgpu::Device,
+        enabled: bool,
+        width: u32,
+        height: u32,
+    ) {
+        self.srgb_blending = enabled;
+        self.backdrop = None;
+        self.resize(device, width, height);
+    }
+
+    /// Returns `true` when visible layers are blended in sRGB space, which needs a copy of the
+    /// render target. See [`Layers::render`].
+    pub(crate) fn reads_render_target(&self) -> bool {
+        self.srgb_blending && self.is_visible()
+    }
+
+    /// Resize the copy of the render target read when blending in sRGB space.
+    pub(crate) fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        if !self.srgb_blending {
+            return;
+        }
+        if let Some(backdrop) = &self.backdrop {
+            if (backdrop.extent.width, backdrop.extent.height) == (width, height) {
+                return;
+            }
+        }
+
+        let extent = wgpu::Extent3d {
+            width,
+            height,
+            depth: 1,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("pixels_layer_backdrop_texture"),
+            size: extent,
+            array_layer_count: 1,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.format,
+            usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
+        });
+        let view = texture.create_default_view();
+
+        self.backdrop = Some(Backdrop {
+            texture,
+            view,
+            extent,
+        });
+    }
+
+    /// Returns `true` when any layer has changed since the last upload.
+    pub(crate) fn is_dirty(&self) -> bool {
+        self.layers.iter().flatten().any(|layer| layer.dirty)
+    }
+
+    /// Encode an upload of every layer which has changed.
+    pub(crate) fn upload(&mut self, device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder) {
+        for layer in self.layers.iter_mut().flatten().filter(|layer| layer.dirty) {
+            let buffer = device.create_buffer_with_data(&layer.pixels, wgpu::BufferUsage::COPY_SRC);
+            encoder.copy_buffer_to_texture(
+                wgpu::BufferCopyView {
+                    buffer: &buffer,
+                    offset: 0,
+                    bytes_per_row: layer.extent.width * 4,
+                    rows_per_image: layer.extent.height,
+                },
+                wgpu::TextureCopyView {
+                    texture: &layer.texture,
+                    mip_level: 0,
+                    array_layer: 0,
+                    origin: wgpu::Origin3d { x: 0, y: 0, z: 0 },
+                },
+                layer.extent,
+            );
+            layer.dirty = false;
+        }
+    }
+
+    /// Encode the composition of all visible layers, in order, over `render_target`. Each layer
+    /// is scaled by its own factor to cover the pixel buffer of `texture_size` pixels.
+    ///
+    /// Blending in sRGB space copies `render_texture`, the texture of `render_target`, before
+    /// each layer. Layers are blended in linear space when it is not given.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn render(
+        &self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        render_target: &TextureView,
+        render_texture: Option<&wgpu::Texture>,
+        scaling: &Scaling,
+        texture_size: (f32, f32),
+        screen_size: (f32, f32),
+    ) {
+        let visible: Vec<_> = self
+            .layers
+            .iter()
+            .flatten()
+            .filter(|layer| layer.visible)
+            .collect();
+        if visible.is_empty() {
+            return;
+        }
+
+        // Place every layer like the pixel buffer, regardless of its resolution, so that all layers
+        // line up in every scaling mode
+        let matrices: Vec<_> = (0..scaling.eyes())
+            .map(|eye| scaling.eye_matrix(texture_size, screen_size, eye))
+            .collect();
+        let scissors: Vec<_> = (0..scaling.eyes())
+            .map(|eye| scaling.scissor(texture_size, screen_size, eye))
+            .collect();
+        for layer in visible.iter() {
+            for (matrix, uniform_buffer) in matrices.iter().zip(layer.uniform_buffers.iter()) {
+                let temp_buf =
+                    device.create_buffer_with_data(matrix.as_bytes(), wgpu::BufferUsage::COPY_SRC);
+                encoder.copy_buffer_to_buffer(
+                    &temp_buf,
+                    0,
+                    uniform_buffer,
+                    0,
+                    bindings::TRANSFORM_SIZE,
+                );
+            }
+        }
+
+        if let (true, Some(render_texture), Some(backdrop)) =
+            (self.srgb_blending, render_texture, &self.backdrop)
+        {
+            for layer in visible.iter() {
+                self.render_srgb(
+                    device,
+                    encoder,
+                    render_target,
+                    render_texture,
+                    backdrop,
+                    layer,
+                    &scissors,
+                );
+            }
+            return;
+        }
+
+        let mut rpass = begin_render_pass(encoder, render_target);
+        rpass.set_pipeline(&self.pipeline);
+        for layer in visible.iter() {
+            for (bind_group, scissor) in layer.bind_groups.iter().zip(scissors.iter()) {
+                set_scissor_rect(&mut rpass, *scissor);
+                rpass.set_bind_group(0, bind_group, &[]);
+                rpass.draw(0..6, 0..1);
+            }
+        }
+    }
+
+    /// Encode a copy of the render target, and the layer blended over the copy in sRGB space.
+    #[allow(clippy::too_many_arguments)]
+    fn render_srgb(
+        &self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        render_target: &TextureView,
+        render_texture: &wgpu::Texture,
+        backdrop: &Backdrop,
+        layer: &Layer,
+        scissors: &[Option<(u32, u32, u32, u32)>],
+    ) {
+        encoder.copy_texture_to_texture(
+            wgpu::TextureCopyView {
+                texture: render_texture,
+                mip_level: 0,
+                array_layer: 0,
+                origin: wgpu::Origin3d { x: 0, y: 0, z: 0 },
+            },
+            wgpu::TextureCopyView {
+                texture: &backdrop.texture,
+                mip_level: 0,
+                array_layer: 0,
+                origin: wgpu::Origin3d { x: 0, y: 0, z: 0 },
+            },
+            backdrop.extent,
+        );
+
+        let bind_groups: Vec<_> = layer
+            .uniform_buffers
+            .iter()
+            .map(|uniform_buffer| {
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("pixels_layer_srgb_bind_group"),
+                    layout: &self.srgb_bind_group_layout,
+                    bindings: &[
+                        wgpu::Binding {
+                            binding: bindings::TEXTURE,
+                            resource: wgpu::BindingResource::TextureView(&layer.view),
+                        },
+                        wgpu::Binding {
+                            binding: bindings::SAMPLER,
+                            resource: wgpu::BindingResource::Sampler(&self.sampler),
+                        },
+                        wgpu::Binding {
+                            binding: bindings::TRANSFORM,
+                            resource: wgpu::BindingResource::Buffer {
+                                buffer: uniform_buffer,
+                                range: 0..bindings::TRANSFORM_SIZE,
+                            },
+                        },
+                        wgpu::Binding {
+                            binding: 3,
+                            resource: wgpu::BindingResource::TextureView(&backdrop.view),
+                        },
+                        wgpu::Binding {
+                            binding: 4,
+                            resource: wgpu::BindingResource::Buffer {
+                                buffer: &self.srgb_locals,
+                                range: 0..16,
+                            },
+                        },
+                    ],
+                })
+            })
+            .collect();
+
+        let mut rpass = begin_render_pass(encoder, render_target);
+        rpass.set_pipeline(&self.srgb_pipeline);
+        for (bind_group, scissor) in bind_groups.iter().zip(scissors.iter()) {
+            set_scissor_rect(&mut rpass, *scissor);
+            rpass.set_bind_group(0, bind_group, &[]);
+            rpass.draw(0..6, 0..1);
+        }
+    }
+
+    fn get_mut(&mut self, id: LayerId) -> &mut Layer {
+        self.layers[id.0].as_mut().expect("Layer was removed")
+    }
+}
+
+fn begin_render_pass<'a>(
+    encoder: &'a mut wgpu::CommandEncoder,
+    render_target: &'a TextureView,
+) -> wgpu::RenderPass<'a> {
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+            attachment: render_target,
+            resolve_target: None,
+            load_op: wgpu::LoadOp::Load,
+            store_op: wgpu::StoreOp::Store,
+            clear_color: wgpu::Color::TRANSPARENT,
+        }],
+        depth_stencil_attachment: None,
+    })
+}
+
+// Keep each eye in its own half of the render target, and hide the overscan
+fn set_scissor_rect(rpass: &mut wgpu::RenderPass<'_>, scissor: Option<(u32, u32, u32, u32)>) {
+    if let Some((x, y, width, height)) = scissor {
+        rpass.set_scissor_rect(x, y, width, height);
+    }
+}
@@ -21,34 +21,195 @@
 //! The order of precedence for choosing a power preference is:
 //!
 //! 1. Application's specific adapter request through [`PixelsBuilder::request_adapter_options`]
-//! 2. `PIXELS_HIGH_PERF`
-//! 3. `PIXELS_LOW_POWER`
-//! 4. `wgpu` default power preference (usually low power)
+//! 2. Application's priority hint through [`PixelsBuilder::priority`]
+//! 3. `PIXELS_HIGH_PERF`
+//! 4. `PIXELS_LOW_POWER`
+//! 5. `wgpu` default power preference (usually low power)
+//!
+//! Libraries can opt out of the variables with [`PixelsBuilder::ignore_env_power_preference`],
+//! and applications can parse them explicitly with [`power_preference_from_env`]. The variables
+//! are only read with the `env` feature.
+//!
+//! # Optional features
+//!
+//! * `renderdoc`: Enables [`Pixels::trigger_gpu_capture`] for capturing frames programmatically
+//!   with [RenderDoc](https://renderdoc.org/).
+//! * `effects`: Enables the [`effects`] module, with built-in render passes like a CRT
+//!   simulation, pixel art outlines, and an adjustable sharpness filter.
+//! * `capture`: Enables the [`capture`] module, for recording rendered frames to animated GIF
+//!   and PNG files.
+//! * `env` (enabled by default): Reads the [environment variables](#environment-variables).
+//!   Without it, the environment is never accessed, e.g. for sandboxed or reproducible builds,
+//!   and the power preference is only configured with the [`PixelsBuilder`].
+//!
+//! # WebAssembly
+//!
+//! The pixel buffer renders with WebGPU on `wasm32-unknown-unknown`. Create the surface from a
+//! canvas with a windowing library that supports the web, like `winit`, and build the pixel
+//! buffer with [`PixelsBuilder::build_async`], because the browser does not allow blocking.
+//! For the same reason, methods which block until the GPU reads back a frame, like
+//! [`Pixels::read_offscreen_frame`] and [`Pixels::render_thumbnail`], cannot be used on the web;
+//! use [`Pixels::request_screenshot`] instead. See the `minimal-web` example.
 
 #![deny(clippy::all)]
 #![forbid(unsafe_code)]
 
-use std::cell::RefCell;
+use instant::Instant;
+#[cfg(feature = "env")]
 use std::env;
-use std::rc::Rc;
+use std::fmt;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
+use crate::animation::Animation;
+pub use crate::back_frame::BackFrame;
+pub use crate::background::BackgroundThrottle;
+pub use crate::capabilities::{CapabilitiesReport, Downgrade};
+#[cfg(feature = "capture")]
+use crate::capture::{Recording, RecordingFormat, RecordingSource};
+use crate::clock::Clock;
+pub use crate::compute::{BoxedComputePass, ComputePass};
+use crate::compute::{ComputePassFactory, ComputeStage};
+use crate::convert::FrameConverter;
+pub use crate::convert::FrameFormat;
+use crate::crash::lock;
+pub use crate::crash::CrashDump;
+use crate::dirty::DirtyRows;
+pub use crate::dirty::TrackedFrame;
+use crate::events::EventQueue;
+pub use crate::events::PixelsEvent;
+pub use crate::fence::SubmissionFence;
+pub use crate::frame::{Frame, Pixel, RowMut};
+pub use crate::frame_queue::FrameQueue;
+use crate::gizmo::GizmoRenderer;
+pub use crate::gizmo::Gizmos;
+#[cfg(feature = "renderdoc")]
+use crate::gpu_capture::GpuCapture;
+use crate::history::FrameHistory;
+use crate::insertion::Dimmer;
+pub use crate::insertion::FrameInsertion;
+use crate::interpolation::Interpolator;
+pub use crate::layer::LayerId;
+use crate::layer::Layers;
 pub use crate::macros::*;
-pub use crate::render_pass::{BoxedRenderPass, Device, Queue, RenderPass};
-use crate::renderers::Renderer;
+pub use crate::magnifier::Magnifier;
+pub use crate::multi::PixelsMulti;
+use crate::pacing::PresentTimer;
+use crate::palette::{Palette, PaletteHandle};
+use crate::pipeline::Blending;
+pub use crate::position::{EdgeBehavior, PixelPosition};
+pub use crate::preset::{AspectPreset, Preset};
+pub use crate::priority::Priority;
+pub use crate::readback::Screenshot;
+use crate::readback::Screenshots;
+pub use crate::render_pass::{
+    BoxedRenderPass, Device, FrameContext, InputSampler, PassInputs, PixelsContext, Queue,
+    RenderPass, RenderPassOptions, RenderPasses, SurfaceResize, UserDataBuffer,
+};
+use crate::render_pass::{LabeledRenderPass, PassPredicate};
+use crate::rendered::RenderedFrame;
+pub use crate::renderers::{BufferDimension, Overscan, ScalingMode, ScalingShaders, SideBySide};
+use crate::renderers::{Crop, Renderer, Scaling, ScalingOptions};
+use crate::source::copy_frame;
+pub use crate::source::{ExternalFrameSource, FrameInfo, SourceFormat};
+use crate::staging::StagingRing;
+pub use crate::swizzle::{Swizzle, SwizzleComponent};
+use crate::thumbnail::render_thumbnail;
+pub use crate::uploader::TextureUploader;
+use crate::uploader::UploadTarget;
 use thiserror::Error;
 pub use wgpu;
 use wgpu::{Extent3d, TextureView};
 
+mod animation;
+mod back_frame;
+mod background;
+pub mod bench;
+pub mod bindings;
+mod capabilities;
+#[cfg(feature = "capture")]
+pub mod capture;
+mod clock;
+pub mod color;
+mod compute;
+mod convert;
+mod crash;
+pub mod debug;
+mod dirty;
+#[cfg(feature = "effects")]
+pub mod effects;
+mod events;
+mod fence;
+mod frame;
+mod frame_queue;
+mod gizmo;
+#[cfg(feature = "renderdoc")]
+mod gpu_capture;
+mod history;
+mod insertion;
+mod interpolation;
+mod layer;
 mod macros;
+mod magnifier;
+mod multi;
+mod pacing;
+pub mod palette;
+mod pipeline;
+mod position;
+mod preset;
+mod priority;
+mod readback;
 mod render_pass;
+mod rendered;
 mod renderers;
+mod source;
+mod staging;
+mod swizzle;
+mod thumbnail;
+mod uploader;
+
+/// Commands recorded by the application in the same submission as the frame.
+enum Overlay<'a> {
+    /// Draws over the surface, see [`Pixels::render_with`].
+    Over(Box<dyn FnOnce(&mut wgpu::CommandEncoder, &TextureView, &PixelsContext<'_>) + 'a>),
+    /// Executes the render passes itself, see [`Pixels::render_with_passes`].
+    Around(Box<dyn FnOnce(&mut wgpu::CommandEncoder, &TextureView, &mut RenderPasses<'_>) + 'a>),
+}
+
+type RenderPassFactory =
+    Box<dyn Fn(Device, Queue, &PassInputs<'_>) -> BoxedRenderPass + Send + Sync>;
 
-type RenderPassFactory = Box<dyn Fn(Device, Queue, &TextureView, &Extent3d) -> BoxedRenderPass>;
+/// A render pass factory added to the builder, with the options it was added with.
+struct PassFactory {
+    label: String,
+    load_op: wgpu::LoadOp,
+    sampler: InputSampler,
+    factory: RenderPassFactory,
+}
+
+/// An axis-aligned rectangle in physical pixel units.
+///
+/// The origin is the top-left corner of the surface.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Rect {
+    /// Distance from the left edge of the surface.
+    pub x: f32,
+    /// Distance from the top edge of the surface.
+    pub y: f32,
+    /// Width of the rectangle.
+    pub width: f32,
+    /// Height of the rectangle.
+    pub height: f32,
+}
 
-/// A logical texture for a window surface.
+/// A logical texture for a window surface, or for offscreen rendering without a window.
 #[derive(Debug)]
 pub struct SurfaceTexture {
-    surface: wgpu::Surface,
+    // `None` for offscreen rendering
+    surface: Option<wgpu::Surface>,
     width: u32,
     height: u32,
 }
@@ -56,41 +217,217 @@ pub struct SurfaceTexture {
 /// Represents a 2D pixel buffer with an explicit image resolution.
 ///
 /// See [`PixelsBuilder`] for building a customized pixel buffer.
+///
+/// The pixel buffer is `Send`, so it can be built on one thread and rendered on another.
+///
+/// # Examples
+///
+/// ```no_run
+/// use pixels::Pixels;
+///
+/// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+/// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+/// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+///
+/// let render_thread = std::thread::spawn(move || loop {
+///     // ... Draw the next frame
+///     pixels.render()?;
+/// #   break Ok::<(), pixels::Error>(());
+/// });
+/// # render_thread.join().unwrap()?;
+/// # Ok::<(), pixels::Error>(())
+/// ```
 #[derive(Debug)]
 pub struct Pixels {
     // WGPU state
-    device: Rc<wgpu::Device>,
-    queue: Rc<RefCell<wgpu::Queue>>,
-    swap_chain: wgpu::SwapChain,
+    device: Device,
+    queue: Queue,
+    swap_chain: Option<wgpu::SwapChain>,
     surface_texture: SurfaceTexture,
+    // Render passes draw into this texture instead of the swap chain texture while the frame is
+    // read back or presented again, see `Pixels::retains_frame`. Without a window, it replaces
+    // the swap chain.
+    rendered_frame: Option<RenderedFrame>,
+    // Set when the last presented frame was drawn into the rendered frame
+    frame_retained: bool,
     present_mode: wgpu::PresentMode,
+    // Format of the swap chain and the rendered frame, which all render passes draw into
+    surface_format: wgpu::TextureFormat,
+
+    // Set when the surface must be redrawn even though the pixel buffer did not change
+    redraw: bool,
+
+    // Requested options which were downgraded by `build`
+    capabilities: CapabilitiesReport,
+
+    // Color of the borders around the scaled pixel buffer
+    clear_color: wgpu::Color,
+
+    // The scaling renderer only copies the texture, so it may be replaced with a texture copy
+    // when nothing else is drawn; see `Pixels::can_pass_through`
+    pass_through: bool,
+
+    // Frame timing feedback for just-in-time presentation
+    present_timer: PresentTimer,
+    present_margin: Option<Duration>,
+
+    // Frames inserted after each rendered frame to reduce motion blur
+    frame_insertion: FrameInsertion,
+    dimmer: Option<Dimmer>,
+
+    // Rendering is throttled while the pixel buffer is backgrounded
+    background: bool,
+    background_throttle: BackgroundThrottle,
+    last_render: Option<Instant>,
+
+    // Blends the two most recent source frames for low-rate sources
+    interpolator: Option<Interpolator>,
+
+    // Time source for animations and interpolation, fixed in deterministic mode
+    clock: Clock,
+    // Frame timing for render passes
+    frame_index: u64,
+    last_frame_time: Option<Duration>,
 
     // List of render passes
-    renderers: Vec<BoxedRenderPass>,
+    renderers: Vec<LabeledRenderPass>,
+
+    // Pixel buffers composited over the main pixel buffer, right after the scaling renderer at
+    // this index in `renderers`
+    layers: Layers,
+    scaling_renderer: usize,
+    // Scaling mode and filter, shared with the scaling renderer for changes at runtime
+    scaling_options: Arc<Mutex<ScalingOptions>>,
+
+    // Debug shapes drawn over all render passes
+    gizmos: Gizmos,
+    gizmo_renderer: GizmoRenderer,
 
-    // Texture state for the texel upload
-    texture: wgpu::Texture,
+    // Texture state for the texel upload, shared with `TextureUploader` handles
+    texture: Arc<wgpu::Texture>,
+    upload_target: Arc<Mutex<UploadTarget>>,
     texture_extent: wgpu::Extent3d,
+    texture_format: wgpu::TextureFormat,
     texture_format_size: u32,
+    dimension: BufferDimension,
+    scaling: Scaling,
     pixels: Vec<u8>,
+    dirty: DirtyRows,
+    // Rows of the pixel buffer are stored bottom to top, and flipped during the upload
+    bottom_up_rows: bool,
+    // Colors are multiplied by alpha during the upload
+    premultiply_alpha: bool,
+    // Reused buffers for uploading the pixel buffer
+    staging: StagingRing,
+
+    // Conversion from the frame format to the texture format on the GPU
+    frame_format: FrameFormat,
+    converter: Option<FrameConverter>,
+    palette_handle: PaletteHandle,
+
+    // Back buffer for double buffering, created when it is first requested
+    back_frame: Option<BackFrame>,
+
+    // Staging memory for frames from an `ExternalFrameSource` which need conversion
+    source_buffer: Vec<u8>,
+
+    // Frames cycled on the GPU instead of the pixel buffer
+    animation: Option<Animation>,
+
+    // Copy of the texture from the previous frame, when a render pass needs it
+    previous_texture: Option<wgpu::Texture>,
+    // The number in the label of the next unlabeled render pass added at runtime
+    next_pass_label: usize,
+
+    // Compute passes which fill the texture instead of the pixel buffer
+    compute: Option<ComputeStage>,
+
+    // The crop configured with the builder, restored by `reset_source_rect`
+    overscan_crop: Crop,
+
+    // Uniform buffer for the per-frame user data, when a render pass needs it
+    user_data: Option<UserDataBuffer>,
+    user_data_size: wgpu::BufferAddress,
+    pending_user_data: Option<Vec<u8>>,
+
+    // Copies of recently rendered frames for post-mortem debugging
+    frame_history: Arc<Mutex<FrameHistory>>,
+    crash_dump: Option<CrashDump>,
+    screenshots: Screenshots,
+
+    // Programmatic frame captures with RenderDoc
+    #[cfg(feature = "renderdoc")]
+    gpu_capture: GpuCapture,
+
+    // Frames recorded to a file, until the recording is stopped
+    #[cfg(feature = "capture")]
+    recording: Option<Recording>,
 
     // The inverse of the scaling matrix used by the renderer
     // Used to convert physical coordinates back to pixel coordinates (for the mouse)
     scaling_matrix_inverse: ultraviolet::Mat4,
+
+    // Changes to the internal state, until the application polls them
+    events: EventQueue,
 }
 
+// Fail the build when a field makes `Pixels` lose `Send`. Check this with `--all-features`, since
+// some fields only exist with optional features.
+const _: () = {
+    fn assert_send<T: Send>() {}
+
+    #[allow(dead_code)]
+    fn assert_pixels_send() {
+        assert_send::<Pixels>();
+    }
+
+    #[allow(dead_code)]
+    fn assert_uploader_send() {
+        assert_send::<TextureUploader>();
+    }
+};
+
 /// A builder to help create customized pixel buffers.
 pub struct PixelsBuilder<'req> {
     request_adapter_options: Option<wgpu::RequestAdapterOptions<'req>>,
     device_descriptor: wgpu::DeviceDescriptor,
     backend: wgpu::BackendBit,
+    shared_device: Option<(wgpu::AdapterInfo, Device, Queue)>,
     width: u32,
     height: u32,
     pixel_aspect_ratio: f64,
     present_mode: wgpu::PresentMode,
+    defer_swap_chain: bool,
+    priority: Priority,
+    background_throttle: BackgroundThrottle,
     surface_texture: SurfaceTexture,
+    surface_format: wgpu::TextureFormat,
     texture_format: wgpu::TextureFormat,
-    renderer_factories: Vec<RenderPassFactory>,
+    dimension: BufferDimension,
+    scaling_mode: ScalingMode,
+    filter_mode: wgpu::FilterMode,
+    side_by_side: Option<SideBySide>,
+    overscan: Overscan,
+    clear_color: wgpu::Color,
+    frame_format: FrameFormat,
+    bottom_up_rows: bool,
+    swizzle: Swizzle,
+    scaling_shaders: ScalingShaders,
+    alpha_blending: bool,
+    premultiply_alpha: bool,
+    pass_through: bool,
+    renderer_factories: Vec<PassFactory>,
+    background_factories: Vec<PassFactory>,
+    compute_factories: Vec<ComputePassFactory>,
+    frame_history: usize,
+    present_margin: Option<Duration>,
+    frame_insertion: FrameInsertion,
+    interpolation: bool,
+    previous_frame: bool,
+    user_data: bool,
+    user_data_size: wgpu::BufferAddress,
+    deterministic: bool,
+    env_power_preference: bool,
 }
 
 /// All the ways in which creating a pixel buffer can fail.
@@ -100,8 +437,90 @@ pub enum Error {
     #[error("No suitable `wgpu::Adapter` found")]
     AdapterNotFound,
     /// Equivalent to [`wgpu::TimeOut`]
+    ///
+    /// The swap chain is recreated and asked for a texture once more before this error is
+    /// returned, so the error is not caused by an outdated swap chain alone.
     #[error("The GPU timed out when attempting to acquire the next texture or if a previous output is still alive.")]
     Timeout,
+    /// Equivalent to [`wgpu::BufferAsyncErr`]
+    #[error("Failed to read the rendered frame back from the GPU.")]
+    ReadbackFailed,
+    /// The pixel buffer size cannot be used with the builder options
+    #[error("The pixel buffer size {width}x{height} is invalid: {reason}")]
+    InvalidDimensions {
+        /// Width of the pixel buffer.
+        width: u32,
+        /// Height of the pixel buffer.
+        height: u32,
+        /// Why the size is invalid.
+        reason: &'static str,
+    },
+    /// The texture format cannot be used for the pixel buffer with the builder options
+    #[error("The texture format `{0:?}` is not supported: {1}")]
+    UnsupportedTextureFormat(wgpu::TextureFormat, &'static str),
+    /// The frame format cannot be used for the pixel buffer
+    #[error("The frame format `{0:?}` is not supported")]
+    UnsupportedFrameFormat(FrameFormat),
+    /// The pixel buffer is too small for the configured [`Overscan`]
+    #[error("The {width}x{height} pixel buffer is too small for the overscan")]
+    BufferTooSmall {
+        /// Width of the pixel buffer.
+        width: u32,
+        /// Height of the pixel buffer.
+        height: u32,
+    },
+}
+
+/// An error returned by [`PixelsBuilder::try_build`], with the builder that failed.
+///
+/// The builder still owns the [`SurfaceTexture`], so the application can adjust the options and
+/// try again, e.g. with another backend or a smaller pixel buffer.
+pub struct BuildError<'req> {
+    error: Error,
+    builder: PixelsBuilder<'req>,
+}
+
+impl<'req> BuildError<'req> {
+    /// The reason the pixel buffer could not be built.
+    pub fn error(&self) -> &Error {
+        &self.error
+    }
+
+    /// Take the builder back, to adjust the options and try again.
+    pub fn into_builder(self) -> PixelsBuilder<'req> {
+        self.builder
+    }
+
+    /// Take the error and the builder apart.
+    pub fn into_parts(self) -> (Error, PixelsBuilder<'req>) {
+        (self.error, self.builder)
+    }
+}
+
+impl fmt::Debug for BuildError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BuildError")
+            .field("error", &self.error)
+            .finish()
+    }
+}
+
+impl fmt::Display for BuildError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl std::error::Error for BuildError<'_> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<BuildError<'_>> for Error {
+    fn from(error: BuildError<'_>) -> Error {
+        error.error
+    }
 }
 
 impl SurfaceTexture {
@@ -137,7 +556,47 @@ impl SurfaceTexture {
         assert!(height > 0);
 
         SurfaceTexture {
-            surface,
+            surface: Some(surface),
+            width,
+            height,
+        }
+    }
+
+    /// Create a logical texture for rendering without a window, e.g. in CI tests or for batch
+    /// rendering animation frames.
+    ///
+    /// Frames are rendered into a GPU texture with the given size instead of a swap chain. Read
+    /// the rendered frame with [`Pixels::read_offscreen_frame`]. Frame insertion and Vsync have
+    /// no effect offscreen.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pixels::{Pixels, SurfaceTexture};
+    ///
+    /// let surface_texture = SurfaceTexture::offscreen(640, 480);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    ///
+    /// for frame in 0..60 {
+    ///     // ... Draw the animation frame
+    ///     pixels.render()?;
+    ///     let rgba = pixels.read_offscreen_frame()?;
+    ///     // ... Save `rgba` as frame number `frame`
+    /// }
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics when `width` or `height` are 0.
+    ///
+    /// [`Pixels::read_offscreen_frame`]: ./struct.Pixels.html#method.read_offscreen_frame
+    pub fn offscreen(width: u32, height: u32) -> SurfaceTexture {
+        assert!(width > 0);
+        assert!(height > 0);
+
+        SurfaceTexture {
+            surface: None,
             width,
             height,
         }
@@ -159,117 +618,299 @@ impl Pixels {
     ///
     /// # Errors
     ///
-    /// Returns an error when a [`wgpu::Adapter`] cannot be found.
-    ///
-    /// # Panics
-    ///
-    /// Panics when `width` or `height` are 0.
+    /// Returns an error when a [`wgpu::Adapter`] cannot be found, or when `width` or `height`
+    /// are 0.
     pub fn new(width: u32, height: u32, surface_texture: SurfaceTexture) -> Result<Pixels, Error> {
         PixelsBuilder::new(width, height, surface_texture).build()
     }
 
     /// Resize the surface upon which the pixel buffer is rendered.
     ///
-    /// This does not resize the pixel buffer. The pixel buffer will be fit onto the surface as
-    /// best as possible by scaling to the nearest integer, e.g. 2x, 3x, 4x, etc.
+    /// This does not resize the pixel buffer. The pixel buffer will be fit onto the surface
+    /// according to the [`ScalingMode`]. By default it is scaled to the nearest integer, e.g. 2x,
+    /// 3x, 4x, etc.
     ///
     /// Call this method in response to a resize event from your window manager. The size expected
-    /// is in physical pixel units.
+    /// is in physical pixel units. Also call it when the DPI scale factor changes, even if the
+    /// physical size stays the same; all render passes are notified through
+    /// [`RenderPass::surface_resized`] on every call.
     pub fn resize(&mut self, width: u32, height: u32) {
-        // TODO: Call `update_bindings` on each render pass to create a texture chain
-
         // Update SurfaceTexture dimensions
+        let old_size = (self.surface_texture.width, self.surface_texture.height);
         self.surface_texture.width = width;
         self.surface_texture.height = height;
 
+        // Recreate the swap chain, and the rendered frame
+        self.create_render_target();
+        self.update_scaling(old_size);
+    }
+
+    /// Create the swap chain, and the rendered frame, with the size of the surface texture.
+    ///
+    /// Window surfaces only resize a rendered frame which was already created by
+    /// [`Pixels::create_rendered_frame`].
+    fn create_render_target(&mut self) {
+        let width = self.surface_texture.width;
+        let height = self.surface_texture.height;
+        if let Some(surface) = &self.surface_texture.surface {
+            self.swap_chain = Some(self.device.create_swap_chain(
+                surface,
+                &wgpu::SwapChainDescriptor {
+                    usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+                    format: self.surface_format,
+                    width,
+                    height,
+                    present_mode: self.present_mode,
+                },
+            ));
+        }
+        if self.surface_texture.surface.is_none() || self.rendered_frame.is_some() {
+            self.create_rendered_frame();
+        }
+        self.layers.resize(&self.device, width, height);
+        self.events
+            .push(PixelsEvent::SwapChainRecreated { width, height });
+    }
+
+    /// Create the rendered frame, unless it already has the size of the surface texture.
+    ///
+    /// The rendered frame is kept when its size did not change, so it can still be presented
+    /// again after the swap chain was recreated.
+    fn create_rendered_frame(&mut self) {
+        let width = self.surface_texture.width;
+        let height = self.surface_texture.height;
+        match &self.rendered_frame {
+            Some(rendered_frame) if rendered_frame.size() == (width, height) => (),
+            _ => {
+                self.rendered_frame = Some(RenderedFrame::new(
+                    &self.device,
+                    width,
+                    height,
+                    self.surface_format,
+                ));
+                self.frame_retained = false;
+            }
+        }
+    }
+
+    /// Recompute the scaling for the current surface size, and notify all render passes.
+    fn update_scaling(&mut self, old_size: (u32, u32)) {
+        self.redraw = true;
+
         // Update ScalingMatrix for mouse transformation
-        self.scaling_matrix_inverse = renderers::ScalingMatrix::new(
-            (
-                self.texture_extent.width as f32,
-                self.texture_extent.height as f32,
-            ),
-            (width as f32, height as f32),
-        )
-        .transform
-        .inversed();
-
-        // Recreate the swap chain
-        self.swap_chain = self.device.create_swap_chain(
-            &self.surface_texture.surface,
-            &wgpu::SwapChainDescriptor {
-                usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
-                format: wgpu::TextureFormat::Bgra8UnormSrgb,
-                width: self.surface_texture.width,
-                height: self.surface_texture.height,
-                present_mode: self.present_mode,
-            },
-        );
+        let scaling_matrix = self.scaling_matrix();
+        self.scaling_matrix_inverse = scaling_matrix.transform.inversed();
+        self.events
+            .scaling_updated(scaling_matrix.scale, scaling_matrix.destination);
+        let resize = SurfaceResize {
+            old_size,
+            new_size: (self.surface_texture.width, self.surface_texture.height),
+            scale: scaling_matrix.scale,
+            destination: scaling_matrix.destination,
+        };
 
         // Update state for all render passes
-        let mut encoder = self
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        let device = &self.device;
+        let command_buffers: Vec<_> = self
+            .renderers
+            .iter_mut()
+            .map(|renderer| {
+                let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some(&renderer.label),
+                });
+                renderer.render_pass.surface_resized(&mut encoder, &resize);
+
+                encoder.finish()
+            })
+            .collect();
+
+        lock(&self.queue).submit(&command_buffers);
+    }
+
+    /// Resize the pixel buffer itself.
+    ///
+    /// This is for applications which switch between resolutions at runtime, like emulators with
+    /// multiple video modes (e.g. 256x224 and 512x448). The texture is recreated, the pixel buffer
+    /// is reallocated and cleared to zero, the scaling is updated for the new size, and
+    /// [`RenderPass::update_inputs`] is called on every render pass with the new texture. The
+    /// copy of the previous frame is recreated with the new size too, and handed to the render
+    /// passes along with the new texture.
+    ///
+    /// Any animation started with [`Pixels::set_animation`] is stopped, and the frame history is
+    /// cleared. The frame format and palette are kept.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use pixels::Pixels;
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let mut pixels = Pixels::new(256, 224, surface_texture)?;
+    ///
+    /// // The emulated console switched to its high resolution mode
+    /// pixels.resize_buffer(512, 448);
+    /// assert_eq!(pixels.get_frame().len(), 512 * 448 * 4);
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics when `width` or `height` are 0, when the height of a [`BufferDimension::D1`]
+    /// buffer is not 1, or when the width of a [`SideBySide`] split buffer is odd.
+    pub fn resize_buffer(&mut self, width: u32, height: u32) {
+        assert!(width > 0);
+        assert!(height > 0);
+        if self.dimension == BufferDimension::D1 {
+            assert_eq!(height, 1);
+        }
+        if let Some(side_by_side) = self.scaling.side_by_side {
+            assert!(!side_by_side.split_buffer || width % 2 == 0);
+        }
+
+        // Recreate the texture
+        self.texture_extent = wgpu::Extent3d {
+            width,
+            height,
+            depth: 1,
+        };
+        self.texture = Arc::new(self.device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: self.texture_extent,
+            array_layer_count: 1,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.texture_format,
+            usage: wgpu::TextureUsage::SAMPLED
+                | wgpu::TextureUsage::COPY_DST
+                | wgpu::TextureUsage::COPY_SRC,
+        }));
+        self.update_upload_target();
+        let texture_view = self.texture.create_default_view();
+        if self.interpolator.is_some() {
+            self.interpolator = Some(Interpolator::new(
+                &self.device,
+                self.texture_format,
+                self.texture_extent,
+            ));
+        }
+        if self.previous_texture.is_some() {
+            self.previous_texture = Some(create_previous_texture(
+                &self.device,
+                self.texture_extent,
+                self.texture_format,
+            ));
+        }
+
+        // Reallocate the pixel buffer and everything sized to it
+        if let Some(converter) = &self.converter {
+            self.converter = Some(converter.resized(&self.device, width, height));
+        }
+        if let Some(compute) = &mut self.compute {
+            compute.resize(&self.device, self.texture_extent, self.texture_format_size);
+        }
+        let capacity = match self.compute {
+            Some(_) => 0,
+            None => self
+                .frame_format
+                .frame_size(width, height, self.texture_format_size),
+        };
+        self.pixels.clear();
+        self.pixels.resize_with(capacity, Default::default);
+        self.dirty = DirtyRows::new(height);
+        self.animation = None;
+        lock(&self.frame_history).clear();
+        if let Some(crash_dump) = &self.crash_dump {
+            crash_dump.resize(width, height);
+        }
+        if let Some(back_frame) = &self.back_frame {
+            back_frame.reset(&self.pixels);
+        }
+
+        // Rebind the new texture
+        let texture_view = match &self.interpolator {
+            Some(interpolator) => interpolator.view(),
+            None => texture_view,
+        };
+        let previous_view = self
+            .previous_texture
+            .as_ref()
+            .map(|texture| texture.create_default_view());
         for renderer in self.renderers.iter_mut() {
-            renderer.resize(&mut encoder, width, height);
+            renderer.render_pass.update_inputs(&PassInputs {
+                texture: &texture_view,
+                texture_size: &self.texture_extent,
+                surface_format: self.surface_format,
+                sampler: renderer.sampler,
+                previous_frame: previous_view.as_ref(),
+                user_data: self.user_data.as_ref(),
+            });
         }
+        self.gizmo_renderer =
+            GizmoRenderer::new(&self.device, &self.texture_extent, self.surface_format);
 
-        self.queue.borrow_mut().submit(&[encoder.finish()]);
+        self.events
+            .push(PixelsEvent::BufferResized { width, height });
+
+        // Update the scaling for the new size
+        self.resize(self.surface_texture.width, self.surface_texture.height);
     }
 
     /// Draw this pixel buffer to the configured [`SurfaceTexture`].
     ///
-    /// This executes all render passes in sequence. See [`RenderPass`].
+    /// This executes all render passes in sequence. See [`RenderPass`]. When
+    /// [`PixelsBuilder::frame_insertion`] is configured, the inserted frames are presented before
+    /// this method returns.
     ///
     /// # Errors
     ///
     /// Returns an error when [`wgpu::SwapChain::get_next_texture`] times out.
     pub fn render(&mut self) -> Result<(), Error> {
-        // TODO: Center frame buffer in surface
-        let frame = self
-            .swap_chain
-            .get_next_texture()
-            .map_err(|_| Error::Timeout)?;
-        let mut encoder = self
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        self.render_frame(None)
+    }
 
-        // Update the pixel buffer texture view
-        let mapped = self.device.create_buffer_mapped(&wgpu::BufferDescriptor {
-            label: None,
-            size: self.pixels.len() as u64,
-            usage: wgpu::BufferUsage::COPY_SRC,
-        });
-        mapped.data.copy_from_slice(&self.pixels);
-        let buffer = mapped.finish();
+    /// Draw this pixel buffer to the configured [`SurfaceTexture`], with new per-frame user data.
+    ///
+    /// `data` is uploaded to the [`UserDataBuffer`] before any render pass is executed, and it
+    /// stays in the buffer until the next call to this method. Plain-old-data structs can be
+    /// converted to bytes with crates like `bytemuck`, as long as their layout matches the
+    /// uniform block in the shaders. The data is ignored when no render pass requested
+    /// [`RenderPassOptions::user_data`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when [`wgpu::SwapChain::get_next_texture`] times out.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `data` is larger than the [`PixelsBuilder::user_data_size`].
+    pub fn render_with_data(&mut self, data: &[u8]) -> Result<(), Error> {
+        assert!(data.len() as wgpu::BufferAddress <= self.user_data_size);
 
-        encoder.copy_buffer_to_texture(
-            wgpu::BufferCopyView {
-                buffer: &buffer,
-                offset: 0,
-                bytes_per_row: self.texture_extent.width * self.texture_format_size,
-                rows_per_image: self.texture_extent.height,
-            },
-            wgpu::TextureCopyView {
-                texture: &self.texture,
-                mip_level: 0,
-                array_layer: 0,
-                origin: wgpu::Origin3d { x: 0, y: 0, z: 0 },
-            },
-            self.texture_extent,
-        );
+        // Copies must be a multiple of 4 bytes
+        let mut data = data.to_vec();
+        data.resize((data.len() + 3) / 4 * 4, 0);
+        self.pending_user_data = Some(data);
 
-        // Execute all render passes
-        for renderer in self.renderers.iter() {
-            // TODO: Create a texture chain so that each pass receives the texture drawn by the previous
-            renderer.render(&mut encoder, &frame.view);
-        }
+        self.render_frame(None)
+    }
 
-        self.queue.borrow_mut().submit(&[encoder.finish()]);
-        Ok(())
+    /// The [`UserDataBuffer`] filled by [`Pixels::render_with_data`], for render passes added
+    /// with [`Pixels::add_render_pass`].
+    ///
+    /// Returns `None` when no render pass requested [`RenderPassOptions::user_data`], because the
+    /// buffer is only created when a render pass requests it.
+    pub fn user_data_buffer(&self) -> Option<&UserDataBuffer> {
+        self.user_data.as_ref()
     }
 
-    /// Get a mutable byte slice for the pixel buffer. The buffer is _not_ cleared for you; it will
-    /// retain the previous frame's contents until you clear it yourself.
+    /// Draw this pixel buffer to the configured [`SurfaceTexture`], then call `overlay` to draw
+    /// over it before the frame is presented.
+    ///
+    /// This is the integration point for GUI libraries like egui or Dear ImGui. `overlay` receives
+    /// a command encoder, the surface texture view, and a [`PixelsContext`] with the device and
+    /// queue. Its commands are submitted after all render passes, layers, and gizmos, in the same
+    /// submission as the frame. The overlay is not included in screenshots or inserted frames.
     ///
     /// # Example
     ///
@@ -279,332 +920,4208 @@ impl Pixels {
     /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
     /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
     ///
-    /// // Clear the pixel buffer
-    /// let frame = pixels.get_frame();
-    /// for pixel in frame.chunks_exact_mut(4) {
-    ///     pixel[0] = 0x00; // R
-    ///     pixel[1] = 0x00; // G
-    ///     pixel[2] = 0x00; // B
-    ///     pixel[3] = 0xff; // A
-    /// }
-    ///
-    /// // Draw it to the `SurfaceTexture`
-    /// pixels.render();
+    /// pixels.render_with(|encoder, render_target, context| {
+    ///     // ... Record the GUI render pass with `context.device`
+    ///     let _rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+    ///         color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+    ///             attachment: render_target,
+    ///             resolve_target: None,
+    ///             load_op: wgpu::LoadOp::Load,
+    ///             store_op: wgpu::StoreOp::Store,
+    ///             clear_color: wgpu::Color::TRANSPARENT,
+    ///         }],
+    ///         depth_stencil_attachment: None,
+    ///     });
+    /// })?;
     /// # Ok::<(), pixels::Error>(())
     /// ```
-    pub fn get_frame(&mut self) -> &mut [u8] {
-        &mut self.pixels
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when [`wgpu::SwapChain::get_next_texture`] times out.
+    pub fn render_with<F>(&mut self, overlay: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut wgpu::CommandEncoder, &TextureView, &PixelsContext<'_>),
+    {
+        self.render_frame(Some(Overlay::Over(Box::new(overlay))))
     }
 
-    /// Calculate the pixel location from a physical location on the window,
-    /// dealing with window resizing, scaling, and margins. Takes a physical
-    /// position (x, y) within the window, and returns a pixel position (x, y).
+    /// Draw this pixel buffer to the configured [`SurfaceTexture`], letting `commands` decide when
+    /// the render passes are executed.
     ///
-    /// The location must be given in physical units (for example, winit's `PhysicalLocation`)
+    /// `commands` receives a command encoder, the surface texture view, and the [`RenderPasses`].
+    /// Commands recorded before and after calling [`RenderPasses::render`] are executed before and
+    /// after the render passes, in the same submission as the frame. This interleaves the
+    /// application's own work with the built-in renderer, like a compute pass which simulates GPU
+    /// particles, and draws behind or over the pixel buffer without a second queue submission.
     ///
-    /// If the given physical position is outside of the drawing area, this
-    /// function returns an `Err` value with the pixel coordinates outside of
-    /// the screen, using isize instead of usize.
+    /// Nothing is drawn by the render passes when `commands` does not call
+    /// [`RenderPasses::render`]. Gizmos are still drawn over everything. The application's
+    /// commands are not included in screenshots or inserted frames.
+    ///
+    /// # Example
     ///
     /// ```no_run
-    /// # use pixels::Pixels;
+    /// use pixels::PixelsBuilder;
+    ///
     /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
     /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
-    /// const WIDTH:  u32 = 320;
-    /// const HEIGHT: u32 = 240;
-    ///
-    /// let mut pixels = Pixels::new(WIDTH, HEIGHT, surface_texture)?;
-    ///
-    /// // A cursor position in physical units
-    /// let cursor_position: (f32, f32) = winit::dpi::PhysicalPosition::new(0.0, 0.0).into();
+    /// let mut pixels = PixelsBuilder::new(320, 240, surface_texture)
+    ///     .alpha_blending(true)
+    ///     .build()?;
     ///
-    /// // Convert it to a pixel location
-    /// let pixel_position: (usize, usize) = pixels.window_pos_to_pixel(cursor_position)
-    ///     // Clamp the output to within the screen
-    ///     .unwrap_or_else(|pos| pixels.clamp_pixel_pos(pos));
+    /// pixels.render_with_passes(|encoder, render_target, passes| {
+    ///     // ... Simulate and draw particles behind the pixel buffer with `passes.context()`
+    ///     passes.render(encoder, render_target);
+    ///     // ... Draw particles in front of the pixel buffer
+    /// })?;
     /// # Ok::<(), pixels::Error>(())
     /// ```
-    pub fn window_pos_to_pixel(
-        &self,
-        physical_position: (f32, f32),
-    ) -> Result<(usize, usize), (isize, isize)> {
-        let physical_width = self.surface_texture.width as f32;
-        let physical_height = self.surface_texture.height as f32;
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when [`wgpu::SwapChain::get_next_texture`] times out.
+    pub fn render_with_passes<F>(&mut self, commands: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut wgpu::CommandEncoder, &TextureView, &mut RenderPasses<'_>),
+    {
+        self.render_frame(Some(Overlay::Around(Box::new(commands))))
+    }
 
-        let pixels_width = self.texture_extent.width as f32;
-        let pixels_height = self.texture_extent.height as f32;
+    /// Upload, draw, and present the pixel buffer, with an optional overlay.
+    fn render_frame(&mut self, overlay: Option<Overlay<'_>>) -> Result<(), Error> {
+        // Skip the frame while backgrounded, keeping the changes for the next rendered frame
+        let now = Instant::now();
+        if self.background && !self.background_throttle.allows(self.last_render, now) {
+            return Ok(());
+        }
+        self.last_render = Some(now);
 
-        let pos = ultraviolet::Vec4::new(
-            (physical_position.0 / physical_width - 0.5) * pixels_width,
-            (physical_position.1 / physical_height - 0.5) * pixels_height,
-            0.0,
-            1.0,
-        );
+        #[cfg(feature = "capture")]
+        {
+            if let Some(recording) = &self.recording {
+                if recording.is_recording(RecordingSource::RenderedFrame) {
+                    let callback = recording.screenshot_callback(self.clock.elapsed());
+                    self.screenshots.request(Box::new(callback));
+                }
+            }
+        }
 
-        let pos = self.scaling_matrix_inverse * pos;
+        self.present(true, overlay)?;
+        self.present_inserted_frames()?;
 
-        let pos = (
-            pos.x / pos.w + pixels_width / 2.0,
-            -pos.y / pos.w + pixels_height / 2.0,
-        );
-        let pixel_x = pos.0.floor() as isize;
-        let pixel_y = pos.1.floor() as isize;
+        // Debugging features keep copies of the frame as it appears in the texture, which only
+        // exists on the GPU when it is filled by compute passes
+        let mut frame_history = lock(&self.frame_history);
+        if self.compute.is_none() && (frame_history.is_enabled() || self.crash_dump.is_some()) {
+            let expanded;
+            let frame = match &self.converter {
+                Some(converter) => {
+                    expanded = converter.expand(&self.pixels);
+                    &expanded
+                }
+                None => &self.pixels,
+            };
 
-        if pixel_x < 0
-            || pixel_x >= self.texture_extent.width as isize
-            || pixel_y < 0
-            || pixel_y >= self.texture_extent.height as isize
-        {
-            Err((pixel_x, pixel_y))
-        } else {
-            Ok((pixel_x as usize, pixel_y as usize))
+            frame_history.push(frame);
+            if let Some(crash_dump) = &self.crash_dump {
+                crash_dump.update(frame);
+            }
         }
+        drop(frame_history);
+
+        #[cfg(feature = "capture")]
+        self.record_pixel_buffer();
+
+        Ok(())
     }
 
-    /// Clamp a pixel position to the pixel buffer size.
+    /// Upload rows of pixels to the GPU immediately, as they are produced.
     ///
-    /// This can be used to clamp the `Err` value returned by [`Pixels::window_pos_to_pixel`]
-    /// to a position clamped within the drawing area.
+    /// `rows` contains one or more complete rows of pixel data, which replace the rows in the pixel
+    /// buffer beginning at `start_row`. Unlike writing to [`Pixels::get_frame`], the rows are
+    /// submitted to the GPU right away instead of waiting for the next call to [`Pixels::render`].
+    ///
+    /// Combined with [`Pixels::present_partial`], this allows emulators to "race the beam" by
+    /// presenting a frame while it is still being produced. With the indexed frame formats, the
+    /// rows are expanded with the current palette, see [`Pixels::set_palette_colors`].
+    ///
+    /// # Example
     ///
     /// ```no_run
     /// # use pixels::Pixels;
     /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
     /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
-    /// const WIDTH:  u32 = 320;
-    /// const HEIGHT: u32 = 240;
-    ///
-    /// let mut pixels = Pixels::new(WIDTH, HEIGHT, surface_texture)?;
+    /// let mut pixels = Pixels::new(256, 240, surface_texture)?;
+    /// let mut scanline = vec![0; 256 * 4];
     ///
-    /// let pixel_pos = pixels.clamp_pixel_pos((-19, 20));
-    /// assert_eq!(pixel_pos, (0, 20));
+    /// for y in 0..240 {
+    ///     // ... Emulate one scanline into `scanline`
+    ///     pixels.push_scanlines(y, &scanline);
     ///
-    /// let pixel_pos = pixels.clamp_pixel_pos((11, 3000));
-    /// assert_eq!(pixel_pos, (11, 240));
+    ///     // Present the top half of the frame early
+    ///     if y == 119 {
+    ///         pixels.present_partial()?;
+    ///     }
+    /// }
+    /// pixels.present_partial()?;
     /// # Ok::<(), pixels::Error>(())
     /// ```
-    pub fn clamp_pixel_pos(&self, pos: (isize, isize)) -> (usize, usize) {
-        (
-            pos.0.max(0).min(self.texture_extent.width as isize - 1) as usize,
-            pos.1.max(0).min(self.texture_extent.height as isize - 1) as usize,
-        )
+    ///
+    /// # Panics
+    ///
+    /// Panics when `rows` does not contain a whole number of rows, or when the rows extend past
+    /// the bottom of the pixel buffer. Also panics when the frame format is
+    /// [`FrameFormat::Planar`], which does not store rows contiguously, or when compute passes
+    /// were added with [`PixelsBuilder::add_compute_pass`].
+    pub fn push_scanlines(&mut self, start_row: u32, rows: &[u8]) {
+        self.assert_cpu_frame();
+        let bytes_per_pixel = self.frame_bytes_per_pixel();
+        let bytes_per_row = (self.texture_extent.width * bytes_per_pixel) as usize;
+        assert_eq!(rows.len() % bytes_per_row, 0);
+
+        let end_row = start_row + (rows.len() / bytes_per_row) as u32;
+        assert!(end_row <= self.texture_extent.height);
+
+        let start = start_row as usize * bytes_per_row;
+        self.pixels[start..start + rows.len()].copy_from_slice(rows);
+
+        // Palette changes made since the last push only apply to the pushed rows
+        self.apply_staged_palette();
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        match &mut self.converter {
+            Some(converter) => converter.convert(
+                &self.device,
+                &mut encoder,
+                &mut self.staging,
+                &self.pixels,
+                &self.texture,
+                self.bottom_up_rows,
+                start_row..end_row,
+            ),
+            None => self.upload_rows(&mut encoder, start_row..end_row),
+        }
+        lock(&self.queue).submit(&[encoder.finish()]);
+        self.staging.recall();
+        self.redraw = true;
     }
-}
 
-impl<'req> PixelsBuilder<'req> {
-    /// Create a builder that can be finalized into a [`Pixels`] pixel buffer.
+    /// Draw the texture to the configured [`SurfaceTexture`] without uploading the pixel buffer.
     ///
-    /// # Examples
+    /// The surface shows whatever has been uploaded so far with [`Pixels::push_scanlines`]; rows
+    /// which have not been pushed yet still contain the previous frame. Changes made through
+    /// [`Pixels::get_frame`] are not uploaded until the next call to [`Pixels::render`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when [`wgpu::SwapChain::get_next_texture`] times out.
+    pub fn present_partial(&mut self) -> Result<(), Error> {
+        self.present(false, None)
+    }
+
+    /// Create a fence which is signaled when the GPU has completed all work submitted so far,
+    /// including the most recent call to [`Pixels::render`].
+    ///
+    /// This is for synchronizing external `wgpu` code which shares the device, e.g. a system
+    /// consuming the rendered output. See [`SubmissionFence`].
+    ///
+    /// # Example
     ///
     /// ```no_run
-    /// # use pixels::PixelsBuilder;
+    /// # use pixels::Pixels;
     /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
     /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
-    /// struct MyRenderPass {
-    ///     // ...
-    /// };
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
     ///
-    /// impl pixels::RenderPass for MyRenderPass {
-    ///     // ...
-    /// # fn update_bindings(&mut self, _: &wgpu::TextureView, _: &wgpu::Extent3d) {}
-    /// # fn render(&self, _: &mut wgpu::CommandEncoder, _: &wgpu::TextureView) {}
+    /// pixels.render()?;
+    /// let mut fence = pixels.submission_fence();
+    /// // ... Do other work
+    /// if fence.is_signaled() {
+    ///     // The frame has been rendered
     /// }
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn submission_fence(&self) -> SubmissionFence {
+        SubmissionFence::new(&self.device, &mut lock(&self.queue))
+    }
+
+    /// Pump one frame from an external source, like a video capture card or a webcam, to the
+    /// surface.
     ///
-    /// let mut pixels = PixelsBuilder::new(256, 240, surface_texture)
-    ///     .pixel_aspect_ratio(8.0 / 7.0)
-    ///     .add_render_pass(|device, queue, texture, texture_size| {
-    ///         // Create reources for MyRenderPass here
-    ///         Box::new(MyRenderPass {
-    ///             // ...
-    ///         })
-    ///     })
-    ///     .build()?;
+    /// The source is asked to negotiate a frame layout first. When it can produce frames in the
+    /// layout of the pixel buffer, it writes them directly into the pixel buffer. Otherwise the
+    /// frame is written to a staging buffer and converted on the CPU, honoring the row stride.
+    /// Frames with a different size are drawn in the top-left corner, cropped to the pixel buffer.
+    ///
+    /// Returns `true` when a new frame was rendered, or `false` when the source had no new frame.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use pixels::{ExternalFrameSource, FrameInfo, Pixels, SourceFormat};
+    ///
+    /// /// A source producing 24-bit `BGR` frames, with rows padded to 4-byte alignment.
+    /// struct TestPattern {
+    ///     frame: u8,
+    /// }
+    ///
+    /// impl ExternalFrameSource for TestPattern {
+    ///     fn negotiate(&mut self, preferred: FrameInfo) -> FrameInfo {
+    ///         let stride = (preferred.width as usize * 3 + 3) & !3;
+    ///
+    ///         FrameInfo { stride, format: SourceFormat::Bgr8, ..preferred }
+    ///     }
+    ///
+    ///     fn next_frame(&mut self, buffer: &mut [u8]) -> Option<FrameInfo> {
+    ///         self.frame = self.frame.wrapping_add(1);
+    ///         for byte in buffer.iter_mut() {
+    ///             *byte = self.frame;
+    ///         }
+    ///
+    ///         Some(FrameInfo { width: 320, height: 240, stride: 960, format: SourceFormat::Bgr8 })
+    ///     }
+    /// }
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    /// let mut source = TestPattern { frame: 0 };
+    ///
+    /// loop {
+    ///     pixels.drive_from(&mut source)?;
+    /// }
     /// # Ok::<(), pixels::Error>(())
     /// ```
     ///
+    /// # Errors
+    ///
+    /// Returns an error when [`wgpu::SwapChain::get_next_texture`] times out.
+    ///
     /// # Panics
     ///
-    /// Panics when `width` or `height` are 0.
-    pub fn new(width: u32, height: u32, surface_texture: SurfaceTexture) -> PixelsBuilder<'req> {
-        assert!(width > 0);
-        assert!(height > 0);
+    /// Panics when the frame format is not [`FrameFormat::Texture`], when the texture format is
+    /// not a 4-byte `RGBA` format, when the frame written by the source does not fit in the
+    /// buffer it was given, or when compute passes were added with
+    /// [`PixelsBuilder::add_compute_pass`].
+    pub fn drive_from<S: ExternalFrameSource>(&mut self, source: &mut S) -> Result<bool, Error> {
+        self.assert_cpu_frame();
+        assert_eq!(self.frame_format, FrameFormat::Texture);
+        assert_eq!(self.texture_format_size, 4);
 
-        PixelsBuilder {
-            request_adapter_options: None,
-            device_descriptor: wgpu::DeviceDescriptor::default(),
-            backend: wgpu::BackendBit::PRIMARY,
+        let width = self.texture_extent.width;
+        let height = self.texture_extent.height;
+        let preferred = FrameInfo {
             width,
             height,
-            pixel_aspect_ratio: 1.0,
-            present_mode: wgpu::PresentMode::Fifo,
-            surface_texture,
-            texture_format: wgpu::TextureFormat::Rgba8UnormSrgb,
-            renderer_factories: Vec::new(),
+            stride: width as usize * 4,
+            format: SourceFormat::Rgba8,
+        };
+        let negotiated = source.negotiate(preferred);
+
+        if negotiated == preferred {
+            // The source writes directly into the pixel buffer
+            match source.next_frame(&mut self.pixels) {
+                Some(info) => assert_eq!(info, preferred),
+                None => return Ok(false),
+            }
+        } else {
+            self.source_buffer.resize(negotiated.frame_size(), 0);
+            let info = match source.next_frame(&mut self.source_buffer) {
+                Some(info) => info,
+                None => return Ok(false),
+            };
+            assert!(info.frame_size() <= self.source_buffer.len());
+
+            copy_frame(&self.source_buffer, &info, &mut self.pixels, width, height);
         }
+
+        self.dirty.mark(0..height);
+        self.render()?;
+
+        Ok(true)
     }
 
-    /// Add options for requesting a [`wgpu::Adapter`].
-    pub const fn request_adapter_options(
-        mut self,
-        request_adapter_options: wgpu::RequestAdapterOptions<'req>,
-    ) -> PixelsBuilder {
-        self.request_adapter_options = Some(request_adapter_options);
-        self
+    /// Render the frame from `queue` that is due at the next vblank.
+    ///
+    /// The pixel buffer is replaced with the newest frame whose timestamp is due, and the surface
+    /// is redrawn even when no new frame is due, so call this once per refresh with Vsync enabled.
+    /// See [`FrameQueue`].
+    ///
+    /// Returns `true` when a new frame was shown.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when [`wgpu::SwapChain::get_next_texture`] times out.
+    ///
+    /// # Panics
+    ///
+    /// Panics when a frame in the queue is not the same size as the pixel buffer, or when compute
+    /// passes were added with [`PixelsBuilder::add_compute_pass`].
+    pub fn render_from_queue(&mut self, queue: &FrameQueue) -> Result<bool, Error> {
+        self.assert_cpu_frame();
+        let now = Instant::now();
+        let display_time = self.present_timer.next_deadline(now).unwrap_or(now);
+
+        let frame = queue.take(display_time);
+        if let Some(frame) = &frame {
+            assert_eq!(frame.len(), self.pixels.len());
+            self.pixels.copy_from_slice(frame);
+            self.dirty.mark(0..self.texture_extent.height);
+        }
+        self.render()?;
+
+        Ok(frame.is_some())
     }
 
-    /// Add options for requesting a [`wgpu::Device`].
-    pub const fn device_descriptor(
-        mut self,
-        device_descriptor: wgpu::DeviceDescriptor,
+    /// The display refresh interval, as estimated from the timing of recent presents.
+    ///
+    /// Returns the interval reported with [`Pixels::set_refresh_rate`], if any. Otherwise returns
+    /// `None` until at least two frames have been rendered. The estimate is only meaningful when
+    /// Vsync is enabled.
+    pub fn estimated_refresh_interval(&self) -> Option<Duration> {
+        self.present_timer.interval()
+    }
+
+    /// Report the refresh rate of the monitor showing the surface, in Hz.
+    ///
+    /// Frame pacing uses the reported rate instead of estimating it, which avoids a period of
+    /// mistimed presents after the window moves between monitors with different refresh rates
+    /// (e.g. from 60 Hz to 144 Hz). Call this again whenever the window moves to another monitor,
+    /// or pass `None` to go back to estimating the refresh rate.
+    ///
+    /// Without a reported rate, the estimate still adapts to a new monitor after several frames.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use pixels::Pixels;
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    ///
+    /// // E.g. from the video mode of the window's current monitor
+    /// let refresh_rate = 144;
+    /// pixels.set_refresh_rate(Some(f64::from(refresh_rate)));
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics when the refresh rate is not positive and finite.
+    pub fn set_refresh_rate(&mut self, refresh_rate: Option<f64>) {
+        let refresh_interval = refresh_rate.map(|hz| {
+            assert!(hz.is_finite() && hz > 0.0);
+            Duration::from_secs_f64(1.0 / hz)
+        });
+
+        self.present_timer.set_refresh_interval(refresh_interval);
+    }
+
+    /// The time remaining before the latest moment a frame can be rendered and still make the
+    /// next vblank.
+    ///
+    /// This is the estimated time of the next vblank, minus the margin configured with
+    /// [`PixelsBuilder::just_in_time_presentation`] (or no margin if it is not configured).
+    /// [`Pixels::render`] never waits for the deadline. Applications drive their own pacing by
+    /// waiting this long before polling input and drawing the frame, which minimizes input
+    /// latency without blocking the event loop.
+    ///
+    /// Returns `None` until the refresh interval has been estimated, or when the deadline has
+    /// already passed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use pixels::Pixels;
+    /// # use std::time::Instant;
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// # let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    /// // E.g. in a winit event loop, wake up just before the deadline
+    /// let wake_at = match pixels.time_until_present_deadline() {
+    ///     Some(remaining) => Instant::now() + remaining,
+    ///     None => Instant::now(),
+    /// };
+    /// // *control_flow = ControlFlow::WaitUntil(wake_at);
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    ///
+    /// [`Pixels::render`]: ./struct.Pixels.html#method.render
+    pub fn time_until_present_deadline(&self) -> Option<Duration> {
+        let now = Instant::now();
+        let deadline = self.present_timer.next_deadline(now)?;
+        let margin = self.present_margin.unwrap_or_default();
+
+        deadline.checked_duration_since(now)?.checked_sub(margin)
+    }
+
+    /// Request a screenshot of the next rendered frame.
+    ///
+    /// The screenshot is copied from the frame exactly as it is presented on the surface, after
+    /// all render passes, layers, gizmos, and overlays have been drawn. The render passes are not
+    /// executed a second time for the screenshot. Reading the image back from the GPU happens
+    /// asynchronously; `callback` is invoked from a later call to [`Pixels::render`] once the data
+    /// is available, so the render loop is never stalled.
+    ///
+    /// The image data delivered to `callback` is always `RGBA`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use pixels::Pixels;
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    ///
+    /// pixels.request_screenshot(|screenshot| match screenshot {
+    ///     Ok(screenshot) => println!("Got a {}x{} screenshot", screenshot.width(), screenshot.height()),
+    ///     Err(err) => eprintln!("Screenshot failed: {}", err),
+    /// });
+    ///
+    /// pixels.render()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn request_screenshot<F>(&mut self, callback: F)
+    where
+        F: FnOnce(Result<Screenshot, Error>) + Send + 'static,
+    {
+        self.screenshots.request(Box::new(callback));
+    }
+
+    /// Read the most recently rendered frame back from an offscreen surface.
+    ///
+    /// This blocks until the GPU has finished rendering and copying the frame. The image data is
+    /// tightly packed `RGBA` with the size of the [`SurfaceTexture`], after all render passes.
+    ///
+    /// See [`SurfaceTexture::offscreen`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the frame cannot be read back from the GPU.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the pixel buffer renders to a window surface instead of offscreen.
+    pub fn read_offscreen_frame(&self) -> Result<Vec<u8>, Error> {
+        assert!(
+            self.surface_texture.surface.is_none(),
+            "The pixel buffer does not render offscreen"
+        );
+
+        self.read_rendered_frame()
+    }
+
+    /// Read the final, post-processed image of the pixel buffer back from the GPU.
+    ///
+    /// Unlike [`Pixels::get_frame`], which is the source buffer before any render passes, this is
+    /// the image as it is presented on the surface, which makes it useful for screenshot hotkeys
+    /// and golden-image tests of custom render passes. The image data is tightly packed `RGBA`
+    /// with the size of the [`SurfaceTexture`].
+    ///
+    /// Offscreen surfaces keep every frame, and so do window surfaces while a screenshot is
+    /// requested or frames are inserted with [`FrameInsertion::Dim`]. The frame is then read
+    /// exactly as it was last presented, including layers, gizmos, and overlays. Otherwise the
+    /// frame was drawn straight into the swap chain, which cannot be read, so all render passes
+    /// are executed again with the pixel buffer as it was last rendered; gizmos and overlays are
+    /// not included. This blocks until the GPU has finished; use [`Pixels::request_screenshot`]
+    /// to read frames without stalling the render loop.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use pixels::Pixels;
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    ///
+    /// pixels.render()?;
+    /// let rgba = pixels.read_rendered_frame()?;
+    /// assert_eq!(rgba.len(), 1024 * 768 * 4);
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the frame cannot be read back from the GPU.
+    pub fn read_rendered_frame(&self) -> Result<Vec<u8>, Error> {
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("pixels_read_rendered_frame"),
+            });
+        let captured;
+        let rendered_frame = match &self.rendered_frame {
+            Some(rendered_frame) if self.frame_retained => rendered_frame,
+            _ => {
+                captured = self.capture(&mut encoder);
+                &captured
+            }
+        };
+        let readback = rendered_frame.readback(&self.device, &mut encoder);
+        lock(&self.queue).submit(&[encoder.finish()]);
+
+        readback.map().wait(&self.device)
+    }
+
+    /// Scale the pixel buffer down to `width` by `height` pixels on the GPU, and read it back.
+    ///
+    /// This is meant for save-state thumbnails and previews of recent files, without resampling
+    /// the pixel buffer on the CPU. The thumbnail is made from the pixel buffer as it was last
+    /// rendered, before any render passes, and is filtered smoothly so that every pixel
+    /// contributes. It is stretched to the requested size; the aspect ratio is not preserved. The
+    /// image data is tightly packed `RGBA`.
+    ///
+    /// This blocks until the GPU has finished.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use pixels::Pixels;
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    ///
+    /// pixels.render()?;
+    /// let thumbnail = pixels.render_thumbnail(80, 60)?;
+    /// assert_eq!(thumbnail.len(), 80 * 60 * 4);
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the thumbnail cannot be read back from the GPU.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `width` or `height` are 0.
+    pub fn render_thumbnail(&self, width: u32, height: u32) -> Result<Vec<u8>, Error> {
+        assert!(width > 0);
+        assert!(height > 0);
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("pixels_render_thumbnail"),
+            });
+        let (_textures, readback) = render_thumbnail(
+            &self.device,
+            &mut encoder,
+            &self.texture.create_default_view(),
+            (self.texture_extent.width, self.texture_extent.height),
+            (width, height),
+        );
+        lock(&self.queue).submit(&[encoder.finish()]);
+
+        readback.map().wait(&self.device)
+    }
+
+    /// Create a second window view which shows a magnified region of this pixel buffer.
+    ///
+    /// The window surface must be compatible with the adapter of this pixel buffer. See
+    /// [`Magnifier`] for details.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use pixels::Pixels;
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// # let inspector_surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    /// let inspector_texture = pixels::SurfaceTexture::new(512, 512, inspector_surface);
+    /// let mut magnifier = pixels.magnifier(inspector_texture);
+    /// magnifier.set_region(64, 32, 16, 16);
+    ///
+    /// pixels.render()?;
+    /// magnifier.render(&pixels)?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics when `surface_texture` is for offscreen rendering.
+    pub fn magnifier(&self, surface_texture: SurfaceTexture) -> Magnifier {
+        Magnifier::new(self, surface_texture)
+    }
+
+    /// Capture the next frame presented by [`Pixels::render`] with RenderDoc.
+    ///
+    /// This is far more reliable than triggering captures by hand, especially for short-lived
+    /// windows. The application must be launched from RenderDoc (or have RenderDoc injected) for
+    /// the capture to happen. Exactly one frame is captured per call.
+    ///
+    /// Returns `false` when RenderDoc is not attached to the process.
+    ///
+    /// This method is only available with the `renderdoc` feature.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "renderdoc")]
+    /// # fn main() -> Result<(), pixels::Error> {
+    /// # use pixels::Pixels;
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    ///
+    /// if !pixels.trigger_gpu_capture() {
+    ///     eprintln!("RenderDoc is not attached");
+    /// }
+    /// pixels.render()?;
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "renderdoc"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg(feature = "renderdoc")]
+    pub fn trigger_gpu_capture(&mut self) -> bool {
+        self.gpu_capture.trigger()
+    }
+
+    /// Start recording every frame rendered by [`Pixels::render`] to a file.
+    ///
+    /// `path` is the file for [`RecordingFormat::Gif`] and [`RecordingFormat::Apng`], or the
+    /// directory for [`RecordingFormat::PngSequence`]. `source` selects whether the pixel buffer
+    /// or the final rendered frame is recorded. Any recording in progress is stopped first. See
+    /// the [`capture`] module for an example.
+    ///
+    /// This method is only available with the `capture` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the file or directory cannot be created, when the previous recording
+    /// failed, or when [`RecordingSource::PixelBuffer`] is used with a texture format which is not
+    /// 8-bit `RGBA` or `BGRA`, or with compute passes, which leave no pixel buffer on the CPU.
+    #[cfg(feature = "capture")]
+    pub fn start_recording<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        format: RecordingFormat,
+        source: RecordingSource,
+    ) -> io::Result<()> {
+        if source == RecordingSource::PixelBuffer && self.compute.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the pixel buffer is filled by compute passes, record the rendered frame instead",
+            ));
+        }
+        if source == RecordingSource::PixelBuffer && self.converter.is_none() {
+            match self.texture_format {
+                wgpu::TextureFormat::Rgba8UnormSrgb
+                | wgpu::TextureFormat::Rgba8Unorm
+                | wgpu::TextureFormat::Bgra8UnormSrgb
+                | wgpu::TextureFormat::Bgra8Unorm => (),
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "only 8-bit RGBA and BGRA pixel buffers can be recorded",
+                    ))
+                }
+            }
+        }
+
+        self.stop_recording()?;
+        self.recording = Some(Recording::create(path.as_ref(), format, source)?);
+
+        Ok(())
+    }
+
+    /// Stop recording and finish the file started by [`Pixels::start_recording`].
+    ///
+    /// Waits for rendered frames which are still being read back from the GPU. Does nothing when
+    /// there is no recording.
+    ///
+    /// This method is only available with the `capture` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error which happened while writing the recording. Frames after the error
+    /// were not recorded, but the file is finished as far as possible.
+    #[cfg(feature = "capture")]
+    pub fn stop_recording(&mut self) -> io::Result<()> {
+        let recording = match self.recording.take() {
+            Some(recording) => recording,
+            None => return Ok(()),
+        };
+
+        if recording.source() == RecordingSource::RenderedFrame {
+            self.device.poll(wgpu::Maintain::Wait);
+            self.screenshots.poll();
+        }
+
+        recording.finish(self.clock.elapsed())
+    }
+
+    /// Pause the recording. Frames rendered while paused are left out, and the frame shown when
+    /// the recording was paused lasts until then.
+    ///
+    /// This method is only available with the `capture` feature.
+    #[cfg(feature = "capture")]
+    pub fn pause_recording(&mut self) {
+        if let Some(recording) = &mut self.recording {
+            recording.set_paused(true, self.clock.elapsed());
+        }
+    }
+
+    /// Resume a recording paused by [`Pixels::pause_recording`].
+    ///
+    /// This method is only available with the `capture` feature.
+    #[cfg(feature = "capture")]
+    pub fn resume_recording(&mut self) {
+        if let Some(recording) = &mut self.recording {
+            recording.set_paused(false, self.clock.elapsed());
+        }
+    }
+
+    /// Returns `true` while a recording is in progress, even when it is paused.
+    ///
+    /// This method is only available with the `capture` feature.
+    #[cfg(feature = "capture")]
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Get the debug shapes to draw over the next frame.
+    ///
+    /// Gizmos are drawn by the GPU above all render passes, so they can highlight hitboxes and
+    /// other debugging information without modifying the pixel buffer. They are cleared after each
+    /// frame is presented. See [`Gizmos`].
+    pub fn gizmos(&mut self) -> &mut Gizmos {
+        &mut self.gizmos
+    }
+
+    /// Get a mutable byte slice for the pixel buffer. The buffer is _not_ cleared for you; it will
+    /// retain the previous frame's contents until you clear it yourself.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use pixels::Pixels;
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    ///
+    /// // Clear the pixel buffer
+    /// let frame = pixels.get_frame();
+    /// for pixel in frame.chunks_exact_mut(4) {
+    ///     pixel[0] = 0x00; // R
+    ///     pixel[1] = 0x00; // G
+    ///     pixel[2] = 0x00; // B
+    ///     pixel[3] = 0xff; // A
+    /// }
+    ///
+    /// // Draw it to the `SurfaceTexture`
+    /// pixels.render();
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics when compute passes were added with [`PixelsBuilder::add_compute_pass`], which fill the texture on
+    /// the GPU instead of a pixel buffer on the CPU.
+    pub fn get_frame(&mut self) -> &mut [u8] {
+        self.assert_cpu_frame();
+
+        // Writes cannot be tracked through a plain slice
+        self.dirty.mark(0..self.texture_extent.height);
+
+        &mut self.pixels
+    }
+
+    /// Get the back buffer, which makes the pixel buffer double-buffered.
+    ///
+    /// The next frame is drawn into the back buffer with [`BackFrame::draw`], possibly from
+    /// another thread, while the current frame is uploaded and rendered. [`Pixels::swap_buffers`]
+    /// makes it the current frame. The back buffer starts out as a copy of the pixel buffer, and
+    /// is reset to a copy when the pixel buffer is resized.
+    ///
+    /// All calls return handles to the same back buffer. See [`BackFrame`].
+    ///
+    /// # Panics
+    ///
+    /// Panics when compute passes were added with [`PixelsBuilder::add_compute_pass`].
+    pub fn get_back_frame(&mut self) -> BackFrame {
+        self.assert_cpu_frame();
+
+        let pixels = &self.pixels;
+        self.back_frame
+            .get_or_insert_with(|| BackFrame::new(pixels.clone()))
+            .clone()
+    }
+
+    /// Make the frame drawn into the back buffer the current frame, and the current frame the back
+    /// buffer. See [`Pixels::get_back_frame`].
+    ///
+    /// Returns `false` and keeps the current frame when no frame was drawn into the back buffer
+    /// since the last swap, or when there is no back buffer.
+    pub fn swap_buffers(&mut self) -> bool {
+        if let Some(back_frame) = &self.back_frame {
+            if back_frame.swap(&mut self.pixels) {
+                self.dirty.mark(0..self.texture_extent.height);
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Get a typed view of the pixel buffer.
+    ///
+    /// This is a safer alternative to [`Pixels::get_frame`] for per-pixel code. The [`Frame`]
+    /// addresses pixels by position instead of byte offset, and checks positions with debug
+    /// assertions. All rows are uploaded with the next render.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use pixels::color::Color;
+    /// use pixels::Pixels;
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    ///
+    /// let mut frame = pixels.get_frame_pixels::<Color>();
+    /// frame.fill(Color::from_rgba8(0x00, 0x00, 0x00, 0xff));
+    /// frame.set(160, 120, Color::from_rgba8(0xff, 0xff, 0xff, 0xff));
+    ///
+    /// // Draw a gradient into each row
+    /// for mut row in frame.rows_mut().skip(200) {
+    ///     for x in 0..row.width() {
+    ///         row.set(x, Color::from_rgba8(x as u8, 0x00, 0x80, 0xff));
+    ///     }
+    /// }
+    ///
+    /// pixels.render()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics when the size of `P` does not match the size of one pixel in the frame format, or
+    /// when the frame format is [`FrameFormat::Planar`], which does not store pixels contiguously.
+    /// Also panics when compute passes were added with [`PixelsBuilder::add_compute_pass`].
+    pub fn get_frame_pixels<P: Pixel>(&mut self) -> Frame<'_, P> {
+        assert_eq!(P::SIZE, self.frame_bytes_per_pixel() as usize);
+
+        let width = self.texture_extent.width;
+        let height = self.texture_extent.height;
+
+        Frame::new(self.get_frame(), width, height)
+    }
+
+    /// Get a write-tracking view of the pixel buffer.
+    ///
+    /// This is an opt-in alternative to [`Pixels::get_frame`]. The [`TrackedFrame`] records which
+    /// rows are written to, and [`Pixels::render`] will only upload that band of rows to the GPU.
+    /// Nothing is uploaded at all when no rows were written. This saves bandwidth for applications
+    /// that only touch a small part of the frame, at the cost of a little bookkeeping per write.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use pixels::Pixels;
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    ///
+    /// // Draw a status bar at the bottom of the screen
+    /// let mut frame = pixels.get_tracked_frame();
+    /// for pixel in frame.rows_mut(232..240).chunks_exact_mut(4) {
+    ///     pixel.copy_from_slice(&[0x20, 0x20, 0x20, 0xff]);
+    /// }
+    ///
+    /// // Only rows 232 through 239 are uploaded
+    /// pixels.render()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics when the frame format is [`FrameFormat::Planar`], which does not store rows
+    /// contiguously. Also panics when compute passes were added with
+    /// [`PixelsBuilder::add_compute_pass`], which fill the texture on the GPU instead of a pixel buffer on
+    /// the CPU.
+    pub fn get_tracked_frame(&mut self) -> TrackedFrame<'_> {
+        self.assert_cpu_frame();
+        let bytes_per_pixel = self.frame_bytes_per_pixel();
+
+        TrackedFrame::new(
+            &mut self.pixels,
+            &mut self.dirty,
+            self.texture_extent.width,
+            self.texture_extent.height,
+            bytes_per_pixel,
+        )
+    }
+
+    /// Add a render pass after all existing render passes, without rebuilding the pixel buffer.
+    ///
+    /// This works like [`PixelsBuilder::add_render_pass`], for effects which are switched on from
+    /// a settings menu. The render pass is notified of the current scaling with
+    /// [`RenderPass::surface_resized`] before it is first rendered.
+    ///
+    /// Returns the index of the render pass for [`Pixels::remove_render_pass`],
+    /// [`Pixels::set_pass_enabled`], and [`Pixels::move_render_pass`]. Indices count every render
+    /// pass added with [`PixelsBuilder::add_render_pass`] or this method, in the order they are
+    /// executed, starting at 0. Background passes and the built-in scaling pass are not counted.
+    ///
+    /// Adding, removing, and moving render passes is reported by [`Pixels::poll_events`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pixels::debug::FrameDiffPass;
+    /// use pixels::Pixels;
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    ///
+    /// let frame_diff = pixels.add_render_pass(FrameDiffPass::factory);
+    /// // ...
+    /// pixels.set_pass_enabled(frame_diff, false);
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn add_render_pass<F>(&mut self, factory: F) -> usize
+    where
+        F: FnOnce(Device, Queue, &PassInputs<'_>) -> BoxedRenderPass,
+    {
+        self.add_render_pass_with(RenderPassOptions::default(), factory)
+    }
+
+    /// Add a render pass with [`RenderPassOptions`] after all existing render passes, without
+    /// rebuilding the pixel buffer.
+    ///
+    /// This works like [`PixelsBuilder::add_render_pass_with`], and the factory receives the same
+    /// [`PassInputs`]. The previous frame texture and the user data buffer are created when the
+    /// options request them for the first time. Render passes without a label are labeled
+    /// `pixels_render_pass_N`, where `N` is never reused by this pixel buffer.
+    ///
+    /// See [`Pixels::add_render_pass`] for how render passes are indexed.
+    ///
+    /// # Panics
+    ///
+    /// Panics when [`RenderPassOptions::background`] is set. Background passes can only be added
+    /// to the builder.
+    pub fn add_render_pass_with<F>(&mut self, options: RenderPassOptions, factory: F) -> usize
+    where
+        F: FnOnce(Device, Queue, &PassInputs<'_>) -> BoxedRenderPass,
+    {
+        assert!(
+            !options.background,
+            "Background passes can only be added to the builder"
+        );
+
+        // Create the shared resources which were not needed by any render pass so far
+        if options.previous_frame && self.previous_texture.is_none() {
+            self.previous_texture = Some(create_previous_texture(
+                &self.device,
+                self.texture_extent,
+                self.texture_format,
+            ));
+        }
+        if options.user_data && self.user_data.is_none() {
+            self.user_data = Some(create_user_data_buffer(&self.device, self.user_data_size));
+        }
+
+        let index = self.render_pass_count();
+        let texture_view = match &self.interpolator {
+            Some(interpolator) => interpolator.view(),
+            None => self.texture.create_default_view(),
+        };
+        let previous_view = self
+            .previous_texture
+            .as_ref()
+            .map(|texture| texture.create_default_view());
+        let inputs = PassInputs {
+            texture: &texture_view,
+            texture_size: &self.texture_extent,
+            surface_format: self.surface_format,
+            sampler: options.sampler,
+            previous_frame: previous_view.as_ref(),
+            user_data: self.user_data.as_ref(),
+        };
+        let mut render_pass = factory(self.device.clone(), self.queue.clone(), &inputs);
+
+        // Bring the new pass up to date with the surface
+        let scaling_matrix = self.scaling_matrix();
+        let size = (self.surface_texture.width, self.surface_texture.height);
+        let resize = SurfaceResize {
+            old_size: size,
+            new_size: size,
+            scale: scaling_matrix.scale,
+            destination: scaling_matrix.destination,
+        };
+        let label = options.label.unwrap_or_else(|| {
+            self.next_pass_label += 1;
+            format!("pixels_render_pass_{}", self.next_pass_label - 1)
+        });
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some(&label),
+            });
+        render_pass.surface_resized(&mut encoder, &resize);
+        lock(&self.queue).submit(&[encoder.finish()]);
+
+        self.renderers.push(LabeledRenderPass {
+            label,
+            load_op: options.load_op,
+            sampler: options.sampler,
+            render_pass,
+            enabled: true,
+            predicate: None,
+            skipped: false,
+        });
+        self.events.push(PixelsEvent::PassAdded { index });
+        self.redraw = true;
+
+        index
+    }
+
+    /// Remove the render pass at `index`. Render passes after it move down by one index.
+    ///
+    /// See [`Pixels::add_render_pass`] for how render passes are indexed.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `index` is out of bounds.
+    pub fn remove_render_pass(&mut self, index: usize) {
+        let position = self.render_pass_position(index);
+        self.renderers.remove(position);
+        self.events.push(PixelsEvent::PassRemoved { index });
+        self.redraw = true;
+    }
+
+    /// Enable or disable the render pass at `index`. Disabled render passes are skipped, but keep
+    /// their resources and position, so they can be switched back on immediately.
+    ///
+    /// See [`Pixels::add_render_pass`] for how render passes are indexed.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `index` is out of bounds.
+    pub fn set_pass_enabled(&mut self, index: usize, enabled: bool) {
+        let position = self.render_pass_position(index);
+        if self.renderers[position].enabled != enabled {
+            self.events
+                .push(PixelsEvent::PassToggled { index, enabled });
+        }
+        self.renderers[position].enabled = enabled;
+        self.redraw = true;
+    }
+
+    /// Execute the render pass at `index` only in frames where `predicate` returns `true`.
+    ///
+    /// The predicate is called once per frame, before any render pass is executed. Frames where it
+    /// returns `false` skip the pass as if it was disabled, which avoids GPU work for effects that
+    /// would not change the image, e.g. bloom on a scene without bright pixels. The predicate
+    /// replaces any predicate set before, and only applies while the pass is enabled with
+    /// [`Pixels::set_pass_enabled`].
+    ///
+    /// See [`Pixels::add_render_pass`] for how render passes are indexed.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pixels::Pixels;
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// # let bloom_factory = pixels::debug::FrameDiffPass::factory;
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    /// let bloom = pixels.add_render_pass(bloom_factory);
+    ///
+    /// // Updated by the application, e.g. from the brightest pixel in the frame
+    /// let bright = Arc::new(AtomicBool::new(false));
+    /// let predicate_bright = bright.clone();
+    /// pixels.set_pass_predicate(bloom, move || predicate_bright.load(Ordering::Relaxed));
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics when `index` is out of bounds.
+    pub fn set_pass_predicate<P>(&mut self, index: usize, predicate: P)
+    where
+        P: FnMut() -> bool + Send + 'static,
+    {
+        let index = self.render_pass_position(index);
+        self.renderers[index].predicate = Some(PassPredicate(Box::new(predicate)));
+        self.redraw = true;
+    }
+
+    /// Remove the predicate of the render pass at `index`, so it is executed in every frame again.
+    /// See [`Pixels::set_pass_predicate`].
+    ///
+    /// # Panics
+    ///
+    /// Panics when `index` is out of bounds.
+    pub fn clear_pass_predicate(&mut self, index: usize) {
+        let index = self.render_pass_position(index);
+        let renderer = &mut self.renderers[index];
+        renderer.predicate = None;
+        renderer.skipped = false;
+        self.redraw = true;
+    }
+
+    /// Move the render pass at `index` to `new_index`, shifting the render passes in between.
+    ///
+    /// See [`Pixels::add_render_pass`] for how render passes are indexed.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `index` or `new_index` is out of bounds.
+    pub fn move_render_pass(&mut self, index: usize, new_index: usize) {
+        let position = self.render_pass_position(index);
+        let new_position = self.render_pass_position(new_index);
+        let renderer = self.renderers.remove(position);
+        self.renderers.insert(new_position, renderer);
+        if index != new_index {
+            self.events.push(PixelsEvent::PassMoved {
+                from: index,
+                to: new_index,
+            });
+        }
+        self.redraw = true;
+    }
+
+    /// Add a layer: an independent RGBA pixel buffer composited over the main pixel buffer.
+    ///
+    /// Layers are drawn in the order they were added, each over the ones before it, with alpha
+    /// blending in linear space, unless [`Pixels::set_layer_srgb_blending`] is enabled. Every
+    /// layer has its own resolution, and is scaled by its own factor to cover the same area of
+    /// the surface as the main pixel buffer in every [`ScalingMode`], so a 640x480 UI layer lines
+    /// up with a 320x240 game layer. Layers with another aspect ratio are stretched to fit.
+    /// Layers always use 4 bytes per pixel in RGBA order with sRGB encoding, regardless of the
+    /// frame format, and start out fully transparent.
+    ///
+    /// Layers are composited right after the scaling renderer, so render passes added to the
+    /// builder draw over them, but do not sample them.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use pixels::Pixels;
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1280, 960, surface);
+    /// // The main pixel buffer is the background layer
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    /// let sprites = pixels.add_layer(320, 240);
+    /// let ui = pixels.add_layer(640, 480);
+    ///
+    /// // ... Draw the background into `pixels.get_frame()`
+    /// // ... Draw sprites into `pixels.get_layer_frame(sprites)`
+    /// let frame = pixels.get_layer_frame(ui);
+    /// for pixel in frame[..640 * 16 * 4].chunks_exact_mut(4) {
+    ///     pixel.copy_from_slice(&[0x00, 0x00, 0x00, 0x80]);
+    /// }
+    ///
+    /// pixels.render()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics when `width` or `height` are 0.
+    pub fn add_layer(&mut self, width: u32, height: u32) -> LayerId {
+        assert!(width > 0);
+        assert!(height > 0);
+
+        self.redraw = true;
+        self.layers
+            .add(&self.device, width, height, self.scaling.eyes())
+    }
+
+    /// Remove a layer created with [`Pixels::add_layer`].
+    ///
+    /// # Panics
+    ///
+    /// Panics when the layer was already removed.
+    pub fn remove_layer(&mut self, layer: LayerId) {
+        self.layers.remove(layer);
+        self.redraw = true;
+    }
+
+    /// Get a mutable byte slice for a layer's RGBA pixels. The layer is uploaded with the next
+    /// render.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the layer was removed.
+    pub fn get_layer_frame(&mut self, layer: LayerId) -> &mut [u8] {
+        self.layers.frame_mut(layer)
+    }
+
+    /// Show or hide a layer, without discarding its pixels.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the layer was removed.
+    pub fn set_layer_visible(&mut self, layer: LayerId, visible: bool) {
+        self.layers.set_visible(layer, visible);
+        self.redraw = true;
+    }
+
+    /// Blend layers in sRGB space, the legacy behavior of many image editors, instead of linear
+    /// space.
+    ///
+    /// Linear blending is physically correct, and avoids the dark fringes that blending in sRGB
+    /// space produces around antialiased edges. Enable this for art which was drawn to look right
+    /// with the old behavior. It is disabled by default.
+    ///
+    /// The render target cannot be blended in sRGB space by the GPU, so each layer is blended
+    /// over a copy of everything drawn before it. Window surfaces draw the frame into a texture
+    /// that is copied to the swap chain while layers are visible. Layers drawn by
+    /// [`Pixels::render_with_passes`] onto other render targets are still blended in linear
+    /// space.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use pixels::Pixels;
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1280, 960, surface);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    /// let ui = pixels.add_layer(640, 480);
+    ///
+    /// // The UI art was made for blending in sRGB space
+    /// pixels.set_layer_srgb_blending(true);
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn set_layer_srgb_blending(&mut self, enabled: bool) {
+        self.layers.set_srgb_blending(
+            &self.device,
+            enabled,
+            self.surface_texture.width,
+            self.surface_texture.height,
+        );
+        self.redraw = true;
+    }
+
+    /// Replace the palette used by the indexed frame formats.
+    ///
+    /// The palette is uploaded to the GPU with the next frame. It has no effect unless the frame
+    /// format is [`FrameFormat::Indexed8`] or [`FrameFormat::Planar`]. The default palette is
+    /// entirely transparent black.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use pixels::palette::Palette;
+    /// use pixels::{FrameFormat, PixelsBuilder};
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let mut pixels = PixelsBuilder::new(320, 200, surface_texture)
+    ///     .frame_format(FrameFormat::Indexed8)
+    ///     .build()?;
+    ///
+    /// let grays = (0..=255).map(|i| [i, i, i, 0xff]).collect();
+    /// pixels.set_palette(&Palette::new(grays));
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn set_palette(&mut self, palette: &Palette) {
+        let premultiply_alpha = self.premultiply_alpha;
+        let srgb = is_srgb(self.texture_format);
+        if let Some(converter) = &mut self.converter {
+            if premultiply_alpha {
+                let mut colors = palette.to_array();
+                premultiply_colors(&mut colors, srgb);
+                converter.set_colors(0, &colors);
+            } else {
+                converter.set_palette(palette);
+            }
+            self.dirty.mark(0..self.texture_extent.height);
+        }
+    }
+
+    /// Replace consecutive palette entries, beginning at `start`.
+    ///
+    /// Only the changed entries are uploaded to the GPU. Rows pushed with
+    /// [`Pixels::push_scanlines`] are expanded with the palette at the time they are pushed, so
+    /// changing colors between scanlines gives raster effects like color bars.
+    ///
+    /// # Raster effects and `render`
+    ///
+    /// The palette is not recorded per row. Like [`Pixels::set_palette`], the next call to
+    /// [`Pixels::render`] expands the *whole* frame again with the palette as it is at that time,
+    /// which replaces every raster effect with the colors of the last scanline. Frames which
+    /// change the palette between scanlines must push all of their rows with
+    /// [`Pixels::push_scanlines`] and be shown with [`Pixels::present_partial`], never with
+    /// [`Pixels::render`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use pixels::{FrameFormat, PixelsBuilder};
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let mut pixels = PixelsBuilder::new(256, 240, surface_texture)
+    ///     .frame_format(FrameFormat::Indexed8)
+    ///     .build()?;
+    /// let scanline = vec![0; 256];
+    ///
+    /// for y in 0..240 {
+    ///     // Change the background color on every scanline
+    ///     pixels.set_palette_colors(0, &[[y as u8, 0x00, 0x80, 0xff]]);
+    ///     pixels.push_scanlines(y, &scanline);
+    /// }
+    /// pixels.present_partial()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics when the entries extend past the end of the 256 color palette.
+    pub fn set_palette_colors(&mut self, start: u8, colors: &[[u8; 4]]) {
+        assert!(start as usize + colors.len() <= palette::MAX_COLORS);
+
+        let premultiply_alpha = self.premultiply_alpha;
+        let srgb = is_srgb(self.texture_format);
+        if let Some(converter) = &mut self.converter {
+            let mut colors = colors.to_vec();
+            if premultiply_alpha {
+                premultiply_colors(&mut colors, srgb);
+            }
+            converter.set_colors(start as usize, &colors);
+            self.dirty.mark(0..self.texture_extent.height);
+        }
+    }
+
+    /// Play a sequence of frames in place of the pixel buffer.
+    ///
+    /// All `frames` are uploaded to the GPU once, and [`Pixels::render`] cycles through them at
+    /// `fps` frames per second with a copy on the GPU, without uploading anything from the CPU.
+    /// This is ideal for splash screens and simple image sequences. The animation loops until
+    /// [`Pixels::clear_animation`] is called, and [`Pixels::needs_redraw`] returns `true` whenever
+    /// the next frame is due.
+    ///
+    /// Each frame has the same layout as the pixel buffer returned by [`Pixels::get_frame`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use pixels::Pixels;
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    /// let frames: Vec<Vec<u8>> = (0..=255).map(|i| vec![i; 320 * 240 * 4]).collect();
+    /// let frames: Vec<&[u8]> = frames.iter().map(Vec::as_slice).collect();
+    ///
+    /// pixels.set_animation(&frames, 30.0);
+    /// loop {
+    ///     pixels.render()?;
+    /// }
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics when `frames` is empty, when any frame is not the same size as the pixel buffer,
+    /// when `fps` is not positive and finite, when the frame format is not
+    /// [`FrameFormat::Texture`], or when compute passes were added with
+    /// [`PixelsBuilder::add_compute_pass`].
+    pub fn set_animation(&mut self, frames: &[&[u8]], fps: f64) {
+        self.assert_cpu_frame();
+        assert!(!frames.is_empty());
+        assert!(frames.iter().all(|frame| frame.len() == self.pixels.len()));
+        assert!(fps.is_finite() && fps > 0.0);
+        assert_eq!(self.frame_format, FrameFormat::Texture);
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("pixels_animation_upload"),
+            });
+        let prepared: Vec<Vec<u8>>;
+        let frames = if self.bottom_up_rows || self.premultiply_alpha {
+            let height = self.texture_extent.height;
+            prepared = frames
+                .iter()
+                .map(|frame| {
+                    let mut output = frame.to_vec();
+                    if self.bottom_up_rows {
+                        self.frame_format.flip_rows(frame, &mut output, height);
+                    }
+                    if self.premultiply_alpha {
+                        color::premultiply_alpha(&mut output, is_srgb(self.texture_format));
+                    }
+                    output
+                })
+                .collect();
+            prepared.iter().map(Vec::as_slice).collect()
+        } else {
+            frames.to_vec()
+        };
+        self.animation = Some(Animation::new(
+            &self.device,
+            &mut encoder,
+            &frames,
+            fps,
+            self.texture_format,
+            self.texture_extent,
+            self.texture_extent.width * self.texture_format_size,
+            self.clock.now(),
+        ));
+        lock(&self.queue).submit(&[encoder.finish()]);
+    }
+
+    /// Stop the animation started with [`Pixels::set_animation`], and show the pixel buffer again.
+    pub fn clear_animation(&mut self) {
+        if self.animation.take().is_some() {
+            self.dirty.mark(0..self.texture_extent.height);
+        }
+    }
+
+    /// Change the row order of the pixel buffer. See [`PixelsBuilder::bottom_up_rows`].
+    ///
+    /// The entire pixel buffer is uploaded again by the next call to [`Pixels::render`]. An
+    /// animation started with [`Pixels::set_animation`] keeps the row order it was started with.
+    pub fn set_bottom_up_rows(&mut self, bottom_up_rows: bool) {
+        if bottom_up_rows != self.bottom_up_rows {
+            self.bottom_up_rows = bottom_up_rows;
+            self.dirty.mark(0..self.texture_extent.height);
+            self.update_upload_target();
+        }
+    }
+
+    /// Get a handle for uploading whole frames to the texture from another thread.
+    ///
+    /// See [`TextureUploader`].
+    ///
+    /// # Panics
+    ///
+    /// Panics when the frame format is converted on the GPU, i.e. anything but
+    /// [`FrameFormat::Texture`], or when compute passes were added with
+    /// [`PixelsBuilder::add_compute_pass`].
+    pub fn texture_uploader(&self) -> TextureUploader {
+        self.assert_cpu_frame();
+        assert!(
+            self.converter.is_none(),
+            "Frames in this format are converted on the GPU, and cannot be uploaded directly"
+        );
+
+        TextureUploader::new(
+            self.device.clone(),
+            self.queue.clone(),
+            self.upload_target.clone(),
+        )
+    }
+
+    /// Share the texture, and how frames are uploaded to it, with [`TextureUploader`] handles.
+    fn update_upload_target(&self) {
+        let mut target = lock(&self.upload_target);
+        target.texture = Arc::clone(&self.texture);
+        target.extent = self.texture_extent;
+        target.bottom_up_rows = self.bottom_up_rows;
+    }
+
+    /// Get a handle for changing palette entries from other threads.
+    ///
+    /// Changes made through the handle are applied with the next call to [`Pixels::render`].
+    /// See [`palette::PaletteHandle`].
+    pub fn palette_handle(&self) -> PaletteHandle {
+        self.palette_handle.clone()
+    }
+
+    /// Returns `true` when the surface is out of date and should be redrawn with
+    /// [`Pixels::render`].
+    ///
+    /// The surface is out of date after the pixel buffer is written through
+    /// [`Pixels::get_frame`] or [`Pixels::get_tracked_frame`], after the surface is resized, after
+    /// rows are pushed with [`Pixels::push_scanlines`], or while a screenshot is pending. Event
+    /// driven applications like editors and image viewers can check this before requesting a
+    /// redraw from the window system, instead of rendering unconditionally at the refresh rate.
+    ///
+    /// Render passes which animate on their own are not tracked; use [`Pixels::request_redraw`]
+    /// to redraw for any other reason.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use pixels::Pixels;
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    ///
+    /// // ... Handle input, which may or may not draw to the pixel buffer
+    ///
+    /// if pixels.needs_redraw() {
+    ///     pixels.render()?;
+    /// }
+    /// assert!(!pixels.needs_redraw());
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn needs_redraw(&self) -> bool {
+        self.redraw
+            || self.dirty.is_dirty()
+            || self.screenshots.is_requested()
+            || self.palette_handle.is_pending()
+            || self.layers.is_dirty()
+            || lock(&self.upload_target).uploaded
+            || self
+                .animation
+                .as_ref()
+                .map_or(false, |animation| animation.is_changed(self.clock.now()))
+            || self.interpolator.as_ref().map_or(false, |interpolator| {
+                interpolator.is_blending(self.clock.now())
+            })
+    }
+
+    /// Take all changes to the internal state since the last call, oldest first.
+    ///
+    /// Up to 64 events are kept between calls; older events are dropped. See [`PixelsEvent`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pixels::{Pixels, PixelsEvent};
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    /// pixels.resize(1280, 960);
+    ///
+    /// for event in pixels.poll_events() {
+    ///     if let PixelsEvent::ScaleChanged { destination, .. } = event {
+    ///         // ... Move the GUI viewport to `destination`
+    ///     }
+    /// }
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn poll_events(&mut self) -> impl Iterator<Item = PixelsEvent> + '_ {
+        self.events.drain()
+    }
+
+    /// Mark the surface as out of date, so that [`Pixels::needs_redraw`] returns `true` until the
+    /// next render.
+    pub fn request_redraw(&mut self) {
+        self.redraw = true;
+    }
+
+    /// Mark the pixel buffer as backgrounded, e.g. when its window loses focus or is hidden.
+    ///
+    /// While backgrounded, [`Pixels::render`] and [`Pixels::render_with`] only upload and present
+    /// as often as the [`BackgroundThrottle`] allows, and return immediately otherwise. Leaving the
+    /// background redraws the surface with the next render. See
+    /// [`PixelsBuilder::background_throttle`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use pixels::Pixels;
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(320, 240, surface);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    /// # let focused = false;
+    ///
+    /// // ... On `WindowEvent::Focused(focused)`
+    /// pixels.set_background(!focused);
+    ///
+    /// // Renders at most 10 frames per second while unfocused
+    /// pixels.render()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn set_background(&mut self, background: bool) {
+        if self.background && !background {
+            self.redraw = true;
+        }
+        self.background = background;
+    }
+
+    /// Returns `true` when the pixel buffer is backgrounded. See [`Pixels::set_background`].
+    pub fn is_background(&self) -> bool {
+        self.background
+    }
+
+    /// The time since the pixel buffer was built.
+    ///
+    /// Render passes which animate should use this as their time uniform, so that they stand still
+    /// in deterministic mode. It is passed to every render pass with [`RenderPass::update`]. See
+    /// [`PixelsBuilder::deterministic`].
+    pub fn time(&self) -> Duration {
+        self.clock.elapsed()
+    }
+
+    /// Move time forward by `duration` in deterministic mode.
+    ///
+    /// This has no effect unless [`PixelsBuilder::deterministic`] is enabled, where time otherwise
+    /// stands still.
+    pub fn advance_time(&mut self, duration: Duration) {
+        if self.clock.is_fixed() {
+            self.clock.advance(duration);
+            self.redraw = true;
+        }
+    }
+
+    /// A seed for render passes which generate noise.
+    ///
+    /// The seed is random for each pixel buffer, and a fixed value in deterministic mode. See
+    /// [`PixelsBuilder::deterministic`].
+    pub fn seed(&self) -> u64 {
+        self.clock.seed()
+    }
+
+    /// The texture format of the surface, which every render pass draws into.
+    ///
+    /// Pipelines created for [`Pixels::render_with`] must target this format. See
+    /// [`PixelsBuilder::surface_texture_format`].
+    pub fn surface_texture_format(&self) -> wgpu::TextureFormat {
+        self.surface_format
+    }
+
+    /// The device which owns all GPU resources of the pixel buffer.
+    ///
+    /// Resources for custom render passes and overlays, like textures and buffers, can be created
+    /// with it at any time after the pixel buffer was built. The handle is shared, so cloning it
+    /// keeps the device alive independently of the pixel buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use pixels::Pixels;
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(320, 240, surface);
+    /// let pixels = Pixels::new(320, 240, surface_texture)?;
+    ///
+    /// let uniforms = pixels
+    ///     .device()
+    ///     .create_buffer_with_data(&[0; 16], wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST);
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// The queue which all work of the pixel buffer is submitted to.
+    ///
+    /// Work submitted to the queue is ordered with the frames rendered by [`Pixels::render`]. See
+    /// [`Pixels::submission_fence`] for waiting on it.
+    pub fn queue(&self) -> &Queue {
+        &self.queue
+    }
+
+    /// Which adapter is in use, and which requested options were downgraded when the pixel buffer
+    /// was built. See [`CapabilitiesReport`].
+    pub fn capabilities(&self) -> &CapabilitiesReport {
+        &self.capabilities
+    }
+
+    /// Change the color of the borders around the scaled pixel buffer.
+    ///
+    /// The surface is redrawn with the new color by the next call to [`Pixels::render`]. See
+    /// [`PixelsBuilder::clear_color`].
+    pub fn set_clear_color(&mut self, clear_color: wgpu::Color) {
+        self.clear_color = clear_color;
+        self.redraw = true;
+    }
+
+    /// Change how the pixel buffer is scaled to the surface.
+    ///
+    /// Only the sampler and transforms of the scaling pass are rebuilt, so this is cheap enough to
+    /// preview options live from a display settings menu. All render passes are notified of the
+    /// new scaling through [`RenderPass::surface_resized`], with an unchanged surface size. See
+    /// [`PixelsBuilder::scaling_mode`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pixels::{Pixels, ScalingMode};
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    ///
+    /// // The player picked "Fill screen" in the video options
+    /// pixels.set_scaling_mode(ScalingMode::FitAspect);
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn set_scaling_mode(&mut self, scaling_mode: ScalingMode) {
+        let mode = self.dimension.scaling_mode(scaling_mode);
+        self.scaling.mode = mode;
+        self.set_scaling_options(ScalingOptions {
+            mode,
+            ..*lock(&self.scaling_options)
+        });
+    }
+
+    /// Change the filter used by the scaling pass to sample the pixel buffer.
+    ///
+    /// Only the sampler of the scaling pass is rebuilt. See [`PixelsBuilder::filter_mode`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use pixels::Pixels;
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    ///
+    /// // The player enabled "Smooth pixels" in the video options
+    /// pixels.set_filter_mode(wgpu::FilterMode::Linear);
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn set_filter_mode(&mut self, filter_mode: wgpu::FilterMode) {
+        self.set_scaling_options(ScalingOptions {
+            filter: filter_mode,
+            ..*lock(&self.scaling_options)
+        });
+    }
+
+    /// Render the pixel buffer into only a part of the surface, e.g. to leave room for native
+    /// menus or side panels around it.
+    ///
+    /// The viewport is given in physical pixel units, with the origin at the top-left corner of
+    /// the surface. The pixel buffer is scaled to fit the viewport with the [`ScalingMode`], as if
+    /// the viewport was the entire surface, and nothing is drawn outside of it except for the
+    /// clear color. [`Pixels::window_pos_to_pixel`] reports positions outside of the viewport as
+    /// outside of the pixel buffer.
+    ///
+    /// The viewport is kept when the surface is resized, and clipped to the surface, so update it
+    /// along with the layout of the rest of the window. Render passes are notified of the new
+    /// scaling through [`RenderPass::surface_resized`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use pixels::Pixels;
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    ///
+    /// // Reserve 64 pixels on the left for a tool palette
+    /// pixels.set_viewport(64, 0, 960, 768);
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn set_viewport(&mut self, x: u32, y: u32, width: u32, height: u32) {
+        let viewport = Rect {
+            x: x as f32,
+            y: y as f32,
+            width: width as f32,
+            height: height as f32,
+        };
+        self.scaling.viewport = Some(viewport);
+        self.set_scaling_options(ScalingOptions {
+            viewport: Some(viewport),
+            ..*lock(&self.scaling_options)
+        });
+    }
+
+    /// Render the pixel buffer into the entire surface again, after [`Pixels::set_viewport`].
+    pub fn reset_viewport(&mut self) {
+        self.scaling.viewport = None;
+        self.set_scaling_options(ScalingOptions {
+            viewport: None,
+            ..*lock(&self.scaling_options)
+        });
+    }
+
+    /// Scale only a rectangle of the pixel buffer to the surface, e.g. to show part of a large
+    /// world map in an embedded preview.
+    ///
+    /// The rectangle is given in pixels, with the origin at the top-left corner of the pixel
+    /// buffer. It is scaled with the [`ScalingMode`] as if it was the entire buffer, and the rest
+    /// of the buffer is not drawn. [`Pixels::window_pos_to_pixel`] still refers to the entire
+    /// buffer, so positions over the rectangle map to the pixels shown there. Nothing is copied,
+    /// so moving the rectangle every frame is cheap.
+    ///
+    /// The rectangle replaces the [`Overscan`] crop, and it stays proportional when the pixel
+    /// buffer is resized, like the overscan. Render passes are notified of the new scaling
+    /// through [`RenderPass::surface_resized`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use pixels::Pixels;
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(640, 480, surface);
+    /// let mut pixels = Pixels::new(4096, 4096, surface_texture)?;
+    ///
+    /// // Show the 320x240 region around the cursor of the map editor
+    /// pixels.set_source_rect(1024, 512, 320, 240);
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics when the rectangle is empty, or when it extends past the edges of the pixel buffer.
+    pub fn set_source_rect(&mut self, x: u32, y: u32, width: u32, height: u32) {
+        let (buffer_width, buffer_height) = (self.texture_extent.width, self.texture_extent.height);
+        assert!(width > 0 && height > 0);
+        assert!(x
+            .checked_add(width)
+            .map_or(false, |right| right <= buffer_width));
+        assert!(y
+            .checked_add(height)
+            .map_or(false, |bottom| bottom <= buffer_height));
+
+        let overscan = Overscan::new(x, y, buffer_width - x - width, buffer_height - y - height);
+        self.set_crop(Crop::new(overscan, buffer_width, buffer_height));
+    }
+
+    /// Scale the entire pixel buffer to the surface again, after [`Pixels::set_source_rect`].
+    ///
+    /// The [`Overscan`] crop configured with [`PixelsBuilder::overscan`] is restored.
+    pub fn reset_source_rect(&mut self) {
+        self.set_crop(self.overscan_crop);
+    }
+
+    /// Replace the crop of the pixel buffer, for both `Pixels` and the scaling renderer.
+    fn set_crop(&mut self, crop: Crop) {
+        self.scaling.crop = crop;
+        self.set_scaling_options(ScalingOptions {
+            crop,
+            ..*lock(&self.scaling_options)
+        });
+    }
+
+    /// Move the pixel buffer on the surface by `dx` and `dy` pixels, e.g. for screen shake.
+    ///
+    /// The offset is in pixel buffer units and may be fractional. The image is clipped to where
+    /// it is drawn without an offset, so the edges uncovered by the offset show the clear color
+    /// instead of the surroundings of the pixel buffer, and nothing is drawn outside of it. The
+    /// pixel buffer is not redrawn, and [`Pixels::window_pos_to_pixel`] follows the moved image.
+    /// Render passes are notified of the new scaling through [`RenderPass::surface_resized`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use pixels::Pixels;
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    ///
+    /// // Shake the screen for a few frames after an explosion
+    /// for dx in &[3.0, -2.0, 1.0, 0.0] {
+    ///     pixels.set_render_offset(*dx, 0.0);
+    ///     pixels.render()?;
+    /// }
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn set_render_offset(&mut self, dx: f32, dy: f32) {
+        self.scaling.offset = (dx, dy);
+        self.set_scaling_options(ScalingOptions {
+            offset: (dx, dy),
+            ..*lock(&self.scaling_options)
+        });
+    }
+
+    /// Write the frames retained by [`PixelsBuilder::keep_frame_history`] to the directory `dir`.
+    ///
+    /// Frames are written oldest first as `frame-0000.pam`, `frame-0001.pam`, etc. The directory
+    /// is created if it does not exist. Nothing is written when the frame history is disabled.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use pixels::PixelsBuilder;
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let mut pixels = PixelsBuilder::new(320, 240, surface_texture)
+    ///     .keep_frame_history(60)
+    ///     .build()?;
+    ///
+    /// // ... Render a glitchy frame
+    ///
+    /// pixels.dump_history("glitch")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the directory or any of the files cannot be written.
+    pub fn dump_history<P: AsRef<Path>>(&self, dir: P) -> io::Result<()> {
+        lock(&self.frame_history).dump(
+            dir.as_ref(),
+            self.texture_extent.width,
+            self.texture_extent.height,
+            self.texture_format_size,
+        )
+    }
+
+    /// Discard all frames retained by [`PixelsBuilder::keep_frame_history`].
+    pub fn clear_history(&mut self) {
+        lock(&self.frame_history).clear();
+    }
+
+    /// Register a panic hook that writes the last rendered frame to the directory `dir`.
+    ///
+    /// The frame is written as `final-frame.pam`. When `include_history` is `true`, the frames
+    /// retained by [`PixelsBuilder::keep_frame_history`] are also written to the `history`
+    /// subdirectory. The previously registered panic hook still runs afterward, so this composes
+    /// with other crash reporters.
+    ///
+    /// The returned [`CrashDump`] handle can be used to write the same files from your own
+    /// shutdown or error handling code. Keeping the last frame costs one copy of the pixel buffer
+    /// per render. Nothing is written when compute passes fill the texture, because there is no
+    /// pixel buffer on the CPU.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use pixels::Pixels;
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    /// let crash_dump = pixels.install_crash_dump("crash-report", false);
+    ///
+    /// // ... Run the event loop, then on a fatal error:
+    /// crash_dump.dump()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn install_crash_dump<P: Into<PathBuf>>(
+        &mut self,
+        dir: P,
+        include_history: bool,
+    ) -> CrashDump {
+        let history = if include_history {
+            Some(self.frame_history.clone())
+        } else {
+            None
+        };
+        let crash_dump = CrashDump::install(
+            dir.into(),
+            self.texture_extent.width,
+            self.texture_extent.height,
+            self.texture_format_size,
+            history,
+        );
+        self.crash_dump = Some(crash_dump.clone());
+
+        crash_dump
+    }
+
+    /// The factor by which the pixel buffer is scaled on the surface, horizontally and vertically.
+    ///
+    /// Each pixel in the buffer covers this many physical pixels on the surface.
+    pub fn current_scale(&self) -> (f32, f32) {
+        self.scaling_matrix().scale
+    }
+
+    /// The rectangle on the surface where the pixel buffer is drawn, in physical pixel units.
+    ///
+    /// The rectangle may extend past the edges of the surface when the surface is smaller than
+    /// the pixel buffer. With an [`Overscan`] crop, it includes the cropped edges, which are not
+    /// drawn.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use pixels::Pixels;
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let pixels = Pixels::new(320, 240, surface_texture)?;
+    ///
+    /// // The buffer is scaled by 3x and centered on the 1024x768 surface
+    /// let rect = pixels.destination_rect();
+    /// assert_eq!((rect.x, rect.y), (32.0, 24.0));
+    /// assert_eq!((rect.width, rect.height), (960.0, 720.0));
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn destination_rect(&self) -> Rect {
+        self.scaling_matrix().destination
+    }
+
+    /// The integer factor by which the pixel buffer is scaled on the surface.
+    ///
+    /// Returns `None` when the buffer is not scaled by the same whole number on both axes, e.g.
+    /// with [`ScalingMode::FitAspect`] or [`ScalingMode::Stretch`]. Use
+    /// [`Pixels::current_scale`] for the exact factors.
+    pub fn integer_scale(&self) -> Option<u32> {
+        let (x, y) = self.scaling_matrix().scale;
+
+        if x == y && x >= 1.0 && x.fract() == 0.0 {
+            Some(x as u32)
+        } else {
+            None
+        }
+    }
+
+    /// The transformation matrix applied by the scaling pass, in column-major order.
+    ///
+    /// The matrix maps the unit quad of the pixel buffer, with corners at `(-1, -1)` and
+    /// `(1, 1)`, to [`Pixels::destination_rect`] in clip space on the surface. Custom render
+    /// passes can use it to draw in the same place as the pixel buffer.
+    pub fn scaling_transform(&self) -> [f32; 16] {
+        let mut transform = [0.0; 16];
+        for (i, col) in self.scaling_matrix().transform.cols.iter().enumerate() {
+            transform[i * 4..i * 4 + 4].copy_from_slice(&[col.x, col.y, col.z, col.w]);
+        }
+
+        transform
+    }
+
+    /// Calculate the pixel location from a physical location on the window,
+    /// dealing with window resizing, scaling, and margins. Takes a physical
+    /// position (x, y) within the window, and returns a pixel position (x, y).
+    ///
+    /// The location must be given in physical units (for example, winit's `PhysicalLocation`)
+    ///
+    /// If the given physical position is outside of the drawing area, this
+    /// function returns an `Err` value with the pixel coordinates outside of
+    /// the screen, using isize instead of usize. See [`Pixels::window_pos_to_pixel_with`] for
+    /// other ways of handling these positions.
+    ///
+    /// Render passes which distort the image are taken into account, see
+    /// [`RenderPass::unwarp_position`].
+    ///
+    /// ```no_run
+    /// # use pixels::Pixels;
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// const WIDTH:  u32 = 320;
+    /// const HEIGHT: u32 = 240;
+    ///
+    /// let mut pixels = Pixels::new(WIDTH, HEIGHT, surface_texture)?;
+    ///
+    /// // A cursor position in physical units
+    /// let cursor_position: (f32, f32) = winit::dpi::PhysicalPosition::new(0.0, 0.0).into();
+    ///
+    /// // Convert it to a pixel location
+    /// let pixel_position: (usize, usize) = pixels.window_pos_to_pixel(cursor_position)
+    ///     // Clamp the output to within the screen
+    ///     .unwrap_or_else(|pos| pixels.clamp_pixel_pos(pos));
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn window_pos_to_pixel(
+        &self,
+        physical_position: (f32, f32),
+    ) -> Result<(usize, usize), (isize, isize)> {
+        // Undo any distortion applied by render passes
+        let physical_position = self
+            .renderers
+            .iter()
+            .rev()
+            .fold(physical_position, |position, renderer| {
+                renderer.render_pass.unwarp_position(position)
+            });
+
+        let physical_width = self.surface_texture.width as f32;
+        let physical_height = self.surface_texture.height as f32;
+
+        let pixels_width = self.texture_extent.width as f32;
+        let pixels_height = self.texture_extent.height as f32;
+
+        // Map the position to clip space, and back through the transform to the unit quad
+        let pos = ultraviolet::Vec4::new(
+            physical_position.0 / physical_width * 2.0 - 1.0,
+            1.0 - physical_position.1 / physical_height * 2.0,
+            0.0,
+            1.0,
+        );
+
+        let pos = self.scaling_matrix_inverse * pos;
+
+        let pos = (
+            (pos.x / pos.w + 1.0) / 2.0 * pixels_width,
+            (pos.y / pos.w + 1.0) / 2.0 * pixels_height,
+        );
+        let pixel_x = pos.0.floor() as isize;
+        let pixel_y = pos.1.floor() as isize;
+
+        // Positions outside of the viewport are not over the pixel buffer, even where a cropped
+        // edge of the pixel buffer would be
+        let viewport = self
+            .scaling
+            .viewport_area((physical_width, physical_height));
+        let in_viewport = physical_position.0 >= viewport.x
+            && physical_position.0 < viewport.x + viewport.width
+            && physical_position.1 >= viewport.y
+            && physical_position.1 < viewport.y + viewport.height;
+
+        if !in_viewport
+            || pixel_x < 0
+            || pixel_x >= self.texture_extent.width as isize
+            || pixel_y < 0
+            || pixel_y >= self.texture_extent.height as isize
+        {
+            Err((pixel_x, pixel_y))
+        } else {
+            Ok((pixel_x as usize, pixel_y as usize))
+        }
+    }
+
+    /// Calculate the pixel location from a physical location on the window, like
+    /// [`Pixels::window_pos_to_pixel`], with a choice of how positions outside of the pixel buffer
+    /// are reported. See [`EdgeBehavior`].
+    ///
+    /// ```no_run
+    /// use pixels::{EdgeBehavior, Pixels, PixelPosition};
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    ///
+    /// // A click in the border above the pixel buffer
+    /// let position = pixels.window_pos_to_pixel_with((512.0, 10.0), EdgeBehavior::OutsideViewport);
+    /// assert_eq!(position, PixelPosition::OutsideViewport);
+    ///
+    /// let position = pixels.window_pos_to_pixel_with((512.0, 10.0), EdgeBehavior::ClampToEdge);
+    /// assert_eq!(position, PixelPosition::Inside(160, 0));
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn window_pos_to_pixel_with(
+        &self,
+        physical_position: (f32, f32),
+        edge_behavior: EdgeBehavior,
+    ) -> PixelPosition {
+        match (self.window_pos_to_pixel(physical_position), edge_behavior) {
+            (Ok((x, y)), _) => PixelPosition::Inside(x, y),
+            (Err(pos), EdgeBehavior::ClampToEdge) => {
+                let (x, y) = self.clamp_pixel_pos(pos);
+                PixelPosition::Inside(x, y)
+            }
+            (Err(_), EdgeBehavior::OutsideViewport) => PixelPosition::OutsideViewport,
+            (Err((x, y)), EdgeBehavior::Raw) => PixelPosition::Raw(x, y),
+        }
+    }
+
+    /// Convert a relative mouse motion in physical units to a motion in pixels, using the current
+    /// scale of the pixel buffer on the surface.
+    ///
+    /// This is meant for pointer-locked controls, like mouse look in a first-person game, where
+    /// the cursor is grabbed and only raw deltas are reported (for example, winit's
+    /// `DeviceEvent::MouseMotion`). The result has a fractional part, which should be accumulated
+    /// so that slow mouse movements are not lost.
+    ///
+    /// ```no_run
+    /// # use pixels::Pixels;
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    ///
+    /// // The pixel buffer is scaled by 3
+    /// let (dx, dy) = pixels.relative_motion_to_pixels(6.0, -1.5);
+    /// assert_eq!((dx, dy), (2.0, -0.5));
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn relative_motion_to_pixels(&self, dx: f32, dy: f32) -> (f32, f32) {
+        let (scale_x, scale_y) = self.scaling_matrix().scale;
+
+        (dx / scale_x, dy / scale_y)
+    }
+
+    /// Clamp a pixel position to the pixel buffer size.
+    ///
+    /// This can be used to clamp the `Err` value returned by [`Pixels::window_pos_to_pixel`]
+    /// to a position clamped within the drawing area.
+    ///
+    /// ```no_run
+    /// # use pixels::Pixels;
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// const WIDTH:  u32 = 320;
+    /// const HEIGHT: u32 = 240;
+    ///
+    /// let mut pixels = Pixels::new(WIDTH, HEIGHT, surface_texture)?;
+    ///
+    /// let pixel_pos = pixels.clamp_pixel_pos((-19, 20));
+    /// assert_eq!(pixel_pos, (0, 20));
+    ///
+    /// let pixel_pos = pixels.clamp_pixel_pos((11, 3000));
+    /// assert_eq!(pixel_pos, (11, 240));
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn clamp_pixel_pos(&self, pos: (isize, isize)) -> (usize, usize) {
+        (
+            pos.0.max(0).min(self.texture_extent.width as isize - 1) as usize,
+            pos.1.max(0).min(self.texture_extent.height as isize - 1) as usize,
+        )
+    }
+
+    /// The number of render passes executed after the scaling renderer.
+    fn render_pass_count(&self) -> usize {
+        self.renderers.len() - self.scaling_renderer - 1
+    }
+
+    /// The position in `renderers` of the render pass at `index`, counted from the first render
+    /// pass after the scaling renderer.
+    fn render_pass_position(&self, index: usize) -> usize {
+        assert!(
+            index < self.render_pass_count(),
+            "Render pass index {} is out of bounds",
+            index
+        );
+
+        self.scaling_renderer + 1 + index
+    }
+
+    /// Hand new options to the scaling renderer, and rebind it to apply them.
+    fn set_scaling_options(&mut self, options: ScalingOptions) {
+        if options == *lock(&self.scaling_options) {
+            return;
+        }
+        *lock(&self.scaling_options) = options;
+
+        let texture_view = match &self.interpolator {
+            Some(interpolator) => interpolator.view(),
+            None => self.texture.create_default_view(),
+        };
+        self.renderers[self.scaling_renderer]
+            .render_pass
+            .update_bindings(&texture_view, &self.texture_extent);
+
+        self.update_scaling((self.surface_texture.width, self.surface_texture.height));
+    }
+
+    /// Everything needed to record commands along with the frame.
+    fn context(&self) -> PixelsContext<'_> {
+        let scaling_matrix = self.scaling_matrix();
+
+        PixelsContext {
+            device: &self.device,
+            queue: &self.queue,
+            texture: &self.texture,
+            texture_extent: self.texture_extent,
+            surface_size: (self.surface_texture.width, self.surface_texture.height),
+            scale: scaling_matrix.scale,
+            destination: scaling_matrix.destination,
+        }
+    }
+
+    /// The scaling matrix for the current texture and surface sizes.
+    fn scaling_matrix(&self) -> renderers::ScalingMatrix {
+        self.scaling.scaling_matrix(
+            (
+                self.texture_extent.width as f32,
+                self.texture_extent.height as f32,
+            ),
+            (
+                self.surface_texture.width as f32,
+                self.surface_texture.height as f32,
+            ),
+        )
+    }
+
+    /// Returns `true` when copying the texture to the offscreen surface gives the same image as
+    /// executing all render passes. See [`PixelsBuilder::pass_through`].
+    fn can_pass_through(&self) -> bool {
+        let surface_size = (
+            self.surface_texture.width as f32,
+            self.surface_texture.height as f32,
+        );
+        let scaling_matrix = self.scaling_matrix();
+
+        self.pass_through
+            && self.surface_texture.surface.is_none()
+            && self.surface_format == self.texture_format
+            && self.interpolator.is_none()
+            && self.scaling.side_by_side.is_none()
+            && scaling_matrix.scale == (1.0, 1.0)
+            && scaling_matrix.destination
+                == Rect {
+                    x: 0.0,
+                    y: 0.0,
+                    width: surface_size.0,
+                    height: surface_size.1,
+                }
+            && !self.layers.is_visible()
+            && self.gizmos.is_empty()
+            && self
+                .renderers
+                .iter()
+                .enumerate()
+                .all(|(i, renderer)| renderer.is_active() == (i == self.scaling_renderer))
+    }
+
+    /// Returns `true` when the next frame must be drawn into the rendered frame, because it is
+    /// read back, presented again, or copied for layers blended in sRGB space. Otherwise window
+    /// surfaces draw it straight into the swap chain texture, which saves copying the frame with
+    /// another fullscreen draw.
+    fn retains_frame(&self, output: Option<&wgpu::SwapChainOutput>) -> bool {
+        output.is_none()
+            || self.screenshots.is_requested()
+            || self.dimmer.is_some()
+            || self.layers.reads_render_target()
+    }
+
+    /// Execute all render passes on the next swap chain texture, optionally uploading dirty rows.
+    ///
+    /// The frame is drawn into the rendered frame instead when [`Pixels::retains_frame`], and
+    /// copied to the swap chain texture from there.
+    fn present(&mut self, upload: bool, overlay: Option<Overlay<'_>>) -> Result<(), Error> {
+        // Deliver screenshots that finished reading back since the last frame
+        self.device.poll(wgpu::Maintain::Poll);
+        self.screenshots.poll();
+
+        let output = self.next_swap_chain_output()?;
+        let retain = self.retains_frame(output.as_ref());
+        if retain {
+            self.create_rendered_frame();
+        }
+        self.frame_retained = retain;
+        self.present_timer.record(Instant::now());
+        self.redraw = false;
+        lock(&self.upload_target).uploaded = false;
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("pixels_upload"),
+            });
+
+        let time = self.clock.elapsed();
+        let context = FrameContext {
+            frame_index: self.frame_index,
+            time,
+            delta_time: self
+                .last_frame_time
+                .map_or(Duration::from_secs(0), |last| time - last),
+            seed: self.clock.seed(),
+        };
+        self.frame_index += 1;
+        self.last_frame_time = Some(time);
+
+        // Update the pixel buffer texture, or fill it with the compute passes
+        if let Some(compute) = &mut self.compute {
+            compute.dispatch(&mut encoder, &context, &self.texture, self.texture_extent);
+        } else if upload {
+            self.upload(&mut encoder);
+        }
+
+        // Update the user data, before any render pass reads it
+        if let (Some(data), Some(user_data)) = (self.pending_user_data.take(), &self.user_data) {
+            let temp_buf = self
+                .device
+                .create_buffer_with_data(&data, wgpu::BufferUsage::COPY_SRC);
+            encoder.copy_buffer_to_buffer(&temp_buf, 0, user_data, 0, data.len() as u64);
+        }
+        self.layers.upload(&self.device, &mut encoder);
+        if let Some(interpolator) = &self.interpolator {
+            interpolator.render(
+                &mut encoder,
+                &self.texture,
+                self.texture_extent,
+                self.clock.now(),
+            );
+        }
+
+        // Decide which render passes are executed in this frame, and bring them up to date
+        for renderer in self.renderers.iter_mut() {
+            renderer.update_predicate();
+            if renderer.is_active() {
+                renderer.render_pass.update(&mut encoder, &context);
+            }
+        }
+
+        // Draw into the rendered frame when it is retained, or straight into the swap chain texture
+        let frame = self.rendered_frame.as_ref().filter(|_| retain);
+        let view = match (frame, &output) {
+            (Some(frame), _) => frame.view(),
+            (None, Some(output)) => &output.view,
+            (None, None) => unreachable!("Offscreen surfaces always retain the frame"),
+        };
+
+        // Copy the texture as-is when the render passes would not change it
+        let pass_through = overlay.is_none() && self.can_pass_through();
+        if let (true, Some(frame)) = (pass_through, frame) {
+            encoder.copy_texture_to_texture(
+                wgpu::TextureCopyView {
+                    texture: &self.texture,
+                    mip_level: 0,
+                    array_layer: 0,
+                    origin: wgpu::Origin3d { x: 0, y: 0, z: 0 },
+                },
+                wgpu::TextureCopyView {
+                    texture: frame.texture(),
+                    mip_level: 0,
+                    array_layer: 0,
+                    origin: wgpu::Origin3d { x: 0, y: 0, z: 0 },
+                },
+                self.texture_extent,
+            );
+        }
+
+        let (overlay, around) = match overlay {
+            Some(Overlay::Over(overlay)) => (Some(overlay), None),
+            Some(Overlay::Around(around)) => (None, Some(around)),
+            None => (None, None),
+        };
+
+        // Let the application execute the render passes between its own commands
+        let mut command_buffers = vec![encoder.finish()];
+        if let Some(around) = around {
+            let mut encoder = self
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("pixels_render_with_passes"),
+                });
+            around(&mut encoder, view, &mut RenderPasses { pixels: &*self });
+            command_buffers.push(encoder.finish());
+        } else if !pass_through {
+            // Otherwise execute all render passes, each with its own labeled command encoder
+            for (i, renderer) in self.renderers.iter().enumerate() {
+                // TODO: Create a texture chain so that each pass receives the texture drawn by the previous
+                let mut encoder =
+                    self.device
+                        .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                            label: Some(&renderer.label),
+                        });
+                renderer.render(&mut encoder, view, self.clear_color);
+                if i == self.scaling_renderer {
+                    self.render_layers(&mut encoder, view, frame.map(RenderedFrame::texture));
+                }
+                command_buffers.push(encoder.finish());
+            }
+        }
+
+        // Draw gizmos over everything, then clear them for the next frame
+        if !self.gizmos.is_empty() {
+            let mut encoder = self
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("pixels_gizmos"),
+                });
+            self.gizmo_renderer.render(
+                &self.device,
+                &mut encoder,
+                view,
+                &self.scaling_matrix(),
+                &self.gizmos,
+            );
+            command_buffers.push(encoder.finish());
+            self.gizmos.clear();
+        }
+
+        // Draw the overlay over everything
+        if let Some(overlay) = overlay {
+            let mut encoder = self
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("pixels_overlay"),
+                });
+            overlay(&mut encoder, view, &self.context());
+            command_buffers.push(encoder.finish());
+        }
+
+        // Show the retained frame on the surface, and read it back for screenshots
+        let mut readback = None;
+        if let Some(frame) = frame {
+            let mut encoder = self
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("pixels_present"),
+                });
+            if let Some(output) = &output {
+                frame.copy_to(&mut encoder, &output.view);
+            }
+            if self.screenshots.is_requested() {
+                readback = Some(frame.readback(&self.device, &mut encoder));
+            }
+            command_buffers.push(encoder.finish());
+        }
+
+        // Keep a copy of this frame's texture for render passes that read the previous frame
+        if let Some(previous_texture) = &self.previous_texture {
+            let mut encoder = self
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("pixels_previous_frame"),
+                });
+            encoder.copy_texture_to_texture(
+                wgpu::TextureCopyView {
+                    texture: &self.texture,
+                    mip_level: 0,
+                    array_layer: 0,
+                    origin: wgpu::Origin3d { x: 0, y: 0, z: 0 },
+                },
+                wgpu::TextureCopyView {
+                    texture: previous_texture,
+                    mip_level: 0,
+                    array_layer: 0,
+                    origin: wgpu::Origin3d { x: 0, y: 0, z: 0 },
+                },
+                self.texture_extent,
+            );
+            command_buffers.push(encoder.finish());
+        }
+
+        lock(&self.queue).submit(&command_buffers);
+        self.staging.recall();
+
+        if let Some(readback) = readback {
+            self.screenshots.submit(readback);
+        }
+
+        Ok(())
+    }
+
+    /// Add the pixel buffer to the recording, as tightly packed `RGBA` rows from top to bottom.
+    #[cfg(feature = "capture")]
+    fn record_pixel_buffer(&self) {
+        let recording = match &self.recording {
+            Some(recording) if recording.is_recording(RecordingSource::PixelBuffer) => recording,
+            _ => return,
+        };
+
+        let (width, height) = (self.texture_extent.width, self.texture_extent.height);
+        let mut rgba = match &self.converter {
+            Some(converter) => converter.expand(&self.pixels),
+            None => {
+                let mut rgba = self.pixels.clone();
+                if is_bgra(self.texture_format) {
+                    for pixel in rgba.chunks_exact_mut(4) {
+                        pixel.swap(0, 2);
+                    }
+                }
+                rgba
+            }
+        };
+        if self.bottom_up_rows {
+            let flipped = rgba.clone();
+            FrameFormat::Texture.flip_rows(&flipped, &mut rgba, height);
+        }
+
+        recording.push(rgba, width, height, self.clock.elapsed());
+    }
+
+    /// Present the frames configured with [`PixelsBuilder::frame_insertion`].
+    ///
+    /// Each inserted frame waits for the next swap chain texture, so they are shown for one
+    /// refresh interval each, immediately after the rendered frame.
+    fn present_inserted_frames(&mut self) -> Result<(), Error> {
+        // Without Vsync, inserted frames are not shown at the display's cadence
+        if self.present_mode != wgpu::PresentMode::Fifo || self.swap_chain.is_none() {
+            return Ok(());
+        }
+
+        for _ in 0..self.frame_insertion.frames() {
+            let output = match self.next_swap_chain_output()? {
+                Some(output) => output,
+                None => return Ok(()),
+            };
+            self.present_timer.record(Instant::now());
+            let mut encoder = self
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("pixels_frame_insertion"),
+                });
+
+            match self.frame_insertion {
+                FrameInsertion::Disabled => unreachable!(),
+                FrameInsertion::Black { .. } => {
+                    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                        color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                            attachment: &output.view,
+                            resolve_target: None,
+                            load_op: wgpu::LoadOp::Clear,
+                            store_op: wgpu::StoreOp::Store,
+                            clear_color: wgpu::Color::BLACK,
+                        }],
+                        depth_stencil_attachment: None,
+                    });
+                }
+                FrameInsertion::Dim { brightness, .. } => {
+                    // Dim a copy of the presented frame, without executing the render passes
+                    // again. The dimmer makes every frame retained.
+                    if let Some(rendered_frame) = &self.rendered_frame {
+                        rendered_frame.copy_to(&mut encoder, &output.view);
+                    }
+                    if let Some(dimmer) = &self.dimmer {
+                        dimmer.render(&mut encoder, &output.view, brightness);
+                    }
+                }
+            }
+
+            lock(&self.queue).submit(&[encoder.finish()]);
+        }
+
+        Ok(())
+    }
+
+    /// Encode an upload of all dirty rows in the pixel buffer to the texture.
+    fn upload(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        // Animations replace the pixel buffer, which is uploaded again when they are cleared
+        let now = self.clock.now();
+        if let Some(animation) = &mut self.animation {
+            self.dirty.take();
+            if let Some(interpolator) = &mut self.interpolator {
+                if animation.is_changed(now) {
+                    interpolator.frame_uploaded(encoder, &self.texture, self.texture_extent, now);
+                }
+            }
+            animation.update(encoder, &self.texture, self.texture_extent, now);
+            return;
+        }
+
+        // Apply palette changes staged from other threads
+        if self.apply_staged_palette() {
+            self.dirty.mark(0..self.texture_extent.height);
+        }
+
+        if let Some(rows) = self.dirty.take() {
+            // Keep the outgoing frame for blending
+            if let Some(interpolator) = &mut self.interpolator {
+                interpolator.frame_uploaded(encoder, &self.texture, self.texture_extent, now);
+            }
+
+            match &mut self.converter {
+                Some(converter) => converter.convert(
+                    &self.device,
+                    encoder,
+                    &mut self.staging,
+                    &self.pixels,
+                    &self.texture,
+                    self.bottom_up_rows,
+                    0..self.texture_extent.height,
+                ),
+                None => self.upload_rows(encoder, rows),
+            }
+        }
+    }
+
+    /// Panics when compute passes fill the texture, because there is no pixel buffer on the CPU.
+    fn assert_cpu_frame(&self) {
+        assert!(
+            self.compute.is_none(),
+            "The texture is filled by compute passes, there is no pixel buffer on the CPU"
+        );
+    }
+
+    /// The number of bytes per pixel in the pixel buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the frame format does not store pixels contiguously.
+    fn frame_bytes_per_pixel(&self) -> u32 {
+        self.frame_format
+            .bytes_per_pixel(self.texture_format_size)
+            .expect("Planar frames do not have contiguous rows")
+    }
+
+    /// Hand palette changes staged through the [`PaletteHandle`] to the frame converter. Returns
+    /// `true` when any entries were changed.
+    fn apply_staged_palette(&mut self) -> bool {
+        let converter = match &mut self.converter {
+            Some(converter) => converter,
+            None => return false,
+        };
+
+        let staged = self.palette_handle.take();
+        for (&index, color) in staged.iter() {
+            let mut colors = [*color];
+            if self.premultiply_alpha {
+                premultiply_colors(&mut colors, is_srgb(self.texture_format));
+            }
+            converter.set_colors(index as usize, &colors);
+        }
+
+        !staged.is_empty()
+    }
+
+    /// Encode an upload of a band of rows in the pixel buffer to the texture.
+    fn upload_rows(&mut self, encoder: &mut wgpu::CommandEncoder, rows: Range<u32>) {
+        let bytes_per_row = self.texture_extent.width * self.texture_format_size;
+        let start = (rows.start * bytes_per_row) as usize;
+        let end = (rows.end * bytes_per_row) as usize;
+
+        // Bottom-up rows are stored in reverse, at the mirrored position in the texture
+        let pixels = &self.pixels[start..end];
+        let bottom_up_rows = self.bottom_up_rows;
+        let premultiply_alpha = self.premultiply_alpha;
+        let srgb = is_srgb(self.texture_format);
+        let buffer = self
+            .staging
+            .write(&self.device, (end - start) as u64, |data| {
+                if bottom_up_rows {
+                    FrameFormat::Texture.flip_rows(pixels, data, rows.end - rows.start);
+                } else {
+                    data.copy_from_slice(pixels);
+                }
+                if premultiply_alpha {
+                    color::premultiply_alpha(data, srgb);
+                }
+            });
+        let y = if bottom_up_rows {
+            self.texture_extent.height - rows.end
+        } else {
+            rows.start
+        };
+
+        encoder.copy_buffer_to_texture(
+            wgpu::BufferCopyView {
+                buffer,
+                offset: 0,
+                bytes_per_row,
+                rows_per_image: rows.end - rows.start,
+            },
+            wgpu::TextureCopyView {
+                texture: &self.texture,
+                mip_level: 0,
+                array_layer: 0,
+                origin: wgpu::Origin3d { x: 0, y, z: 0 },
+            },
+            wgpu::Extent3d {
+                width: self.texture_extent.width,
+                height: rows.end - rows.start,
+                depth: 1,
+            },
+        );
+    }
+
+    /// Encode the composition of all layers over `render_target`.
+    ///
+    /// Layers are only blended in sRGB space when `render_texture`, the texture of
+    /// `render_target`, is given; see [`Pixels::set_layer_srgb_blending`].
+    fn render_layers(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        render_target: &TextureView,
+        render_texture: Option<&wgpu::Texture>,
+    ) {
+        self.layers.render(
+            &self.device,
+            encoder,
+            render_target,
+            render_texture,
+            &self.scaling,
+            (
+                self.texture_extent.width as f32,
+                self.texture_extent.height as f32,
+            ),
+            (
+                self.surface_texture.width as f32,
+                self.surface_texture.height as f32,
+            ),
+        );
+    }
+
+    /// Block until the GPU has finished all submitted work.
+    pub(crate) fn wait_idle(&self) {
+        self.device.poll(wgpu::Maintain::Wait);
+    }
+
+    /// Acquire the next swap chain texture, or `None` when rendering offscreen.
+    fn next_swap_chain_output(&mut self) -> Result<Option<wgpu::SwapChainOutput>, Error> {
+        // The swap chain is only missing when it was deferred, and no resize has happened yet
+        if self.swap_chain.is_none() && self.surface_texture.surface.is_some() {
+            self.create_render_target();
+        }
+
+        let swap_chain = match self.swap_chain.as_mut() {
+            Some(swap_chain) => swap_chain,
+            None => return Ok(None),
+        };
+
+        // The swap chain becomes unusable when it is outdated, e.g. when the window was resized
+        // before the resize event arrived, or when the GPU was reset. Recreate it and try again.
+        if let Ok(output) = swap_chain.get_next_texture() {
+            return Ok(Some(output));
+        }
+        self.create_render_target();
+
+        self.swap_chain
+            .as_mut()
+            .unwrap()
+            .get_next_texture()
+            .map(Some)
+            .map_err(|_| Error::Timeout)
+    }
+
+    /// Execute all render passes again on a new rendered frame, for reading back a frame which
+    /// was drawn straight into the swap chain texture.
+    ///
+    /// The rendered frame is returned so that it outlives the command buffer submission.
+    fn capture(&self, encoder: &mut wgpu::CommandEncoder) -> RenderedFrame {
+        let frame = RenderedFrame::new(
+            &self.device,
+            self.surface_texture.width,
+            self.surface_texture.height,
+            self.surface_format,
+        );
+
+        for (i, renderer) in self.renderers.iter().enumerate() {
+            renderer.render(encoder, frame.view(), self.clear_color);
+            if i == self.scaling_renderer {
+                self.render_layers(encoder, frame.view(), Some(frame.texture()));
+            }
+        }
+
+        frame
+    }
+}
+
+impl<'req> PixelsBuilder<'req> {
+    /// Create a builder that can be finalized into a [`Pixels`] pixel buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use pixels::PixelsBuilder;
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// struct MyRenderPass {
+    ///     // ...
+    /// };
+    ///
+    /// impl pixels::RenderPass for MyRenderPass {
+    ///     // ...
+    /// # fn update_bindings(&mut self, _: &wgpu::TextureView, _: &wgpu::Extent3d) {}
+    /// # fn render(&self, _: &mut wgpu::CommandEncoder, _: &wgpu::TextureView) {}
+    /// }
+    ///
+    /// let mut pixels = PixelsBuilder::new(256, 240, surface_texture)
+    ///     .pixel_aspect_ratio(8.0 / 7.0)
+    ///     .add_render_pass(|device, queue, inputs| {
+    ///         // Create reources for MyRenderPass here
+    ///         Box::new(MyRenderPass {
+    ///             // ...
+    ///         })
+    ///     })
+    ///     .build()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    ///
+    /// The size is validated by [`PixelsBuilder::build`], which returns
+    /// [`Error::InvalidDimensions`] when `width` or `height` are 0.
+    pub fn new(width: u32, height: u32, surface_texture: SurfaceTexture) -> PixelsBuilder<'req> {
+        PixelsBuilder {
+            request_adapter_options: None,
+            device_descriptor: wgpu::DeviceDescriptor::default(),
+            backend: wgpu::BackendBit::PRIMARY,
+            shared_device: None,
+            width,
+            height,
+            pixel_aspect_ratio: 1.0,
+            present_mode: wgpu::PresentMode::Fifo,
+            defer_swap_chain: false,
+            priority: Priority::Normal,
+            background_throttle: BackgroundThrottle::default(),
+            surface_texture,
+            surface_format: default_surface_format(true),
+            texture_format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            dimension: BufferDimension::D2,
+            scaling_mode: ScalingMode::IntegerPerfect,
+            filter_mode: wgpu::FilterMode::Nearest,
+            side_by_side: None,
+            overscan: Overscan::NONE,
+            clear_color: wgpu::Color::BLACK,
+            frame_format: FrameFormat::Texture,
+            bottom_up_rows: false,
+            swizzle: Swizzle::IDENTITY,
+            scaling_shaders: ScalingShaders::default(),
+            alpha_blending: false,
+            premultiply_alpha: false,
+            pass_through: true,
+            renderer_factories: Vec::new(),
+            background_factories: Vec::new(),
+            compute_factories: Vec::new(),
+            frame_history: 0,
+            present_margin: None,
+            frame_insertion: FrameInsertion::Disabled,
+            interpolation: false,
+            previous_frame: false,
+            user_data: false,
+            user_data_size: 256,
+            deterministic: false,
+            env_power_preference: true,
+        }
+    }
+
+    /// Configure the builder with a curated bundle of options.
+    ///
+    /// See [`Preset`] for the options that each preset sets. Presets overwrite options which were
+    /// set earlier, so apply the preset first and then tweak the options you want to change.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use pixels::{PixelsBuilder, Preset};
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let mut pixels = PixelsBuilder::new(256, 240, surface_texture)
+    ///     .preset(Preset::Emulator)
+    ///     .pixel_aspect_ratio(8.0 / 7.0)
+    ///     .build()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn preset(self, preset: Preset) -> PixelsBuilder<'req> {
+        match preset {
+            Preset::Emulator => self
+                .enable_vsync(true)
+                .just_in_time_presentation(Duration::from_millis(4))
+                .texture_format(wgpu::TextureFormat::Rgba8UnormSrgb)
+                .scaling_mode(ScalingMode::FitAspect),
+            Preset::PixelArtGame => self
+                .enable_vsync(true)
+                .disable_just_in_time_presentation()
+                .texture_format(wgpu::TextureFormat::Rgba8UnormSrgb)
+                .scaling_mode(ScalingMode::IntegerPerfect),
+            Preset::Visualization => self
+                .enable_vsync(false)
+                .disable_just_in_time_presentation()
+                .texture_format(wgpu::TextureFormat::Rgba8Unorm)
+                .surface_texture_format(default_surface_format(false))
+                .scaling_mode(ScalingMode::FitAspect),
+        }
+    }
+
+    /// Add options for requesting a [`wgpu::Adapter`].
+    pub const fn request_adapter_options(
+        mut self,
+        request_adapter_options: wgpu::RequestAdapterOptions<'req>,
+    ) -> PixelsBuilder {
+        self.request_adapter_options = Some(request_adapter_options);
+        self
+    }
+
+    /// Add options for requesting a [`wgpu::Device`].
+    pub const fn device_descriptor(
+        mut self,
+        device_descriptor: wgpu::DeviceDescriptor,
+    ) -> PixelsBuilder<'req> {
+        self.device_descriptor = device_descriptor;
+        self
+    }
+
+    /// Change the size of the pixel buffer, e.g. to retry a failed build with a smaller buffer.
+    /// See [`PixelsBuilder::try_build`].
+    pub const fn buffer_size(mut self, width: u32, height: u32) -> PixelsBuilder<'req> {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Set which backends wgpu will attempt to use.
+    ///
+    /// The default value of this is [`wgpu::BackendBit::PRIMARY`], which enables
+    /// the well supported backends for wgpu.
+    pub const fn wgpu_backend(mut self, backend: wgpu::BackendBit) -> PixelsBuilder<'req> {
+        self.backend = backend;
+        self
+    }
+
+    /// Build the pixel buffer with an existing device and queue instead of requesting new ones.
+    ///
+    /// This lets several pixel buffers, e.g. one for each window of an emulator and its
+    /// debugger, share one GPU context and its resources. `adapter` is the adapter that the
+    /// device was requested from, and must be compatible with the surface. The options for
+    /// requesting an adapter and a device, like [`PixelsBuilder::request_adapter_options`],
+    /// [`PixelsBuilder::device_descriptor`], [`PixelsBuilder::wgpu_backend`], and the power
+    /// preference of the [`PixelsBuilder::priority`], are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pixels::PixelsBuilder;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let debugger_surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// let adapter = pollster::block_on(wgpu::Adapter::request(
+    ///     &wgpu::RequestAdapterOptions {
+    ///         power_preference: wgpu::PowerPreference::Default,
+    ///         compatible_surface: Some(&surface),
+    ///     },
+    ///     wgpu::BackendBit::PRIMARY,
+    /// ))
+    /// .expect("No adapter");
+    /// let (device, queue) =
+    ///     pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default()));
+    /// let device = Arc::new(device);
+    /// let queue = Arc::new(Mutex::new(queue));
+    ///
+    /// let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let pixels = PixelsBuilder::new(320, 240, surface_texture)
+    ///     .shared_device(&adapter, device.clone(), queue.clone())
+    ///     .build()?;
+    ///
+    /// let surface_texture = pixels::SurfaceTexture::new(512, 512, debugger_surface);
+    /// let tile_viewer = PixelsBuilder::new(128, 128, surface_texture)
+    ///     .shared_device(&adapter, device, queue)
+    ///     .build()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn shared_device(
+        mut self,
+        adapter: &wgpu::Adapter,
+        device: Device,
+        queue: Queue,
+    ) -> PixelsBuilder<'req> {
+        self.shared_device = Some((adapter.get_info(), device, queue));
+        self
+    }
+
+    /// Set the pixel aspect ratio to simulate non-square pixels.
+    ///
+    /// The scaling renderer stretches the pixel buffer horizontally by the given factor. The
+    /// vertical scale still follows the [`ScalingMode`], so with
+    /// [`ScalingMode::IntegerPerfect`] rows are scaled by an integer and columns by that integer
+    /// times the aspect ratio. [`Pixels::window_pos_to_pixel`] accounts for the non-square
+    /// pixels.
+    ///
+    /// E.g. set this to `8.0 / 7.0` for an 8:7 pixel aspect ratio. The default value is `1.0`.
+    ///
+    /// # Panics
+    ///
+    /// The aspect ratio must be > 0.
+    pub fn pixel_aspect_ratio(mut self, pixel_aspect_ratio: f64) -> PixelsBuilder<'req> {
+        assert!(pixel_aspect_ratio > 0.0);
+
+        self.pixel_aspect_ratio = pixel_aspect_ratio;
+        self
+    }
+
+    /// Hide the edges of the pixel buffer, like the overscan area that a CRT TV never showed.
+    ///
+    /// The rest of the pixel buffer is scaled to the surface as if it was the entire buffer. See
+    /// [`Overscan`]. The default value is [`Overscan::NONE`].
+    pub const fn overscan(mut self, overscan: Overscan) -> PixelsBuilder<'req> {
+        self.overscan = overscan;
+        self
+    }
+
+    /// Set the pixel aspect ratio and overscan crop of a common emulated system.
+    ///
+    /// See [`AspectPreset`] for the options that each preset sets.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pixels::{AspectPreset, PixelsBuilder, ScalingMode};
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// // Shows the 256x224 middle of the frame with 8:7 pixels
+    /// let pixels = PixelsBuilder::new(256, 240, surface_texture)
+    ///     .aspect_preset(AspectPreset::Nes)
+    ///     .scaling_mode(ScalingMode::FitAspect)
+    ///     .build()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn aspect_preset(self, preset: AspectPreset) -> PixelsBuilder<'req> {
+        let overscan = preset.overscan();
+        let visible_size = (
+            self.width
+                .saturating_sub(overscan.left.saturating_add(overscan.right))
+                .max(1),
+            self.height
+                .saturating_sub(overscan.top.saturating_add(overscan.bottom))
+                .max(1),
+        );
+
+        self.overscan(overscan)
+            .pixel_aspect_ratio(preset.pixel_aspect_ratio(visible_size))
+    }
+
+    /// Enable or disable Vsync.
+    ///
+    /// Vsync is enabled by default.
+    ///
+    /// The `wgpu` present mode will be set to `Fifo` when Vsync is enabled, or `Immediate` when
+    /// Vsync is disabled. To set the present mode to `Mailbox` or another value, use the
+    /// [`present_mode`] method.
+    pub fn enable_vsync(mut self, enable_vsync: bool) -> PixelsBuilder<'req> {
+        self.present_mode = if enable_vsync {
+            wgpu::PresentMode::Fifo
+        } else {
+            wgpu::PresentMode::Immediate
+        };
+        self
+    }
+
+    /// Set the `wgpu` present mode.
+    ///
+    /// This differs from [`enable_vsync`] by allowing the present mode to be set to any value.
+    pub fn present_mode(mut self, present_mode: wgpu::PresentMode) -> PixelsBuilder<'req> {
+        self.present_mode = present_mode;
+        self
+    }
+
+    /// Defer creating the swap chain until the first call to [`Pixels::resize`] or
+    /// [`Pixels::render`].
+    ///
+    /// Some platforms report a bogus window size before the window is mapped, like winit on
+    /// Wayland. With this option, the size of the [`SurfaceTexture`] is only used for the initial
+    /// scaling, and no swap chain is created with a possibly wrong size. Call [`Pixels::resize`]
+    /// when the real size is known; otherwise the first frame creates the swap chain with the size
+    /// of the `SurfaceTexture`.
+    ///
+    /// This has no effect on offscreen rendering. Disabled by default.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pixels::PixelsBuilder;
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let mut pixels = PixelsBuilder::new(320, 240, surface_texture)
+    ///     .defer_swap_chain(true)
+    ///     .build()?;
+    ///
+    /// // ... The first resize event arrives with the real window size
+    /// pixels.resize(1280, 960);
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    ///
+    /// [`Pixels::resize`]: ./struct.Pixels.html#method.resize
+    /// [`Pixels::render`]: ./struct.Pixels.html#method.render
+    pub const fn defer_swap_chain(mut self, enable: bool) -> PixelsBuilder<'req> {
+        self.defer_swap_chain = enable;
+        self
+    }
+
+    /// Enable just-in-time presentation.
+    ///
+    /// [`Pixels::time_until_present_deadline`] reports the time until `margin` before the
+    /// estimated next vblank. Applications wait for it before polling input and rendering, so the
+    /// frame is presented as late as possible. This minimizes the time between the input being
+    /// read and the frame being displayed. [`Pixels::render`] itself never waits, so the
+    /// application stays in control of its event loop. The estimate is derived from the timing of
+    /// previous presents, so this only has an effect with Vsync enabled.
+    ///
+    /// The margin must cover the time it takes to handle input, upload, and draw the frame; a
+    /// margin that is too small causes missed vblanks. A few milliseconds is usually enough.
+    ///
+    /// Just-in-time presentation is disabled by default.
+    ///
+    /// [`Pixels::time_until_present_deadline`]: ./struct.Pixels.html#method.time_until_present_deadline
+    /// [`Pixels::render`]: ./struct.Pixels.html#method.render
+    pub fn just_in_time_presentation(mut self, margin: Duration) -> PixelsBuilder<'req> {
+        self.present_margin = Some(margin);
+        self
+    }
+
+    /// Disable just-in-time presentation.
+    ///
+    /// See [`PixelsBuilder::just_in_time_presentation`].
+    pub fn disable_just_in_time_presentation(mut self) -> PixelsBuilder<'req> {
+        self.present_margin = None;
+        self
+    }
+
+    /// Hint how the pixel buffer should share the GPU with other work.
+    ///
+    /// The default value is [`Priority::Normal`]. Use [`Priority::Background`] for secondary
+    /// views, like preview windows, so they don't compete with the main view. See [`Priority`] for
+    /// what each hint changes.
+    ///
+    /// An adapter requested with [`PixelsBuilder::request_adapter_options`] takes precedence over
+    /// the hint's power preference.
+    pub const fn priority(mut self, priority: Priority) -> PixelsBuilder<'req> {
+        self.priority = priority;
+        self
+    }
+
+    /// Set how often the pixel buffer is rendered while it is backgrounded with
+    /// [`Pixels::set_background`].
+    ///
+    /// The default value is [`BackgroundThrottle::Interval`] with 100 milliseconds.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pixels::{BackgroundThrottle, PixelsBuilder};
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(320, 240, surface);
+    /// let pixels = PixelsBuilder::new(320, 240, surface_texture)
+    ///     .background_throttle(BackgroundThrottle::Paused)
+    ///     .build()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub const fn background_throttle(
+        mut self,
+        background_throttle: BackgroundThrottle,
+    ) -> PixelsBuilder<'req> {
+        self.background_throttle = background_throttle;
+        self
+    }
+
+    /// Ignore the `PIXELS_HIGH_PERF` and `PIXELS_LOW_POWER` environment variables.
+    ///
+    /// Libraries which embed a pixel buffer should not be affected by variables meant for the end
+    /// user's application. Without a priority hint or adapter request, the `wgpu` default power
+    /// preference is used. Applications can still honor the variables deliberately with
+    /// [`power_preference_from_env`].
+    ///
+    /// This has no effect without the `env` feature, which never reads the variables.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pixels::PixelsBuilder;
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let pixels = PixelsBuilder::new(320, 240, surface_texture)
+    ///     .ignore_env_power_preference()
+    ///     .build()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    ///
+    /// [`power_preference_from_env`]: ./fn.power_preference_from_env.html
+    pub const fn ignore_env_power_preference(mut self) -> PixelsBuilder<'req> {
+        self.env_power_preference = false;
+        self
+    }
+
+    /// Set the texture format of the surface.
+    ///
+    /// `wgpu` cannot report which formats a surface supports, so the default value is the format
+    /// which swap chains support on the target platform: [`wgpu::TextureFormat::Rgba8UnormSrgb`]
+    /// on Android, where many drivers lack `BGRA` swap chains, and
+    /// [`wgpu::TextureFormat::Bgra8UnormSrgb`] everywhere else. Use this when the platform requires
+    /// another format. Only 8-bit `RGBA` and `BGRA` formats are accepted. The pixel buffer texture
+    /// format is independent of the surface format; see [`PixelsBuilder::texture_format`].
+    ///
+    /// Render passes draw into the surface, so their pipelines must target this format. Render
+    /// pass factories receive it in [`PassInputs::surface_format`], and the built-in render passes
+    /// in the [`debug`] and `effects` modules use it.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pixels::PixelsBuilder;
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let pixels = PixelsBuilder::new(320, 240, surface_texture)
+    ///     .surface_texture_format(wgpu::TextureFormat::Rgba8UnormSrgb)
+    ///     .build()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    ///
+    /// [`debug`]: ./debug/index.html
+    pub const fn surface_texture_format(
+        mut self,
+        surface_format: wgpu::TextureFormat,
+    ) -> PixelsBuilder<'req> {
+        self.surface_format = surface_format;
+        self
+    }
+
+    /// Set the texture format.
+    ///
+    /// The default value is [`wgpu::TextureFormat::Rgba8UnormSrgb`], which is 4 unsigned bytes in
+    /// `RGBA` order using the SRGB color space. This is typically what you want when you are
+    /// working with color values from popular image editing tools or web apps.
+    pub const fn texture_format(
+        mut self,
+        texture_format: wgpu::TextureFormat,
+    ) -> PixelsBuilder<'req> {
+        self.texture_format = texture_format;
+        self
+    }
+
+    /// Set the shape of the pixel buffer.
+    ///
+    /// The default value is [`BufferDimension::D2`]. Use [`BufferDimension::D1`] with a height of
+    /// 1 for strip buffers, like audio visualizers, which should fill the whole surface instead of
+    /// being scaled by an integer factor.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pixels::{BufferDimension, PixelsBuilder};
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// // One column per frequency band
+    /// let pixels = PixelsBuilder::new(64, 1, surface_texture)
+    ///     .buffer_dimension(BufferDimension::D1)
+    ///     .build()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub const fn buffer_dimension(mut self, dimension: BufferDimension) -> PixelsBuilder<'req> {
+        self.dimension = dimension;
+        self
+    }
+
+    /// Set how the pixel buffer is scaled to fit the surface.
+    ///
+    /// The default value is [`ScalingMode::IntegerPerfect`], which leaves black borders around
+    /// the buffer when the surface size is not a multiple of the buffer size. Use
+    /// [`ScalingMode::FitAspect`] to fill as much of the surface as possible without distorting
+    /// the buffer, [`ScalingMode::SharpBilinear`] to do the same while keeping pixels crisp and
+    /// evenly sized, or [`ScalingMode::Stretch`] to fill all of it. Use
+    /// [`ScalingMode::IntegerPerAxis`] for buffers with non-square pixels.
+    ///
+    /// The scaling mode is ignored by [`BufferDimension::D1`] buffers, which are always
+    /// stretched. It can be changed after the pixel buffer is built with
+    /// [`Pixels::set_scaling_mode`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pixels::{PixelsBuilder, ScalingMode};
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1000, 700, surface);
+    /// // Scaled by 2.1875 to 700x525, instead of 2 to 640x480
+    /// let pixels = PixelsBuilder::new(320, 240, surface_texture)
+    ///     .scaling_mode(ScalingMode::FitAspect)
+    ///     .build()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub const fn scaling_mode(mut self, scaling_mode: ScalingMode) -> PixelsBuilder<'req> {
+        self.scaling_mode = scaling_mode;
+        self
+    }
+
+    /// Set the filter used by the scaling pass to sample the pixel buffer.
+    ///
+    /// The default value is [`wgpu::FilterMode::Nearest`], which keeps pixel edges crisp.
+    /// [`wgpu::FilterMode::Linear`] interpolates between pixels for a smoother image, which suits
+    /// small buffers scaled by a non-integer factor, like with [`ScalingMode::FitAspect`]. It can
+    /// be changed after the pixel buffer is built with [`Pixels::set_filter_mode`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pixels::{PixelsBuilder, ScalingMode};
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1000, 700, surface);
+    /// let pixels = PixelsBuilder::new(320, 240, surface_texture)
+    ///     .scaling_mode(ScalingMode::FitAspect)
+    ///     .filter_mode(wgpu::FilterMode::Linear)
+    ///     .build()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub const fn filter_mode(mut self, filter_mode: wgpu::FilterMode) -> PixelsBuilder<'req> {
+        self.filter_mode = filter_mode;
+        self
+    }
+
+    /// Set the color of the borders around the scaled pixel buffer.
+    ///
+    /// The surface is cleared to this color before the first render pass, so it shows wherever
+    /// the pixel buffer does not cover the surface, like the letterbox borders of
+    /// [`ScalingMode::IntegerPerfect`]. The default value is [`wgpu::Color::BLACK`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pixels::PixelsBuilder;
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let pixels = PixelsBuilder::new(320, 240, surface_texture)
+    ///     .clear_color(wgpu::Color {
+    ///         r: 0.1,
+    ///         g: 0.05,
+    ///         b: 0.2,
+    ///         a: 1.0,
+    ///     })
+    ///     .build()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub const fn clear_color(mut self, clear_color: wgpu::Color) -> PixelsBuilder<'req> {
+        self.clear_color = clear_color;
+        self
+    }
+
+    /// Blend the pixel buffer over the surface with its alpha channel.
+    ///
+    /// By default the scaling pass replaces everything under the pixel buffer, and the alpha
+    /// channel is ignored. When enabled, translucent pixels show the clear color, or whatever the
+    /// background passes drew, behind them. Blending happens in linear color space. See
+    /// [`PixelsBuilder::clear_color`] and [`RenderPassOptions::background`].
+    ///
+    /// The default value is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pixels::PixelsBuilder;
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let pixels = PixelsBuilder::new(320, 240, surface_texture)
+    ///     .clear_color(wgpu::Color::WHITE)
+    ///     .alpha_blending(true)
+    ///     .build()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub const fn alpha_blending(mut self, alpha_blending: bool) -> PixelsBuilder<'req> {
+        self.alpha_blending = alpha_blending;
+        self
+    }
+
+    /// Premultiply the colors of the pixel buffer by its alpha channel during the upload.
+    ///
+    /// Frames are authored with straight alpha, but filtering and blending straight alpha on the
+    /// GPU darkens the edges of translucent shapes. With this option the texture holds
+    /// premultiplied colors, and [`PixelsBuilder::alpha_blending`] blends them accordingly. Render
+    /// passes which sample the texture receive premultiplied colors, too. Colors of `sRGB` texture
+    /// formats are multiplied in linear space. For the indexed frame formats, the palette is
+    /// premultiplied instead. See [`color::premultiply_alpha`].
+    ///
+    /// The premultiplication happens on the CPU, as part of copying rows into the upload buffer.
+    ///
+    /// The default value is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pixels::PixelsBuilder;
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let pixels = PixelsBuilder::new(320, 240, surface_texture)
+    ///     .alpha_blending(true)
+    ///     .premultiply_alpha(true)
+    ///     .filter_mode(wgpu::FilterMode::Linear)
+    ///     .build()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    ///
+    /// [`color::premultiply_alpha`]: ./color/fn.premultiply_alpha.html
+    pub const fn premultiply_alpha(mut self, premultiply_alpha: bool) -> PixelsBuilder<'req> {
+        self.premultiply_alpha = premultiply_alpha;
+        self
+    }
+
+    /// Copy the pixel buffer to the surface instead of drawing it, when that gives the same image.
+    ///
+    /// Applications which already render at the surface resolution do not need the scaling
+    /// renderer, so the fullscreen draw is replaced with a texture copy when:
+    ///
+    /// - The pixel buffer has the same size and format as the surface, and is shown at its
+    ///   original size, without overscan or side-by-side rendering.
+    /// - The scaling renderer uses the default shaders, without a swizzle or alpha blending.
+    /// - No other render pass is enabled, and there are no visible layers, gizmos, overlays, or
+    ///   interpolation.
+    ///
+    /// Swap chain textures can only be drawn into, so pass-through only applies to offscreen
+    /// surfaces created with [`SurfaceTexture::offscreen`]. The conditions are checked for every
+    /// frame, so enabling a render pass switches back to drawing immediately.
+    ///
+    /// The default value is `true`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pixels::{PixelsBuilder, SurfaceTexture};
+    ///
+    /// let surface_texture = SurfaceTexture::offscreen(640, 480);
+    /// let pixels = PixelsBuilder::new(640, 480, surface_texture)
+    ///     .surface_texture_format(wgpu::TextureFormat::Rgba8UnormSrgb)
+    ///     .pass_through(false)
+    ///     .build()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    ///
+    /// [`SurfaceTexture::offscreen`]: ./struct.SurfaceTexture.html#method.offscreen
+    pub const fn pass_through(mut self, enable: bool) -> PixelsBuilder<'req> {
+        self.pass_through = enable;
+        self
+    }
+
+    /// Render the pixel buffer twice, side by side, for simple stereoscopic or dual-view setups.
+    ///
+    /// Each eye gets half of the surface, and is scaled into it with the configured
+    /// [`ScalingMode`]. See [`SideBySide`] for details.
+    pub const fn side_by_side(mut self, side_by_side: SideBySide) -> PixelsBuilder<'req> {
+        self.side_by_side = Some(side_by_side);
+        self
+    }
+
+    /// Set the layout of the pixel buffer.
+    ///
+    /// The default value is [`FrameFormat::Texture`], which stores pixels in the texture format.
+    /// Any other format is expanded into the texture by a compute shader on the GPU, so
+    /// applications producing indexed, `RGB565`, or planar frames never convert pixels on the CPU.
+    /// The texture format must be a 4-byte `RGBA` format, like the default
+    /// [`wgpu::TextureFormat::Rgba8UnormSrgb`].
+    ///
+    /// See [`Pixels::set_palette`] for configuring the colors of indexed frames.
+    pub const fn frame_format(mut self, frame_format: FrameFormat) -> PixelsBuilder<'req> {
+        self.frame_format = frame_format;
+        self
+    }
+
+    /// Store the rows of the pixel buffer from the bottom of the image to the top.
+    ///
+    /// Many frame producers, like BMP decoders, some emulator cores, and OpenGL readbacks, supply
+    /// bottom-up rows. The rows are flipped as part of the upload instead of on the CPU by the
+    /// application. Row indices, like the `start_row` of [`Pixels::push_scanlines`], refer to the
+    /// pixel buffer as it is stored, while positions on the surface, like the ones returned by
+    /// [`Pixels::window_pos_to_pixel`], refer to the displayed image, with row 0 at the top.
+    ///
+    /// The default value is `false`. It can be changed after the pixel buffer is built with
+    /// [`Pixels::set_bottom_up_rows`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pixels::PixelsBuilder;
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let mut pixels = PixelsBuilder::new(320, 240, surface_texture)
+    ///     .bottom_up_rows(true)
+    ///     .build()?;
+    ///
+    /// // The first row of the frame is shown at the bottom of the surface
+    /// pixels.get_frame()[..320 * 4].copy_from_slice(&[0xff; 320 * 4]);
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub const fn bottom_up_rows(mut self, bottom_up_rows: bool) -> PixelsBuilder<'req> {
+        self.bottom_up_rows = bottom_up_rows;
+        self
+    }
+
+    /// Set the component swizzle for drawing the texture.
+    ///
+    /// The default value is [`Swizzle::IDENTITY`]. Use [`Swizzle::BGRA`] or [`Swizzle::BGRX`] to
+    /// display frames from sources like Windows GDI captures, which store pixels in `BGRA` order,
+    /// without swapping channels on the CPU.
+    ///
+    /// The swizzle only applies to the built-in scaling pass. Additional render passes still
+    /// receive the unmodified texture.
+    pub const fn swizzle(mut self, swizzle: Swizzle) -> PixelsBuilder<'req> {
+        self.swizzle = swizzle;
+        self
+    }
+
+    /// Replace the shaders of the built-in scaling pass.
+    ///
+    /// See [`ScalingShaders`] for the interface that custom shaders must implement. The built-in
+    /// shaders are used by default.
+    pub fn scaling_shaders(mut self, shaders: ScalingShaders) -> PixelsBuilder<'req> {
+        self.scaling_shaders = shaders;
+        self
+    }
+
+    /// Replace the fragment shader of the built-in scaling pass with a SPIR-V binary.
+    ///
+    /// This is the simplest way to add an effect like scanlines or color grading: the built-in
+    /// pipeline, bind groups, and vertex shader are kept, and only the final blit is replaced.
+    /// See [`ScalingShaders`] for the bindings available to the shader. The binary is usually
+    /// embedded with `include_bytes!`. WGSL is not supported by this version of `wgpu`; compile
+    /// shaders to SPIR-V with e.g. `glslangValidator`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pixels::PixelsBuilder;
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// # let scanlines: &[u8] = &[];
+    /// // let scanlines = include_bytes!("scanlines.spv");
+    /// let pixels = PixelsBuilder::new(320, 240, surface_texture)
+    ///     .fragment_shader(scanlines)
+    ///     .build()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics when `spirv` is not a valid SPIR-V binary.
+    pub fn fragment_shader(mut self, spirv: &[u8]) -> PixelsBuilder<'req> {
+        let spirv = wgpu::read_spirv(io::Cursor::new(spirv)).expect("Invalid SPIR-V shader");
+
+        self.scaling_shaders.fragment = Some(spirv);
+        self
+    }
+
+    /// Insert black or dimmed frames after each rendered frame.
+    ///
+    /// This reduces the motion blur of smoothly scrolling pixel art on high refresh rate
+    /// displays, at the cost of brightness. See [`FrameInsertion`] for the available modes.
+    ///
+    /// Inserted frames are only presented when Vsync is enabled. The default value is
+    /// [`FrameInsertion::Disabled`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pixels::{FrameInsertion, PixelsBuilder};
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// // A 60 Hz game on a 120 Hz display
+    /// let pixels = PixelsBuilder::new(320, 240, surface_texture)
+    ///     .frame_insertion(FrameInsertion::Dim {
+    ///         frames: 1,
+    ///         brightness: 0.25,
+    ///     })
+    ///     .build()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub const fn frame_insertion(mut self, frame_insertion: FrameInsertion) -> PixelsBuilder<'req> {
+        self.frame_insertion = frame_insertion;
+        self
+    }
+
+    /// Blend between the two most recent source frames, weighted by time.
+    ///
+    /// When the pixel buffer is updated less often than the display refreshes, like a 30 Hz
+    /// simulation on a 144 Hz display, each render shows a mix of the previous and the current
+    /// frame which moves from the former to the latter over one source frame interval. This
+    /// smooths scrolling, at the cost of one source frame of latency. Render the pixel buffer
+    /// whenever [`Pixels::needs_redraw`] returns `true`, which includes the entire blend.
+    ///
+    /// Frames more than 100 ms apart are not blended, so a static image is updated immediately.
+    /// Interpolation is disabled by default.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pixels::PixelsBuilder;
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let pixels = PixelsBuilder::new(320, 240, surface_texture)
+    ///     .interpolate_frames(true)
+    ///     .build()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub const fn interpolate_frames(mut self, interpolate: bool) -> PixelsBuilder<'req> {
+        self.interpolation = interpolate;
+        self
+    }
+
+    /// Remove all sources of nondeterminism from rendering, for golden-image tests.
+    ///
+    /// In deterministic mode, the same sequence of pixel buffers produces byte-identical output on
+    /// the same hardware, regardless of timing:
+    ///
+    /// - Time stands still unless it is moved forward with [`Pixels::advance_time`]. Animations
+    ///   started with [`Pixels::set_animation`] and [`Pixels::time`] follow this clock.
+    /// - Temporal effects are disabled: [`PixelsBuilder::interpolate_frames`] and
+    ///   [`PixelsBuilder::frame_insertion`] are ignored.
+    /// - [`Pixels::seed`] returns the same value on every run, for render passes which generate
+    ///   noise.
+    ///
+    /// Deterministic mode is disabled by default.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pixels::{PixelsBuilder, SurfaceTexture};
+    ///
+    /// let mut pixels = PixelsBuilder::new(320, 240, SurfaceTexture::offscreen(640, 480))
+    ///     .deterministic(true)
+    ///     .build()?;
+    ///
+    /// // ... Draw the frame under test
+    /// pixels.render()?;
+    /// let rendered = pixels.read_offscreen_frame()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub const fn deterministic(mut self, deterministic: bool) -> PixelsBuilder<'req> {
+        self.deterministic = deterministic;
+        self
+    }
+
+    /// Retain copies of the last `frames` pixel buffers passed to [`Pixels::render`].
+    ///
+    /// The history can be written to disk with [`Pixels::dump_history`], which is invaluable for
+    /// tracking down intermittent visual glitches. Each retained frame costs one copy of the pixel
+    /// buffer in memory and one `memcpy` per render. No frames are retained when compute passes
+    /// fill the texture, see [`PixelsBuilder::add_compute_pass`].
+    ///
+    /// The frame history is disabled (set to 0) by default.
+    pub const fn keep_frame_history(mut self, frames: usize) -> PixelsBuilder<'req> {
+        self.frame_history = frames;
+        self
+    }
+
+    /// Add a render pass with the default [`RenderPassOptions`].
+    ///
+    /// Render passes are executed in the order they are added. See
+    /// [`PixelsBuilder::add_render_pass_with`] for details on the factory arguments.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pixels::{BoxedRenderPass, Device, PassInputs, PixelsBuilder, Queue, RenderPass};
+    ///
+    /// struct MyRenderPass {
+    ///     device: Device,
+    ///     queue: Queue,
+    /// }
+    ///
+    /// impl MyRenderPass {
+    ///     fn factory(device: Device, queue: Queue, inputs: &PassInputs<'_>) -> BoxedRenderPass {
+    ///         // Create a bind group, pipeline, etc. and store all of the necessary state...
+    ///         Box::new(MyRenderPass { device, queue })
+    ///     }
+    /// }
+    ///
+    /// impl RenderPass for MyRenderPass {
+    ///     // ...
+    /// # fn update_bindings(&mut self, _: &wgpu::TextureView, _: &wgpu::Extent3d) {}
+    /// # fn render(&self, _: &mut wgpu::CommandEncoder, _: &wgpu::TextureView) {}
+    /// }
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let builder = PixelsBuilder::new(320, 240, surface_texture)
+    ///     .add_render_pass(MyRenderPass::factory)
+    ///     .build()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn add_render_pass(
+        self,
+        factory: impl Fn(Device, Queue, &PassInputs<'_>) -> BoxedRenderPass + Send + Sync + 'static,
     ) -> PixelsBuilder<'req> {
-        self.device_descriptor = device_descriptor;
-        self
+        self.add_render_pass_with(RenderPassOptions::default(), factory)
     }
 
-    /// Set which backends wgpu will attempt to use.
+    /// Add a render pass with a debug label, a custom input sampler, a load op, access to the
+    /// previous frame or the per-frame user data, or to draw underneath the pixel buffer.
     ///
-    /// The default value of this is [`wgpu::BackendBit::PRIMARY`], which enables
-    /// the well supported backends for wgpu.
-    pub const fn wgpu_backend(mut self, backend: wgpu::BackendBit) -> PixelsBuilder<'req> {
-        self.backend = backend;
-        self
-    }
-
-    /// Set the pixel aspect ratio to simulate non-square pixels.
+    /// See [`RenderPassOptions`] for all options, which can be combined freely.
     ///
-    /// This setting enables a render pass that horizontally scales the pixel buffer by the given
-    /// factor.
+    /// # Factory Arguments
     ///
-    /// E.g. set this to `8.0 / 7.0` for an 8:7 pixel aspect ratio.
+    /// * `device` - A reference-counted [`wgpu::Device`] which allows you to create GPU resources.
+    /// * `queue` - A reference-counted [`wgpu::Queue`] which can execute command buffers.
+    /// * `inputs` - The [`PassInputs`]: the input texture and its size, the sampler
+    ///   configuration, and the previous frame and user data when they were requested.
     ///
-    /// # Panics
+    /// # Examples
     ///
-    /// The aspect ratio must be > 0.
-    pub fn pixel_aspect_ratio(mut self, pixel_aspect_ratio: f64) -> PixelsBuilder<'req> {
-        assert!(pixel_aspect_ratio > 0.0);
-
-        self.pixel_aspect_ratio = pixel_aspect_ratio;
-        self
-    }
-
-    /// Enable or disable Vsync.
+    /// ```no_run
+    /// use pixels::wgpu::{AddressMode, FilterMode, Sampler};
+    /// use pixels::{
+    ///     BoxedRenderPass, Device, InputSampler, PassInputs, PixelsBuilder, Queue, RenderPass,
+    ///     RenderPassOptions, UserDataBuffer,
+    /// };
     ///
-    /// Vsync is enabled by default.
+    /// struct Starfield {
+    ///     sampler: Sampler,
+    ///     user_data: UserDataBuffer,
+    /// }
     ///
-    /// The `wgpu` present mode will be set to `Fifo` when Vsync is enabled, or `Immediate` when
-    /// Vsync is disabled. To set the present mode to `Mailbox` or another value, use the
-    /// [`present_mode`] method.
-    pub fn enable_vsync(mut self, enable_vsync: bool) -> PixelsBuilder<'req> {
-        self.present_mode = if enable_vsync {
-            wgpu::PresentMode::Fifo
+    /// impl Starfield {
+    ///     fn factory(device: Device, queue: Queue, inputs: &PassInputs<'_>) -> BoxedRenderPass {
+    ///         // Bind the texture with the repeating sampler, and the scroll position next to it...
+    ///         Box::new(Starfield {
+    ///             sampler: inputs.sampler.create(&device),
+    ///             // The buffer is always passed when it was requested in the options
+    ///             user_data: inputs.user_data.unwrap().clone(),
+    ///         })
+    ///     }
+    /// }
+    ///
+    /// impl RenderPass for Starfield {
+    ///     // ...
+    /// # fn update_bindings(&mut self, _: &wgpu::TextureView, _: &wgpu::Extent3d) {}
+    /// # fn render(&self, _: &mut wgpu::CommandEncoder, _: &wgpu::TextureView) {}
+    /// }
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let options = RenderPassOptions {
+    ///     label: Some("starfield".to_string()),
+    ///     sampler: InputSampler::new(AddressMode::Repeat, FilterMode::Nearest),
+    ///     user_data: true,
+    ///     background: true,
+    ///     ..RenderPassOptions::default()
+    /// };
+    /// let mut pixels = PixelsBuilder::new(320, 240, surface_texture)
+    ///     .user_data_size(16)
+    ///     .add_render_pass_with(options, Starfield::factory)
+    ///     .build()?;
+    ///
+    /// // The scroll position of the starfield, in pixels
+    /// let data: Vec<u8> = [0.0f32, 48.0, 0.0, 0.0]
+    ///     .iter()
+    ///     .flat_map(|value| value.to_ne_bytes().to_vec())
+    ///     .collect();
+    /// pixels.render_with_data(&data)?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn add_render_pass_with(
+        mut self,
+        options: RenderPassOptions,
+        factory: impl Fn(Device, Queue, &PassInputs<'_>) -> BoxedRenderPass + Send + Sync + 'static,
+    ) -> PixelsBuilder<'req> {
+        self.previous_frame |= options.previous_frame;
+        self.user_data |= options.user_data;
+
+        let (factories, kind) = if options.background {
+            (&mut self.background_factories, "background")
         } else {
-            wgpu::PresentMode::Immediate
+            (&mut self.renderer_factories, "render")
         };
+        let index = factories.len();
+        let label = options
+            .label
+            .unwrap_or_else(|| format!("pixels_{}_pass_{}", kind, index));
+        factories.push(PassFactory {
+            label,
+            load_op: options.load_op,
+            sampler: options.sampler,
+            factory: Box::new(factory),
+        });
         self
     }
 
-    /// Set the `wgpu` present mode.
+    /// Set the size of the [`UserDataBuffer`] in bytes.
     ///
-    /// This differs from [`enable_vsync`] by allowing the present mode to be set to any value.
-    pub fn present_mode(mut self, present_mode: wgpu::PresentMode) -> PixelsBuilder<'req> {
-        self.present_mode = present_mode;
-        self
-    }
-
-    /// Set the texture format.
+    /// The size must be a multiple of 16, like uniform blocks in shaders. The default is 256
+    /// bytes. See [`RenderPassOptions::user_data`].
     ///
-    /// The default value is [`wgpu::TextureFormat::Rgba8UnormSrgb`], which is 4 unsigned bytes in
-    /// `RGBA` order using the SRGB color space. This is typically what you want when you are
-    /// working with color values from popular image editing tools or web apps.
-    pub const fn texture_format(
-        mut self,
-        texture_format: wgpu::TextureFormat,
-    ) -> PixelsBuilder<'req> {
-        self.texture_format = texture_format;
+    /// # Panics
+    ///
+    /// Panics when `size` is zero or not a multiple of 16.
+    pub fn user_data_size(mut self, size: wgpu::BufferAddress) -> PixelsBuilder<'req> {
+        assert!(size > 0 && size % 16 == 0);
+        self.user_data_size = size;
         self
     }
 
-    /// Add a render pass.
+    /// Add a compute pass which fills the pixel buffer texture on the GPU.
     ///
-    /// Render passes are executed in the order they are added.
+    /// This is for procedural effects which generate every pixel in a compute shader. Compute
+    /// passes are executed once per frame, in the order they are added, before the built-in
+    /// scaling pass and any render passes. Their output replaces the pixel buffer: once a compute
+    /// pass is added, no pixel buffer is allocated on the CPU. [`Pixels::get_frame`],
+    /// [`Pixels::get_tracked_frame`], and the other methods which write to the pixel buffer panic.
+    /// The frame history and crash dumps do not keep any frames, and only the rendered frame can
+    /// be recorded.
     ///
     /// # Factory Arguments
     ///
     /// * `device` - A reference-counted [`wgpu::Device`] which allows you to create GPU resources.
     /// * `queue` - A reference-counted [`wgpu::Queue`] which can execute command buffers.
-    /// * `texture` - A [`wgpu::TextureView`] reference that is used as the texture input for the
-    ///   render pass.
-    /// * `texture_size` - A [`wgpu::Extent3d`] providing the input texture size.
+    /// * `output` - The storage buffer which is copied into the texture, see [`ComputePass`] for
+    ///   its layout.
+    /// * `texture_size` - A [`wgpu::Extent3d`] providing the texture size.
     ///
     /// # Examples
     ///
     /// ```no_run
-    /// use pixels::{BoxedRenderPass, Device, PixelsBuilder, Queue, RenderPass};
-    /// use pixels::wgpu::{Extent3d, TextureView};
+    /// use pixels::wgpu::Extent3d;
+    /// use pixels::{BoxedComputePass, ComputePass, Device, FrameContext, PixelsBuilder, Queue};
     ///
-    /// struct MyRenderPass {
-    ///     device: Device,
-    ///     queue: Queue,
+    /// struct Plasma {
+    ///     width: u32,
+    ///     height: u32,
     /// }
     ///
-    /// impl MyRenderPass {
+    /// impl Plasma {
     ///     fn factory(
     ///         device: Device,
     ///         queue: Queue,
-    ///         texture: &TextureView,
+    ///         output: &wgpu::Buffer,
     ///         texture_size: &Extent3d,
-    ///     ) -> BoxedRenderPass {
-    ///         // Create a bind group, pipeline, etc. and store all of the necessary state...
-    ///         Box::new(MyRenderPass { device, queue })
+    ///     ) -> BoxedComputePass {
+    ///         // Bind the output buffer as a storage buffer, create the pipeline, etc...
+    ///         Box::new(Plasma {
+    ///             width: texture_size.width,
+    ///             height: texture_size.height,
+    ///         })
     ///     }
     /// }
     ///
-    /// impl RenderPass for MyRenderPass {
+    /// impl ComputePass for Plasma {
+    ///     fn dispatch(&mut self, encoder: &mut wgpu::CommandEncoder, context: &FrameContext) {
+    ///         // ... Upload the time, and dispatch one invocation for every pixel
+    ///     }
+    ///
     ///     // ...
-    /// # fn update_bindings(&mut self, _: &wgpu::TextureView, _: &wgpu::Extent3d) {}
-    /// # fn render(&self, _: &mut wgpu::CommandEncoder, _: &wgpu::TextureView) {}
+    /// # fn update_bindings(&mut self, _: &wgpu::Buffer, _: &wgpu::Extent3d) {}
     /// }
     ///
     /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
     /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
-    /// let builder = PixelsBuilder::new(320, 240, surface_texture)
-    ///     .add_render_pass(MyRenderPass::factory)
+    /// let pixels = PixelsBuilder::new(320, 240, surface_texture)
+    ///     .add_compute_pass(Plasma::factory)
     ///     .build()?;
     /// # Ok::<(), pixels::Error>(())
     /// ```
-    pub fn add_render_pass(
+    pub fn add_compute_pass(
         mut self,
-        factory: impl Fn(Device, Queue, &TextureView, &Extent3d) -> BoxedRenderPass + 'static,
+        factory: impl Fn(Device, Queue, &wgpu::Buffer, &Extent3d) -> BoxedComputePass
+            + Send
+            + Sync
+            + 'static,
     ) -> PixelsBuilder<'req> {
-        self.renderer_factories.push(Box::new(factory));
+        self.compute_factories.push(Box::new(factory));
         self
     }
 
+    /// Check that the options can be used together, before any GPU resources are created.
+    fn validate(&self) -> Result<(), Error> {
+        let (width, height) = (self.width, self.height);
+        let invalid_dimensions = |reason| Error::InvalidDimensions {
+            width,
+            height,
+            reason,
+        };
+
+        if width == 0 || height == 0 {
+            return Err(invalid_dimensions(
+                "the width and height must be at least 1",
+            ));
+        }
+        if self.dimension == BufferDimension::D1 && height != 1 {
+            return Err(invalid_dimensions(
+                "one-dimensional buffers must have a height of 1",
+            ));
+        }
+        if self
+            .side_by_side
+            .map_or(false, |side_by_side| side_by_side.split_buffer)
+            && width % 2 != 0
+        {
+            return Err(invalid_dimensions("split buffers must have an even width"));
+        }
+
+        match self.texture_format {
+            wgpu::TextureFormat::Depth32Float
+            | wgpu::TextureFormat::Depth24Plus
+            | wgpu::TextureFormat::Depth24PlusStencil8 => {
+                return Err(Error::UnsupportedTextureFormat(
+                    self.texture_format,
+                    "depth formats cannot be uploaded",
+                ));
+            }
+            _ => (),
+        }
+        match self.surface_format {
+            wgpu::TextureFormat::Bgra8UnormSrgb
+            | wgpu::TextureFormat::Bgra8Unorm
+            | wgpu::TextureFormat::Rgba8UnormSrgb
+            | wgpu::TextureFormat::Rgba8Unorm => (),
+            _ => {
+                return Err(Error::UnsupportedTextureFormat(
+                    self.surface_format,
+                    "surfaces must use an 8-bit RGBA or BGRA format",
+                ));
+            }
+        }
+        if self.premultiply_alpha {
+            match self.texture_format {
+                wgpu::TextureFormat::Rgba8UnormSrgb
+                | wgpu::TextureFormat::Rgba8Unorm
+                | wgpu::TextureFormat::Bgra8UnormSrgb
+                | wgpu::TextureFormat::Bgra8Unorm => (),
+                _ => {
+                    return Err(Error::UnsupportedTextureFormat(
+                        self.texture_format,
+                        "premultiplied alpha requires an 8-bit RGBA or BGRA format",
+                    ));
+                }
+            }
+        }
+        if self.frame_format != FrameFormat::Texture
+            && get_texture_format_size(self.texture_format) != 4
+        {
+            return Err(Error::UnsupportedTextureFormat(
+                self.texture_format,
+                "converted frame formats require 4 bytes per pixel",
+            ));
+        }
+        if let FrameFormat::Planar(planes) = self.frame_format {
+            if !(1..=8).contains(&planes) {
+                return Err(Error::UnsupportedFrameFormat(self.frame_format));
+            }
+        }
+
+        // Crops which overflow are larger than any pixel buffer
+        if self.overscan.left.saturating_add(self.overscan.right) >= width
+            || self.overscan.top.saturating_add(self.overscan.bottom) >= height
+        {
+            return Err(Error::BufferTooSmall { width, height });
+        }
+
+        Ok(())
+    }
+
     /// Create a pixel buffer from the options builder.
     ///
     /// # Errors
     ///
-    /// Returns an error when a [`wgpu::Adapter`] cannot be found.
+    /// Returns an error when a [`wgpu::Adapter`] cannot be found, or when the options cannot be
+    /// used together:
+    ///
+    /// * [`Error::InvalidDimensions`] when the width or height is 0, when a
+    ///   [`BufferDimension::D1`] buffer has a height other than 1, or when a [`SideBySide`] split
+    ///   buffer has an odd width.
+    /// * [`Error::UnsupportedTextureFormat`] when the texture format is a depth format, when a
+    ///   frame format other than [`FrameFormat::Texture`] is used with a texture format that is
+    ///   not 4 bytes per pixel, or when the surface texture format is not an 8-bit `RGBA` or
+    ///   `BGRA` format.
+    /// * [`Error::UnsupportedFrameFormat`] when a planar frame format does not have 1 through 8
+    ///   planes.
+    /// * [`Error::BufferTooSmall`] when the [`Overscan`] crops the entire buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pixels::{Error, PixelsBuilder};
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// match PixelsBuilder::new(0, 240, surface_texture).build() {
+    ///     Err(Error::InvalidDimensions { reason, .. }) => eprintln!("Bad video mode: {}", reason),
+    ///     Err(err) => eprintln!("Could not create the pixel buffer: {}", err),
+    ///     Ok(pixels) => {
+    ///         // ...
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// This blocks the current thread until the device is ready. Use
+    /// [`PixelsBuilder::build_async`] where blocking is not allowed, like on `wasm32`.
     pub fn build(self) -> Result<Pixels, Error> {
-        // TODO: Use `options.pixel_aspect_ratio` to stretch the scaled texture
-        let compatible_surface = Some(&self.surface_texture.surface);
-        let adapter = pollster::block_on(wgpu::Adapter::request(
-            &self.request_adapter_options.map_or_else(
-                || wgpu::RequestAdapterOptions {
-                    compatible_surface,
-                    power_preference: get_default_power_preference(),
-                },
-                |rao| wgpu::RequestAdapterOptions {
-                    compatible_surface: rao.compatible_surface.or(compatible_surface),
-                    power_preference: rao.power_preference,
-                },
-            ),
-            self.backend,
-        ))
-        .ok_or(Error::AdapterNotFound)?;
+        pollster::block_on(self.build_async())
+    }
+
+    /// Create a pixel buffer from the options builder, or give the builder back on failure.
+    ///
+    /// [`PixelsBuilder::build`] consumes the builder along with the [`SurfaceTexture`], so a
+    /// failed build can only be retried with a new surface. This returns the builder in the
+    /// [`BuildError`] instead, which allows falling back to other options.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`PixelsBuilder::build`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pixels::PixelsBuilder;
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let builder = PixelsBuilder::new(320, 240, surface_texture)
+    ///     .wgpu_backend(wgpu::BackendBit::VULKAN);
+    ///
+    /// let pixels = match builder.try_build() {
+    ///     Ok(pixels) => pixels,
+    ///     Err(err) => {
+    ///         eprintln!("Falling back to the primary backends: {}", err);
+    ///         err.into_builder()
+    ///             .wgpu_backend(wgpu::BackendBit::PRIMARY)
+    ///             .build()?
+    ///     }
+    /// };
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn try_build(self) -> Result<Pixels, BuildError<'req>> {
+        pollster::block_on(self.try_build_async())
+    }
+
+    /// Create a pixel buffer from the options builder, without blocking.
+    ///
+    /// Requesting the adapter and device is asynchronous in `wgpu`. [`PixelsBuilder::build`]
+    /// blocks on it, which is forbidden in the browser, so WebGPU applications on `wasm32` must
+    /// await this instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`PixelsBuilder::build`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pixels::PixelsBuilder;
+    ///
+    /// # async fn run() -> Result<(), pixels::Error> {
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+    /// let pixels = PixelsBuilder::new(320, 240, surface_texture)
+    ///     .build_async()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn build_async(self) -> Result<Pixels, Error> {
+        Ok(self.try_build_async().await?)
+    }
+
+    /// Create a pixel buffer from the options builder without blocking, or give the builder back
+    /// on failure. See [`PixelsBuilder::try_build`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`PixelsBuilder::build`].
+    pub async fn try_build_async(mut self) -> Result<Pixels, BuildError<'req>> {
+        if let Err(error) = self.validate() {
+            return Err(BuildError {
+                error,
+                builder: self,
+            });
+        }
+
+        // RenderDoc must be loaded before the device is created to hook the graphics API
+        #[cfg(feature = "renderdoc")]
+        let gpu_capture = GpuCapture::new();
+
+        // Use the device shared by the application, or request a new one
+        let (adapter_info, device, queue, power_preference) = match self.shared_device.take() {
+            Some((adapter_info, device, queue)) => (adapter_info, device, queue, None),
+            None => {
+                let compatible_surface = self.surface_texture.surface.as_ref();
+                let priority = self.priority;
+                let env_power_preference = self.env_power_preference;
+                let power_preference = self.request_adapter_options.as_ref().map_or_else(
+                    || {
+                        priority
+                            .power_preference()
+                            .or_else(|| read_env_power_preference(env_power_preference))
+                            .unwrap_or(wgpu::PowerPreference::Default)
+                    },
+                    |rao| rao.power_preference,
+                );
+                let adapter = wgpu::Adapter::request(
+                    &self.request_adapter_options.as_ref().map_or_else(
+                        || wgpu::RequestAdapterOptions {
+                            compatible_surface,
+                            power_preference,
+                        },
+                        |rao| wgpu::RequestAdapterOptions {
+                            compatible_surface: rao.compatible_surface.or(compatible_surface),
+                            power_preference: rao.power_preference,
+                        },
+                    ),
+                    self.backend,
+                )
+                .await;
+                let adapter = match adapter {
+                    Some(adapter) => adapter,
+                    None => {
+                        return Err(BuildError {
+                            error: Error::AdapterNotFound,
+                            builder: self,
+                        })
+                    }
+                };
+
+                let (device, queue) = adapter.request_device(&self.device_descriptor).await;
+                (
+                    adapter.get_info(),
+                    Arc::new(device),
+                    Arc::new(Mutex::new(queue)),
+                    Some(power_preference),
+                )
+            }
+        };
 
-        let (device, queue) = pollster::block_on(adapter.request_device(&self.device_descriptor));
-        let device = Rc::new(device);
-        let queue = Rc::new(RefCell::new(queue));
+        // Report what the adapter could not provide
+        let mut capabilities = CapabilitiesReport::new(adapter_info);
+        let device_type = capabilities.adapter().device_type.clone();
+        if device_type == wgpu::DeviceType::Cpu {
+            capabilities.push(Downgrade::SoftwareAdapter);
+        }
+        if power_preference == Some(wgpu::PowerPreference::HighPerformance)
+            && device_type != wgpu::DeviceType::DiscreteGpu
+        {
+            capabilities.push(Downgrade::PowerPreference { device_type });
+        }
 
         // The rest of this is technically a fixed-function pipeline... For now!
 
@@ -616,7 +5133,7 @@ impl<'req> PixelsBuilder<'req> {
             height,
             depth: 1,
         };
-        let texture = device.create_texture(&wgpu::TextureDescriptor {
+        let texture = Arc::new(device.create_texture(&wgpu::TextureDescriptor {
             label: None,
             size: texture_extent,
             array_layer_count: 1,
@@ -624,55 +5141,244 @@ impl<'req> PixelsBuilder<'req> {
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format: self.texture_format,
-            usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
-        });
+            usage: wgpu::TextureUsage::SAMPLED
+                | wgpu::TextureUsage::COPY_DST
+                | wgpu::TextureUsage::COPY_SRC,
+        }));
         let texture_view = texture.create_default_view();
+
+        // Create a texture for the previous frame, only when a render pass needs it
+        let previous_texture = if self.previous_frame {
+            Some(create_previous_texture(
+                &device,
+                texture_extent,
+                self.texture_format,
+            ))
+        } else {
+            None
+        };
+        let previous_view = previous_texture
+            .as_ref()
+            .map(|texture| texture.create_default_view());
+
+        // Create a uniform buffer for the user data, only when a render pass needs it
+        let user_data = if self.user_data {
+            Some(create_user_data_buffer(&device, self.user_data_size))
+        } else {
+            None
+        };
         let texture_format_size = get_texture_format_size(self.texture_format);
 
-        // Create the pixel buffer
-        let capacity = (width * height * texture_format_size) as usize;
+        // Create the compute passes, which replace the upload of the pixel buffer
+        let compute = if self.compute_factories.is_empty() {
+            None
+        } else {
+            Some(ComputeStage::new(
+                &device,
+                &queue,
+                &self.compute_factories,
+                texture_extent,
+                texture_format_size,
+            ))
+        };
+
+        // Create a compute pipeline to expand frames which are not in the texture format
+        let converter = if self.frame_format == FrameFormat::Texture {
+            None
+        } else {
+            Some(FrameConverter::new(
+                &device,
+                self.frame_format,
+                width,
+                height,
+            ))
+        };
+
+        // Create the pixel buffer, unless compute passes replace it
+        let capacity = match compute {
+            Some(_) => 0,
+            None => self
+                .frame_format
+                .frame_size(width, height, texture_format_size),
+        };
         let mut pixels = Vec::with_capacity(capacity);
         pixels.resize_with(capacity, Default::default);
 
-        let present_mode = self.present_mode;
+        // Background views never render faster than the display
+        let present_mode = match self.priority {
+            Priority::Background => wgpu::PresentMode::Fifo,
+            _ => self.present_mode,
+        };
+        if present_mode != self.present_mode {
+            capabilities.push(Downgrade::PresentMode {
+                requested: self.present_mode,
+                used: present_mode,
+            });
+        }
+        // Inserted frames are a temporal effect
+        let frame_insertion = if self.deterministic {
+            FrameInsertion::Disabled
+        } else {
+            self.frame_insertion
+        };
+        if frame_insertion.frames() > 0
+            && (present_mode != wgpu::PresentMode::Fifo || self.surface_texture.surface.is_none())
+        {
+            capabilities.push(Downgrade::FrameInsertionDisabled);
+        }
+
+        // Create a pass to dim inserted frames
+        let dimmer = match frame_insertion {
+            FrameInsertion::Dim { .. } => Some(Dimmer::new(&device, self.surface_format)),
+            _ => None,
+        };
 
-        // Create swap chain
+        // Create swap chain, or a texture to render into without a window
         let surface_texture = self.surface_texture;
-        let swap_chain = device.create_swap_chain(
-            &surface_texture.surface,
-            &wgpu::SwapChainDescriptor {
-                usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
-                format: wgpu::TextureFormat::Bgra8UnormSrgb,
-                width: surface_texture.width,
-                height: surface_texture.height,
-                present_mode,
-            },
-        );
+        let swap_chain = surface_texture
+            .surface
+            .as_ref()
+            .filter(|_| !self.defer_swap_chain)
+            .map(|surface| {
+                device.create_swap_chain(
+                    surface,
+                    &wgpu::SwapChainDescriptor {
+                        usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+                        format: self.surface_format,
+                        width: surface_texture.width,
+                        height: surface_texture.height,
+                        present_mode,
+                    },
+                )
+            });
+        let rendered_frame = if surface_texture.surface.is_none() {
+            Some(RenderedFrame::new(
+                &device,
+                surface_texture.width,
+                surface_texture.height,
+                self.surface_format,
+            ))
+        } else {
+            None
+        };
 
-        let scaling_matrix_inverse = renderers::ScalingMatrix::new(
+        let scaling = Scaling {
+            mode: self.dimension.scaling_mode(self.scaling_mode),
+            side_by_side: self.side_by_side,
+            pixel_aspect_ratio: self.pixel_aspect_ratio as f32,
+            crop: Crop::new(self.overscan, width, height),
+            viewport: None,
+            offset: (0.0, 0.0),
+        };
+        let scaling_matrix = scaling.scaling_matrix(
             (width as f32, height as f32),
             (surface_texture.width as f32, surface_texture.height as f32),
-        )
-        .transform
-        .inversed();
+        );
+        let scaling_matrix_inverse = scaling_matrix.transform.inversed();
 
-        // Create a renderer that impls `RenderPass`
-        let mut renderers = vec![Renderer::factory(
-            device.clone(),
-            queue.clone(),
-            &texture_view,
-            &texture_extent,
-        )];
+        // Render passes sample the blended frame when interpolating
+        let interpolator = if self.interpolation && !self.deterministic {
+            Some(Interpolator::new(
+                &device,
+                self.texture_format,
+                texture_extent,
+            ))
+        } else {
+            None
+        };
+        let texture_view = match &interpolator {
+            Some(interpolator) => interpolator.view(),
+            None => texture_view,
+        };
 
-        // Create all render passes
-        renderers.extend(self.renderer_factories.iter().map(|f| {
-            // TODO: Create a texture chain so that each pass receives the texture drawn by the previous
-            f(
+        // Create all background passes, which draw first
+        let inputs = |sampler| PassInputs {
+            texture: &texture_view,
+            texture_size: &texture_extent,
+            surface_format: self.surface_format,
+            sampler,
+            previous_frame: previous_view.as_ref(),
+            user_data: user_data.as_ref(),
+        };
+        let mut renderers: Vec<_> = self
+            .background_factories
+            .iter()
+            .enumerate()
+            .map(|(i, pass)| LabeledRenderPass {
+                label: pass.label.clone(),
+                load_op: if i == 0 {
+                    wgpu::LoadOp::Clear
+                } else {
+                    pass.load_op
+                },
+                sampler: pass.sampler,
+                render_pass: (pass.factory)(device.clone(), queue.clone(), &inputs(pass.sampler)),
+                enabled: true,
+                predicate: None,
+                skipped: false,
+            })
+            .collect();
+
+        // Create a renderer that impls `RenderPass`
+        let scaling_renderer = renderers.len();
+        let scaling_options = Arc::new(Mutex::new(ScalingOptions {
+            mode: scaling.mode,
+            filter: self.filter_mode,
+            viewport: None,
+            offset: (0.0, 0.0),
+            crop: scaling.crop,
+        }));
+        renderers.push(LabeledRenderPass {
+            label: "pixels_scaling_renderer".to_string(),
+            load_op: if renderers.is_empty() {
+                wgpu::LoadOp::Clear
+            } else {
+                wgpu::LoadOp::Load
+            },
+            sampler: InputSampler::default(),
+            render_pass: Renderer::factory(
                 device.clone(),
                 queue.clone(),
                 &texture_view,
                 &texture_extent,
-            )
+                self.swizzle,
+                &self.scaling_shaders,
+                scaling,
+                scaling_options.clone(),
+                self.surface_format,
+                match (self.alpha_blending, self.premultiply_alpha) {
+                    (false, _) => Blending::Replace,
+                    (true, false) => Blending::Alpha,
+                    (true, true) => Blending::PremultipliedAlpha,
+                },
+            ),
+            enabled: true,
+            predicate: None,
+            skipped: false,
+        });
+
+        // Create all render passes
+        renderers.extend(self.renderer_factories.iter().map(|pass| {
+            // TODO: Create a texture chain so that each pass receives the texture drawn by the previous
+            LabeledRenderPass {
+                label: pass.label.clone(),
+                load_op: pass.load_op,
+                sampler: pass.sampler,
+                render_pass: (pass.factory)(device.clone(), queue.clone(), &inputs(pass.sampler)),
+                enabled: true,
+                predicate: None,
+                skipped: false,
+            }
+        }));
+
+        let upload_target = Arc::new(Mutex::new(UploadTarget {
+            texture: Arc::clone(&texture),
+            extent: texture_extent,
+            bytes_per_pixel: texture_format_size,
+            bottom_up_rows: self.bottom_up_rows,
+            premultiply_alpha: self.premultiply_alpha,
+            srgb: is_srgb(self.texture_format),
+            uploaded: false,
         }));
 
         Ok(Pixels {
@@ -680,17 +5386,133 @@ impl<'req> PixelsBuilder<'req> {
             queue,
             swap_chain,
             surface_texture,
+            rendered_frame,
+            frame_retained: false,
             present_mode,
+            surface_format: self.surface_format,
+            present_timer: PresentTimer::default(),
+            redraw: true,
+            capabilities,
+            clear_color: self.clear_color,
+            pass_through: self.pass_through
+                && self.swizzle == Swizzle::IDENTITY
+                && self.scaling_shaders == ScalingShaders::default()
+                && !self.alpha_blending,
+            present_margin: self.present_margin,
+            frame_insertion,
+            dimmer,
+            background: false,
+            background_throttle: self.background_throttle,
+            last_render: None,
+            interpolator,
+            clock: Clock::new(self.deterministic),
+            frame_index: 0,
+            last_frame_time: None,
             renderers,
+            layers: Layers::new(&device, self.surface_format),
+            scaling_renderer,
+            scaling_options,
+            gizmos: Gizmos::default(),
+            gizmo_renderer: GizmoRenderer::new(&device, &texture_extent, self.surface_format),
             texture,
+            upload_target,
             texture_extent,
+            texture_format: self.texture_format,
             texture_format_size,
+            dimension: self.dimension,
+            scaling,
             pixels,
+            dirty: DirtyRows::new(height),
+            bottom_up_rows: self.bottom_up_rows,
+            premultiply_alpha: self.premultiply_alpha,
+            staging: StagingRing::default(),
+            frame_format: self.frame_format,
+            converter,
+            palette_handle: PaletteHandle::default(),
+            back_frame: None,
+            source_buffer: Vec::new(),
+            animation: None,
+            previous_texture,
+            next_pass_label: self.renderer_factories.len(),
+            compute,
+            overscan_crop: scaling.crop,
+            user_data,
+            user_data_size: self.user_data_size,
+            pending_user_data: None,
+            frame_history: Arc::new(Mutex::new(FrameHistory::new(self.frame_history))),
+            crash_dump: None,
+            screenshots: Screenshots::default(),
+            #[cfg(feature = "renderdoc")]
+            gpu_capture,
+            #[cfg(feature = "capture")]
+            recording: None,
             scaling_matrix_inverse,
+            events: EventQueue::new(scaling_matrix.scale, scaling_matrix.destination),
         })
     }
 }
 
+/// Create the texture which holds a copy of the pixel buffer texture from the previous frame.
+fn create_previous_texture(
+    device: &wgpu::Device,
+    texture_extent: Extent3d,
+    texture_format: wgpu::TextureFormat,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("pixels_previous_frame_texture"),
+        size: texture_extent,
+        array_layer_count: 1,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: texture_format,
+        usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
+    })
+}
+
+/// Create the uniform buffer for the per-frame user data, filled with zeros.
+fn create_user_data_buffer(device: &wgpu::Device, size: wgpu::BufferAddress) -> UserDataBuffer {
+    let zeros = vec![0; size as usize];
+
+    Arc::new(device.create_buffer_with_data(
+        &zeros,
+        wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+    ))
+}
+
+/// The surface format used when none is set with [`PixelsBuilder::surface_texture_format`].
+fn default_surface_format(srgb: bool) -> wgpu::TextureFormat {
+    match (cfg!(target_os = "android"), srgb) {
+        (true, true) => wgpu::TextureFormat::Rgba8UnormSrgb,
+        (true, false) => wgpu::TextureFormat::Rgba8Unorm,
+        (false, true) => wgpu::TextureFormat::Bgra8UnormSrgb,
+        (false, false) => wgpu::TextureFormat::Bgra8Unorm,
+    }
+}
+
+/// Whether a texture format stores colors with `sRGB` encoding.
+fn is_srgb(format: wgpu::TextureFormat) -> bool {
+    match format {
+        wgpu::TextureFormat::Rgba8UnormSrgb | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+        _ => false,
+    }
+}
+
+/// Premultiply palette colors, like the pixels of the texture frame format.
+fn premultiply_colors(colors: &mut [[u8; 4]], srgb: bool) {
+    for color in colors.iter_mut() {
+        color::premultiply_alpha(color, srgb);
+    }
+}
+
+/// Whether the red and blue channels of a surface format are swapped for readback.
+fn is_bgra(format: wgpu::TextureFormat) -> bool {
+    match format {
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+        _ => false,
+    }
+}
+
 fn get_texture_format_size(texture_format: wgpu::TextureFormat) -> u32 {
     match texture_format {
         // 8-bit formats
@@ -743,13 +5565,52 @@ fn get_texture_format_size(texture_format: wgpu::TextureFormat) -> u32 {
     }
 }
 
-fn get_default_power_preference() -> wgpu::PowerPreference {
-    env::var("PIXELS_HIGH_PERF").map_or_else(
-        |_| {
-            env::var("PIXELS_LOW_POWER").map_or(wgpu::PowerPreference::Default, |_| {
-                wgpu::PowerPreference::LowPower
-            })
-        },
-        |_| wgpu::PowerPreference::HighPerformance,
-    )
+/// Parse the power preference requested by the `PIXELS_HIGH_PERF` and `PIXELS_LOW_POWER`
+/// environment variables.
+///
+/// Returns `None` when neither variable is set. `PIXELS_HIGH_PERF` takes precedence when both are
+/// set. See the [crate documentation](./index.html#environment-variables) for how the variables
+/// are applied by default.
+///
+/// # Examples
+///
+/// ```no_run
+/// use pixels::{power_preference_from_env, PixelsBuilder};
+///
+/// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+/// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+/// let power_preference = power_preference_from_env().unwrap_or(wgpu::PowerPreference::LowPower);
+/// let pixels = PixelsBuilder::new(320, 240, surface_texture)
+///     .request_adapter_options(wgpu::RequestAdapterOptions {
+///         power_preference,
+///         compatible_surface: None,
+///     })
+///     .build()?;
+/// # Ok::<(), pixels::Error>(())
+/// ```
+#[cfg(feature = "env")]
+pub fn power_preference_from_env() -> Option<wgpu::PowerPreference> {
+    if env::var_os("PIXELS_HIGH_PERF").is_some() {
+        Some(wgpu::PowerPreference::HighPerformance)
+    } else if env::var_os("PIXELS_LOW_POWER").is_some() {
+        Some(wgpu::PowerPreference::LowPower)
+    } else {
+        None
+    }
+}
+
+/// The power preference from the environment variables, when `enabled`.
+#[cfg(feature = "env")]
+pub(crate) fn read_env_power_preference(enabled: bool) -> Option<wgpu::PowerPreference> {
+    if enabled {
+        power_preference_from_env()
+    } else {
+        None
+    }
+}
+
+/// The environment is never read without the `env` feature.
+#[cfg(not(feature = "env"))]
+pub(crate) fn read_env_power_preference(_enabled: bool) -> Option<wgpu::PowerPreference> {
+    None
 }
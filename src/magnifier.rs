@@ -0,0 +1,236 @@
+use crate::bindings;
+use crate::crash::lock;
+use crate::include_spv;
+use crate::pipeline::{create_pipeline, Blending};
+use crate::render_pass::{Device, InputSampler, Queue};
+use crate::renderers::ScalingMatrix;
+use crate::{Error, Pixels, Rect, SurfaceTexture};
+
+/// A second window which shows a magnified, live region of a pixel buffer.
+///
+/// The magnifier shares the device and the pixel buffer texture with the [`Pixels`] it was
+/// created from, so nothing is copied on the CPU. It is meant for debugging tools around
+/// emulators, like inspecting sprites or tiles while the game runs.
+///
+/// The region is scaled by the largest factor that fits the window, with square pixels and
+/// nearest neighbor sampling, and centered. There are no borders: the rest of the window shows
+/// the pixels surrounding the region, where there are any.
+///
+/// Create a magnifier with [`Pixels::magnifier`].
+///
+/// [`Pixels`]: ./struct.Pixels.html
+/// [`Pixels::magnifier`]: ./struct.Pixels.html#method.magnifier
+#[derive(Debug)]
+pub struct Magnifier {
+    device: Device,
+    queue: Queue,
+    surface: wgpu::Surface,
+    swap_chain: wgpu::SwapChain,
+    format: wgpu::TextureFormat,
+    present_mode: wgpu::PresentMode,
+    size: (u32, u32),
+    // Magnified region of the pixel buffer, in pixels
+    region: (u32, u32, u32, u32),
+    sampler: wgpu::Sampler,
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl Magnifier {
+    pub(crate) fn new(pixels: &Pixels, surface_texture: SurfaceTexture) -> Magnifier {
+        let SurfaceTexture {
+            surface,
+            width,
+            height,
+        } = surface_texture;
+        let surface = surface.expect("The magnifier requires a window surface");
+
+        let device = pixels.device.clone();
+        let queue = pixels.queue.clone();
+        let format = pixels.surface_format;
+        let present_mode = pixels.present_mode;
+        let swap_chain = create_swap_chain(&device, &surface, format, present_mode, width, height);
+
+        let vs_module = device.create_shader_module(include_spv!("../shaders/vert.spv"));
+        let fs_module = device.create_shader_module(include_spv!("../shaders/frag.spv"));
+        let sampler = InputSampler::new(wgpu::AddressMode::ClampToEdge, wgpu::FilterMode::Nearest)
+            .create(&device);
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("pixels_magnifier_bind_group_layout"),
+            bindings: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: bindings::TEXTURE,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::SampledTexture {
+                        component_type: wgpu::TextureComponentType::Uint,
+                        multisampled: false,
+                        dimension: wgpu::TextureViewDimension::D2,
+                    },
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: bindings::SAMPLER,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler { comparison: false },
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: bindings::TRANSFORM,
+                    visibility: wgpu::ShaderStage::VERTEX,
+                    ty: wgpu::BindingType::UniformBuffer { dynamic: false },
+                },
+            ],
+        });
+        let pipeline = create_pipeline(
+            &device,
+            &bind_group_layout,
+            &vs_module,
+            &fs_module,
+            format,
+            Blending::Replace,
+        );
+
+        let extent = pixels.texture_extent;
+        Magnifier {
+            device,
+            queue,
+            surface,
+            swap_chain,
+            format,
+            present_mode,
+            size: (width, height),
+            region: (0, 0, extent.width, extent.height),
+            sampler,
+            bind_group_layout,
+            pipeline,
+        }
+    }
+
+    /// Select the region of the pixel buffer to magnify, in pixels.
+    ///
+    /// The entire pixel buffer is shown until a region is selected.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `width` or `height` are 0.
+    pub fn set_region(&mut self, x: u32, y: u32, width: u32, height: u32) {
+        assert!(width > 0);
+        assert!(height > 0);
+
+        self.region = (x, y, width, height);
+    }
+
+    /// Resize the magnifier window surface, in physical pixels.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.size = (width, height);
+        self.swap_chain = create_swap_chain(
+            &self.device,
+            &self.surface,
+            self.format,
+            self.present_mode,
+            width,
+            height,
+        );
+    }
+
+    /// Draw the magnified region of `pixels` into the magnifier window.
+    ///
+    /// The magnifier shows the pixel buffer as it was uploaded by the most recent call to
+    /// [`Pixels::render`], before any render passes. `pixels` must be the pixel buffer that the
+    /// magnifier was created from.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the next texture cannot be acquired from the swap chain.
+    ///
+    /// [`Pixels::render`]: ./struct.Pixels.html#method.render
+    pub fn render(&mut self, pixels: &Pixels) -> Result<(), Error> {
+        let frame = self
+            .swap_chain
+            .get_next_texture()
+            .map_err(|_| Error::Timeout)?;
+
+        // Place the whole texture so that the region fills the window
+        let extent = pixels.texture_extent;
+        let (x, y, width, height) = self.region;
+        let screen_size = (self.size.0 as f32, self.size.1 as f32);
+        let scale = (screen_size.0 / width as f32).min(screen_size.1 / height as f32);
+        let destination = Rect {
+            x: (screen_size.0 - width as f32 * scale) / 2.0 - x as f32 * scale,
+            y: (screen_size.1 - height as f32 * scale) / 2.0 - y as f32 * scale,
+            width: extent.width as f32 * scale,
+            height: extent.height as f32 * scale,
+        };
+        let matrix = ScalingMatrix::from_destination(screen_size, (scale, scale), destination);
+        let transform_buffer = self
+            .device
+            .create_buffer_with_data(matrix.as_bytes(), wgpu::BufferUsage::UNIFORM);
+
+        // The texture is replaced when the pixel buffer is resized, so bind it on every draw
+        let texture_view = pixels.texture.create_default_view();
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("pixels_magnifier_bind_group"),
+            layout: &self.bind_group_layout,
+            bindings: &[
+                wgpu::Binding {
+                    binding: bindings::TEXTURE,
+                    resource: wgpu::BindingResource::TextureView(&texture_view),
+                },
+                wgpu::Binding {
+                    binding: bindings::SAMPLER,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+                wgpu::Binding {
+                    binding: bindings::TRANSFORM,
+                    resource: wgpu::BindingResource::Buffer {
+                        buffer: &transform_buffer,
+                        range: 0..bindings::TRANSFORM_SIZE,
+                    },
+                },
+            ],
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("pixels_magnifier_command_encoder"),
+            });
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                    attachment: &frame.view,
+                    resolve_target: None,
+                    load_op: wgpu::LoadOp::Clear,
+                    store_op: wgpu::StoreOp::Store,
+                    clear_color: wgpu::Color::BLACK,
+                }],
+                depth_stencil_attachment: None,
+            });
+            rpass.set_pipeline(&self.pipeline);
+            rpass.set_bind_group(0, &bind_group, &[]);
+            rpass.draw(0..6, 0..1);
+        }
+        lock(&self.queue).submit(&[encoder.finish()]);
+
+        Ok(())
+    }
+}
+
+fn create_swap_chain(
+    device: &wgpu::Device,
+    surface: &wgpu::Surface,
+    format: wgpu::TextureFormat,
+    present_mode: wgpu::PresentMode,
+    width: u32,
+    height: u32,
+) -> wgpu::SwapChain {
+    device.create_swap_chain(
+        surface,
+        &wgpu::SwapChainDescriptor {
+            usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+            format,
+            width,
+            height,
+            present_mode,
+        },
+    )
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+use crate::render_pass::{Device, Queue};
+use crate::{read_env_power_preference, Error, Pixels, PixelsBuilder};
+
+/// Pixel buffers for several windows, which all share one device and queue.
+///
+/// Each window is identified by a key of the application's choice, like the window ID from the
+/// windowing library, and gets its own [`Pixels`] with its own surface, size, and options. All
+/// GPU resources are created on one device, so multi-window emulators and tools do not have to
+/// share devices through [`PixelsBuilder::shared_device`] by hand.
+///
+/// # Examples
+///
+/// ```no_run
+/// use pixels::{PixelsBuilder, PixelsMulti, SurfaceTexture};
+///
+/// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+/// # let debugger_surface = wgpu::Surface::create(&pixels_mocks::RWH);
+/// let mut windows = PixelsMulti::new(&surface)?;
+///
+/// let surface_texture = SurfaceTexture::new(1024, 768, surface);
+/// windows.add_window("main", PixelsBuilder::new(320, 240, surface_texture))?;
+/// let surface_texture = SurfaceTexture::new(512, 512, debugger_surface);
+/// windows.add_window("tiles", PixelsBuilder::new(128, 128, surface_texture))?;
+///
+/// // ... Draw into each frame
+/// windows.get_mut(&"main").unwrap().get_frame();
+/// windows.resize(&"tiles", 768, 768);
+/// windows.render_all()?;
+/// # Ok::<(), pixels::Error>(())
+/// ```
+///
+/// [`Pixels`]: ./struct.Pixels.html
+/// [`PixelsBuilder::shared_device`]: ./struct.PixelsBuilder.html#method.shared_device
+pub struct PixelsMulti<K> {
+    adapter: wgpu::Adapter,
+    device: Device,
+    queue: Queue,
+    windows: HashMap<K, Pixels>,
+}
+
+impl<K: Eq + Hash> PixelsMulti<K> {
+    /// Request a device which is compatible with `surface`, without any windows.
+    ///
+    /// The power preference is read from the environment, like the default for a single pixel
+    /// buffer, unless the `env` feature is disabled. See [`power_preference_from_env`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when a [`wgpu::Adapter`] cannot be found.
+    ///
+    /// [`power_preference_from_env`]: ./fn.power_preference_from_env.html
+    pub fn new(surface: &wgpu::Surface) -> Result<PixelsMulti<K>, Error> {
+        let adapter = pollster::block_on(wgpu::Adapter::request(
+            &wgpu::RequestAdapterOptions {
+                power_preference: read_env_power_preference(true)
+                    .unwrap_or(wgpu::PowerPreference::Default),
+                compatible_surface: Some(surface),
+            },
+            wgpu::BackendBit::PRIMARY,
+        ))
+        .ok_or(Error::AdapterNotFound)?;
+        let (device, queue) =
+            pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default()));
+
+        Ok(PixelsMulti {
+            adapter,
+            device: Arc::new(device),
+            queue: Arc::new(Mutex::new(queue)),
+            windows: HashMap::new(),
+        })
+    }
+
+    /// Build a pixel buffer for the window identified by `key`, on the shared device.
+    ///
+    /// Any pixel buffer that already exists for `key` is replaced.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`PixelsBuilder::build`].
+    ///
+    /// [`PixelsBuilder::build`]: ./struct.PixelsBuilder.html#method.build
+    pub fn add_window(&mut self, key: K, builder: PixelsBuilder<'_>) -> Result<&mut Pixels, Error>
+    where
+        K: Clone,
+    {
+        let pixels = builder
+            .shared_device(&self.adapter, self.device.clone(), self.queue.clone())
+            .build()?;
+        self.windows.insert(key.clone(), pixels);
+
+        Ok(self.windows.get_mut(&key).unwrap())
+    }
+
+    /// Remove the pixel buffer for the window identified by `key`, e.g. when the window closes.
+    pub fn remove_window(&mut self, key: &K) -> Option<Pixels> {
+        self.windows.remove(key)
+    }
+
+    /// The pixel buffer for the window identified by `key`.
+    pub fn get(&self, key: &K) -> Option<&Pixels> {
+        self.windows.get(key)
+    }
+
+    /// The pixel buffer for the window identified by `key`, for drawing into its frame.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut Pixels> {
+        self.windows.get_mut(key)
+    }
+
+    /// Resize the surface of the window identified by `key`. See [`Pixels::resize`].
+    ///
+    /// # Panics
+    ///
+    /// Panics when there is no pixel buffer for `key`.
+    ///
+    /// [`Pixels::resize`]: ./struct.Pixels.html#method.resize
+    pub fn resize(&mut self, key: &K, width: u32, height: u32) {
+        self.window_mut(key).resize(width, height);
+    }
+
+    /// Render the window identified by `key`. See [`Pixels::render`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the next texture cannot be acquired from the swap chain.
+    ///
+    /// # Panics
+    ///
+    /// Panics when there is no pixel buffer for `key`.
+    ///
+    /// [`Pixels::render`]: ./struct.Pixels.html#method.render
+    pub fn render(&mut self, key: &K) -> Result<(), Error> {
+        self.window_mut(key).render()
+    }
+
+    /// Render every window. Stops at the first error.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the next texture cannot be acquired from a swap chain.
+    pub fn render_all(&mut self) -> Result<(), Error> {
+        for pixels in self.windows.values_mut() {
+            pixels.render()?;
+        }
+
+        Ok(())
+    }
+
+    /// Iterate over all windows and their pixel buffers.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &mut Pixels)> {
+        self.windows.iter_mut()
+    }
+
+    /// The device shared by all windows.
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// The queue shared by all windows.
+    pub fn queue(&self) -> &Queue {
+        &self.queue
+    }
+
+    fn window_mut(&mut self, key: &K) -> &mut Pixels {
+        self.windows
+            .get_mut(key)
+            .expect("There is no pixel buffer for the window")
+    }
+}
+
+impl<K: fmt::Debug> fmt::Debug for PixelsMulti<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PixelsMulti")
+            .field("device", &self.device)
+            .field("queue", &self.queue)
+            .field("windows", &self.windows)
+            .finish()
+    }
+}
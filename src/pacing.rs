@@ -0,0 +1,174 @@
+use instant::Instant;
+use std::time::Duration;
+
+/// Weight of each new sample in the moving average of the refresh interval.
+const SMOOTHING: f64 = 0.1;
+
+/// Number of consecutive outliers which indicate that the refresh rate changed, e.g. when the
+/// window moves to another monitor.
+const OUTLIERS_BEFORE_RESET: u32 = 8;
+
+/// Estimates the display refresh interval and the time of the next vblank from the times that
+/// swap chain textures become available.
+///
+/// With a `Fifo` present mode, acquiring the next texture blocks until the display has consumed
+/// a frame, so these timestamps track the vblank closely.
+#[derive(Debug, Default)]
+pub(crate) struct PresentTimer {
+    last_present: Option<Instant>,
+    interval: Option<Duration>,
+    outliers: u32,
+    refresh_interval: Option<Duration>,
+}
+
+impl PresentTimer {
+    /// Record that a swap chain texture was acquired at `now`.
+    pub(crate) fn record(&mut self, now: Instant) {
+        if let Some(last_present) = self.last_present {
+            let delta = now.saturating_duration_since(last_present);
+
+            self.interval = Some(match self.interval {
+                // Missed frames and stalls are not representative of the refresh rate, unless
+                // they keep happening
+                Some(interval) if delta > interval * 2 || delta < interval / 2 => {
+                    self.outliers += 1;
+                    if self.outliers < OUTLIERS_BEFORE_RESET {
+                        interval
+                    } else {
+                        self.outliers = 0;
+                        delta
+                    }
+                }
+                Some(interval) => {
+                    self.outliers = 0;
+                    interval.mul_f64(1.0 - SMOOTHING) + delta.mul_f64(SMOOTHING)
+                }
+                None => delta,
+            });
+        }
+
+        self.last_present = Some(now);
+    }
+
+    /// Use the refresh interval reported by the application instead of the estimate, or go back
+    /// to estimating with `None`.
+    ///
+    /// The estimate restarts from the new interval, so it adapts immediately when the window
+    /// moves to a monitor with a different refresh rate.
+    pub(crate) fn set_refresh_interval(&mut self, refresh_interval: Option<Duration>) {
+        self.refresh_interval = refresh_interval;
+        self.interval = refresh_interval;
+        self.outliers = 0;
+    }
+
+    /// The refresh interval, if it was reported or enough frames have been presented to estimate
+    /// it.
+    pub(crate) fn interval(&self) -> Option<Duration> {
+        self.refresh_interval.or(self.interval)
+    }
+
+    /// The estimated time of the next vblank after `now`.
+    pub(crate) fn next_deadline(&self, now: Instant) -> Option<Instant> {
+        let last_present = self.last_present?;
+        let interval = self.interval()?;
+        if interval == Duration::from_secs(0) {
+            return None;
+        }
+
+        // Skip ahead by whole intervals when presents were missed
+        let elapsed = now.saturating_duration_since(last_present);
+        let intervals = elapsed.as_nanos() / interval.as_nanos() + 1;
+
+        Some(last_present + interval * intervals as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: Duration = Duration::from_millis(16);
+
+    fn assert_close(actual: Option<Duration>, expected: Duration) {
+        let actual = actual.expect("no refresh interval");
+        let error = if actual > expected {
+            actual - expected
+        } else {
+            expected - actual
+        };
+        assert!(error < Duration::from_micros(1), "{:?}", actual);
+    }
+
+    /// A timer which recorded `frames` presents `FRAME` apart, and the time of the last one.
+    fn steady_timer(frames: u32) -> (PresentTimer, Instant) {
+        let mut timer = PresentTimer::default();
+        let mut now = Instant::now();
+        timer.record(now);
+        for _ in 1..frames {
+            now += FRAME;
+            timer.record(now);
+        }
+
+        (timer, now)
+    }
+
+    #[test]
+    fn estimates_the_interval() {
+        let (timer, _) = steady_timer(1);
+        assert_eq!(timer.interval(), None);
+
+        let (timer, _) = steady_timer(10);
+        assert_close(timer.interval(), FRAME);
+    }
+
+    #[test]
+    fn ignores_missed_frames() {
+        let (mut timer, now) = steady_timer(10);
+        timer.record(now + FRAME * 3);
+
+        assert_close(timer.interval(), FRAME);
+    }
+
+    #[test]
+    fn resets_when_the_refresh_rate_changes() {
+        let (mut timer, mut now) = steady_timer(10);
+        let slower = Duration::from_millis(40);
+        for _ in 0..OUTLIERS_BEFORE_RESET - 1 {
+            now += slower;
+            timer.record(now);
+        }
+        assert_close(timer.interval(), FRAME);
+
+        now += slower;
+        timer.record(now);
+        assert_close(timer.interval(), slower);
+    }
+
+    #[test]
+    fn reported_refresh_interval() {
+        let (mut timer, _) = steady_timer(10);
+        let reported = Duration::from_millis(7);
+
+        timer.set_refresh_interval(Some(reported));
+        assert_eq!(timer.interval(), Some(reported));
+
+        // Going back to estimating starts over
+        timer.set_refresh_interval(None);
+        assert_eq!(timer.interval(), None);
+    }
+
+    #[test]
+    fn next_deadline() {
+        let mut timer = PresentTimer::default();
+        let start = Instant::now();
+        assert_eq!(timer.next_deadline(start), None);
+
+        timer.record(start);
+        timer.set_refresh_interval(Some(FRAME));
+        assert_eq!(timer.next_deadline(start), Some(start + FRAME));
+
+        // Missed vblanks are skipped
+        let late = start + Duration::from_millis(40);
+        assert_eq!(timer.next_deadline(late), Some(start + FRAME * 3));
+    }
+}
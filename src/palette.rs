@@ -0,0 +1,456 @@
+//! Palette utilities for indexed-color pixel buffers.
+//!
+//! A [`Palette`] holds up to 256 `RGBA` colors. Indexed frames store one byte per pixel, each an
+//! index into the palette. The palettes of common retro systems are available with
+//! [`Palette::preset`].
+
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Index;
+use std::sync::{Arc, Mutex};
+
+use crate::color::Color;
+use crate::crash::lock;
+
+/// The maximum number of colors in a [`Palette`].
+pub const MAX_COLORS: usize = 256;
+
+/// The system palettes available with [`Palette::preset`].
+///
+/// All colors are fully opaque.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Preset {
+    /// The 16 colors of CGA text modes, including the brown at index 6.
+    Cga,
+    /// All 64 colors of the EGA, in the order of the 6-bit `rgbRGB` color value.
+    Ega,
+    /// The default 256 color palette of VGA mode 13h: the 16 CGA colors, 16 grays, 216 colors in
+    /// three intensities and three saturations of a 24 step hue wheel, and 8 black entries.
+    Vga,
+    /// The 4 shades of green of the original Game Boy, from lightest (index 0) to darkest.
+    GameBoy,
+    /// The 64 entries of the NES PPU for NTSC consoles, in the order of the PPU color values.
+    ///
+    /// The NES generates its colors as an analog signal, so there is no exact RGB palette. This is
+    /// the widely used emulator approximation, which is slightly off from real hardware; games
+    /// still look the way most players remember them.
+    Nes,
+}
+
+/// A list of up to 256 `RGBA` colors for indexed-color frames.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Palette {
+    colors: Vec<[u8; 4]>,
+}
+
+impl Palette {
+    /// Create a palette from a list of `RGBA` colors.
+    ///
+    /// # Panics
+    ///
+    /// Panics when there are more than 256 colors.
+    pub fn new(colors: Vec<[u8; 4]>) -> Palette {
+        assert!(colors.len() <= MAX_COLORS);
+
+        Palette { colors }
+    }
+
+    /// Create the palette of a retro system.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pixels::palette::{Palette, Preset};
+    ///
+    /// let palette = Palette::preset(Preset::Nes);
+    /// assert_eq!(palette.len(), 64);
+    /// assert_eq!(palette[0x30], [0xfc, 0xfc, 0xfc, 0xff]);
+    ///
+    /// let palette = Palette::preset(Preset::Vga);
+    /// assert_eq!(palette.len(), 256);
+    /// assert_eq!(palette.colors()[..16], Palette::preset(Preset::Cga).colors()[..]);
+    /// ```
+    pub fn preset(preset: Preset) -> Palette {
+        let colors = match preset {
+            Preset::Cga => CGA.iter().copied().map(rgb).collect(),
+            Preset::Ega => (0u8..64)
+                .map(|value| {
+                    // Each channel has a high intensity bit and a low intensity bit
+                    let channel =
+                        |high, low| 0xaa * ((value >> high) & 1) + 0x55 * ((value >> low) & 1);
+
+                    [channel(2, 5), channel(1, 4), channel(0, 3), 0xff]
+                })
+                .collect(),
+            Preset::Vga => vga(),
+            Preset::GameBoy => GAME_BOY.iter().copied().map(rgb).collect(),
+            Preset::Nes => NES.iter().copied().map(rgb).collect(),
+        };
+
+        Palette::new(colors)
+    }
+
+    /// Create a palette from colors with linear components in `RGBA` order, e.g. computed with
+    /// lighting math. The colors are encoded as `sRGB` for the default texture format.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pixels::palette::Palette;
+    ///
+    /// // A ramp with evenly spaced light intensities, which looks brighter than even bytes
+    /// let ramp: Vec<_> = (0..4).map(|i| [i as f32 / 3.0; 4]).collect();
+    /// let palette = Palette::from_linear(&ramp);
+    /// assert_eq!(palette[1], [0x9c, 0x9c, 0x9c, 0x55]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics when there are more than 256 colors.
+    pub fn from_linear(colors: &[[f32; 4]]) -> Palette {
+        let colors = colors
+            .iter()
+            .map(|&[r, g, b, a]| Color::from_linear(r, g, b, a).to_rgba8())
+            .collect();
+
+        Palette::new(colors)
+    }
+
+    /// Decode all colors to linear components in `RGBA` order.
+    ///
+    /// This is a conversion table for color math on indexed frames: look up the linear color of
+    /// an index, compute with it, and convert the result back with [`Palette::from_linear`] or
+    /// [`Color::from_linear`].
+    ///
+    /// [`Color::from_linear`]: ../color/struct.Color.html#method.from_linear
+    pub fn to_linear(&self) -> Vec<[f32; 4]> {
+        self.colors
+            .iter()
+            .map(|&[r, g, b, a]| Color::from_rgba8(r, g, b, a).to_linear())
+            .collect()
+    }
+
+    /// The colors in the palette.
+    pub fn colors(&self) -> &[[u8; 4]] {
+        &self.colors
+    }
+
+    /// The number of colors in the palette.
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    /// Returns `true` when the palette has no colors.
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+
+    /// Get the full 256 entry table. Unused entries are transparent black.
+    pub fn to_array(&self) -> [[u8; 4]; MAX_COLORS] {
+        let mut table = [[0; 4]; MAX_COLORS];
+        table[..self.colors.len()].copy_from_slice(&self.colors);
+
+        table
+    }
+}
+
+/// A handle for changing palette entries from any thread.
+///
+/// Handles are cheap to clone and can be sent to other threads, like an emulator core which
+/// changes palette entries in the middle of a frame. Changes are staged until the next call to
+/// [`Pixels::render`], where only the changed entries are uploaded to the GPU.
+///
+/// Get a handle with [`Pixels::palette_handle`]. Palettes only apply to the indexed frame
+/// formats.
+///
+/// # Examples
+///
+/// ```no_run
+/// use pixels::{FrameFormat, PixelsBuilder};
+///
+/// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+/// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+/// let mut pixels = PixelsBuilder::new(256, 240, surface_texture)
+///     .frame_format(FrameFormat::Indexed8)
+///     .build()?;
+/// let palette = pixels.palette_handle();
+///
+/// std::thread::spawn(move || {
+///     // ... Emulate until the core writes to palette RAM
+///     palette.set_color(3, [0x5e, 0x48, 0xe8, 0xff]);
+/// });
+/// # Ok::<(), pixels::Error>(())
+/// ```
+///
+/// [`Pixels::render`]: ../struct.Pixels.html#method.render
+/// [`Pixels::palette_handle`]: ../struct.Pixels.html#method.palette_handle
+#[derive(Clone, Debug, Default)]
+pub struct PaletteHandle {
+    staged: Arc<Mutex<BTreeMap<u8, [u8; 4]>>>,
+}
+
+impl PaletteHandle {
+    /// Change the color of one palette entry.
+    pub fn set_color(&self, index: u8, color: [u8; 4]) {
+        lock(&self.staged).insert(index, color);
+    }
+
+    /// Change consecutive palette entries, beginning at `start`.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the entries extend past the end of the palette.
+    pub fn set_colors(&self, start: u8, colors: &[[u8; 4]]) {
+        assert!(start as usize + colors.len() <= MAX_COLORS);
+
+        let mut staged = lock(&self.staged);
+        for (index, color) in (start..=255).zip(colors.iter()) {
+            staged.insert(index, *color);
+        }
+    }
+
+    /// Replace every entry with the colors in `palette`. Unused entries become transparent black.
+    pub fn set_palette(&self, palette: &Palette) {
+        self.set_colors(0, &palette.to_array());
+    }
+
+    /// Returns `true` when changes are waiting to be applied.
+    pub(crate) fn is_pending(&self) -> bool {
+        !lock(&self.staged).is_empty()
+    }
+
+    /// Take all staged changes, ordered by palette index.
+    pub(crate) fn take(&self) -> BTreeMap<u8, [u8; 4]> {
+        std::mem::replace(&mut lock(&self.staged), BTreeMap::new())
+    }
+}
+
+/// CGA colors as `0xRRGGBB`.
+const CGA: [u32; 16] = [
+    0x00_00_00, 0x00_00_aa, 0x00_aa_00, 0x00_aa_aa, 0xaa_00_00, 0xaa_00_aa, 0xaa_55_00, 0xaa_aa_aa,
+    0x55_55_55, 0x55_55_ff, 0x55_ff_55, 0x55_ff_ff, 0xff_55_55, 0xff_55_ff, 0xff_ff_55, 0xff_ff_ff,
+];
+
+/// Game Boy shades as `0xRRGGBB`.
+const GAME_BOY: [u32; 4] = [0x9b_bc_0f, 0x8b_ac_0f, 0x30_62_30, 0x0f_38_0f];
+
+/// NES NTSC colors as `0xRRGGBB`.
+#[rustfmt::skip]
+const NES: [u32; 64] = [
+    0x7c_7c_7c, 0x00_00_fc, 0x00_00_bc, 0x44_28_bc, 0x94_00_84, 0xa8_00_20, 0xa8_10_00, 0x88_14_00,
+    0x50_30_00, 0x00_78_00, 0x00_68_00, 0x00_58_00, 0x00_40_58, 0x00_00_00, 0x00_00_00, 0x00_00_00,
+    0xbc_bc_bc, 0x00_78_f8, 0x00_58_f8, 0x68_44_fc, 0xd8_00_cc, 0xe4_00_58, 0xf8_38_00, 0xe4_5c_10,
+    0xac_7c_00, 0x00_b8_00, 0x00_a8_00, 0x00_a8_44, 0x00_88_88, 0x00_00_00, 0x00_00_00, 0x00_00_00,
+    0xf8_f8_f8, 0x3c_bc_fc, 0x68_88_fc, 0x98_78_f8, 0xf8_78_f8, 0xf8_58_98, 0xf8_78_58, 0xfc_a0_44,
+    0xf8_b8_00, 0xb8_f8_18, 0x58_d8_54, 0x58_f8_98, 0x00_e8_d8, 0x78_78_78, 0x00_00_00, 0x00_00_00,
+    0xfc_fc_fc, 0xa4_e4_fc, 0xb8_b8_f8, 0xd8_b8_f8, 0xf8_b8_f8, 0xf8_a4_c0, 0xf0_d0_b0, 0xfc_e0_a8,
+    0xf8_d8_78, 0xd8_f8_78, 0xb8_f8_b8, 0xb8_f8_d8, 0x00_fc_fc, 0xf8_d8_f8, 0x00_00_00, 0x00_00_00,
+];
+
+/// VGA gray ramp as 6-bit DAC values.
+const VGA_GRAYS: [u8; 16] = [
+    0x00, 0x05, 0x08, 0x0b, 0x0e, 0x11, 0x14, 0x18, 0x1c, 0x20, 0x24, 0x28, 0x2d, 0x32, 0x38, 0x3f,
+];
+
+/// The 5 levels of each VGA hue wheel as 6-bit DAC values, for high, medium, and low intensity,
+/// each with high, medium, and low saturation.
+#[rustfmt::skip]
+const VGA_LEVELS: [[u8; 5]; 9] = [
+    [0x00, 0x10, 0x1f, 0x2f, 0x3f],
+    [0x1f, 0x27, 0x2f, 0x37, 0x3f],
+    [0x2d, 0x31, 0x36, 0x3a, 0x3f],
+    [0x00, 0x07, 0x0e, 0x15, 0x1c],
+    [0x0e, 0x11, 0x15, 0x18, 0x1c],
+    [0x14, 0x16, 0x18, 0x1a, 0x1c],
+    [0x00, 0x04, 0x08, 0x0c, 0x10],
+    [0x08, 0x0a, 0x0c, 0x0e, 0x10],
+    [0x0b, 0x0c, 0x0d, 0x0f, 0x10],
+];
+
+/// The 24 steps of the VGA hue wheel, from blue through red and green, as indices into a row of
+/// [`VGA_LEVELS`] for red, green, and blue.
+#[rustfmt::skip]
+const VGA_HUES: [[usize; 3]; 24] = [
+    [0, 0, 4], [1, 0, 4], [2, 0, 4], [3, 0, 4], [4, 0, 4], [4, 0, 3], [4, 0, 2], [4, 0, 1],
+    [4, 0, 0], [4, 1, 0], [4, 2, 0], [4, 3, 0], [4, 4, 0], [3, 4, 0], [2, 4, 0], [1, 4, 0],
+    [0, 4, 0], [0, 4, 1], [0, 4, 2], [0, 4, 3], [0, 4, 4], [0, 3, 4], [0, 2, 4], [0, 1, 4],
+];
+
+/// Convert `0xRRGGBB` to an opaque `RGBA` color.
+fn rgb(color: u32) -> [u8; 4] {
+    [(color >> 16) as u8, (color >> 8) as u8, color as u8, 0xff]
+}
+
+/// The default VGA mode 13h palette.
+fn vga() -> Vec<[u8; 4]> {
+    // Expand the 6-bit DAC values to 8 bits
+    let dac = |value: u8| (value << 2) | (value >> 4);
+
+    let mut colors: Vec<_> = CGA.iter().copied().map(rgb).collect();
+    colors.extend(VGA_GRAYS.iter().map(|&gray| {
+        let gray = dac(gray);
+        [gray, gray, gray, 0xff]
+    }));
+    for levels in VGA_LEVELS.iter() {
+        colors.extend(VGA_HUES.iter().map(|hue| {
+            [
+                dac(levels[hue[0]]),
+                dac(levels[hue[1]]),
+                dac(levels[hue[2]]),
+                0xff,
+            ]
+        }));
+    }
+    colors.resize(MAX_COLORS, [0x00, 0x00, 0x00, 0xff]);
+
+    colors
+}
+
+impl Index<u8> for Palette {
+    type Output = [u8; 4];
+
+    fn index(&self, index: u8) -> &[u8; 4] {
+        &self.colors[index as usize]
+    }
+}
+
+/// Reduce an `RGBA` frame to at most `n_colors` colors with the median cut algorithm.
+///
+/// Returns an indexed frame with one byte per pixel, and the palette that the indices refer to.
+/// The palette may have fewer than `n_colors` colors when the frame does not contain that many
+/// distinct colors.
+///
+/// # Examples
+///
+/// ```
+/// use pixels::palette::quantize;
+///
+/// let red = [0xff, 0x00, 0x00, 0xff];
+/// let blue = [0x00, 0x00, 0xff, 0xff];
+/// let frame = [red, red, blue, red].concat();
+///
+/// let (indices, palette) = quantize(&frame, 16);
+/// assert_eq!(palette.len(), 2);
+/// assert_eq!(indices.len(), 4);
+/// assert_eq!(palette[indices[0]], red);
+/// assert_eq!(palette[indices[2]], blue);
+/// ```
+///
+/// # Panics
+///
+/// Panics when `n_colors` is not in the range `1..=256`, or when the length of `frame` is not a
+/// multiple of 4.
+pub fn quantize(frame: &[u8], n_colors: usize) -> (Vec<u8>, Palette) {
+    assert!(n_colors > 0 && n_colors <= MAX_COLORS);
+    assert_eq!(frame.len() % 4, 0);
+
+    // Build a histogram of the distinct colors
+    let mut histogram: HashMap<[u8; 4], u32> = HashMap::new();
+    for pixel in frame.chunks_exact(4) {
+        let color = [pixel[0], pixel[1], pixel[2], pixel[3]];
+        *histogram.entry(color).or_insert(0) += 1;
+    }
+
+    // Repeatedly split the box with the widest channel range at its weighted median
+    let mut boxes = vec![ColorBox::new(histogram.into_iter().collect())];
+    while boxes.len() < n_colors {
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.colors.len() > 1)
+            .max_by_key(|(_, b)| b.range().0);
+        let i = match widest {
+            Some((i, _)) => i,
+            None => break,
+        };
+
+        let (first, second) = boxes.swap_remove(i).split();
+        boxes.push(first);
+        boxes.push(second);
+    }
+
+    // Map every distinct color to the box which contains it
+    let mut lookup = HashMap::new();
+    let mut colors = Vec::with_capacity(boxes.len());
+    for (i, color_box) in boxes.iter().enumerate() {
+        for (color, _) in color_box.colors.iter() {
+            lookup.insert(*color, i as u8);
+        }
+        colors.push(color_box.average());
+    }
+
+    let indices = frame
+        .chunks_exact(4)
+        .map(|pixel| lookup[&[pixel[0], pixel[1], pixel[2], pixel[3]]])
+        .collect();
+
+    (indices, Palette::new(colors))
+}
+
+/// A set of distinct colors and their pixel counts.
+struct ColorBox {
+    colors: Vec<([u8; 4], u32)>,
+}
+
+impl ColorBox {
+    fn new(colors: Vec<([u8; 4], u32)>) -> ColorBox {
+        ColorBox { colors }
+    }
+
+    /// The widest channel range in the box, and the channel it belongs to.
+    fn range(&self) -> (u8, usize) {
+        (0..4)
+            .map(|channel| {
+                let values = self.colors.iter().map(|(color, _)| color[channel]);
+                let min = values.clone().min().unwrap_or(0);
+                let max = values.max().unwrap_or(0);
+
+                (max - min, channel)
+            })
+            .max()
+            .unwrap_or((0, 0))
+    }
+
+    /// Split the box in two at the weighted median of its widest channel.
+    fn split(mut self) -> (ColorBox, ColorBox) {
+        let (_, channel) = self.range();
+        self.colors
+            .sort_unstable_by_key(|(color, _)| color[channel]);
+
+        let total: u64 = self.colors.iter().map(|(_, count)| u64::from(*count)).sum();
+        let mut sum = 0;
+        let mut median = 1;
+        for (i, (_, count)) in self.colors.iter().enumerate() {
+            sum += u64::from(*count);
+            if sum * 2 >= total {
+                median = i + 1;
+                break;
+            }
+        }
+
+        // Both halves must contain at least one color
+        let median = median.min(self.colors.len() - 1);
+        let second = self.colors.split_off(median);
+
+        (self, ColorBox::new(second))
+    }
+
+    /// The average color of all pixels in the box.
+    fn average(&self) -> [u8; 4] {
+        let mut sums = [0u64; 4];
+        let mut total = 0u64;
+        for (color, count) in self.colors.iter() {
+            let count = u64::from(*count);
+            for (sum, &value) in sums.iter_mut().zip(color.iter()) {
+                *sum += u64::from(value) * count;
+            }
+            total += count;
+        }
+
+        let mut average = [0; 4];
+        for (average, sum) in average.iter_mut().zip(sums.iter()) {
+            *average = ((sum + total / 2) / total.max(1)) as u8;
+        }
+
+        average
+    }
+}
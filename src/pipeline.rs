@@ -0,0 +1,126 @@
+/// How a render pipeline combines its output with the render target.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Blending {
+    /// The output replaces the render target, and its alpha channel is ignored.
+    Replace,
+    /// Blend with the alpha channel.
+    Alpha,
+    /// Blend with the alpha channel, which was premultiplied into the colors.
+    PremultipliedAlpha,
+    /// Mix the output into the render target, which keeps the fraction given by the blend color.
+    MixByBlendColor,
+    /// Multiply the render target by the blend color. The output is ignored.
+    MultiplyByBlendColor,
+}
+
+impl Blending {
+    /// The blend state for the color and the alpha channel.
+    fn descriptors(self) -> (wgpu::BlendDescriptor, wgpu::BlendDescriptor) {
+        let blend = |src_factor, dst_factor| wgpu::BlendDescriptor {
+            src_factor,
+            dst_factor,
+            operation: wgpu::BlendOperation::Add,
+        };
+        let premultiplied = blend(wgpu::BlendFactor::One, wgpu::BlendFactor::OneMinusSrcAlpha);
+
+        match self {
+            Blending::Replace => (
+                wgpu::BlendDescriptor::REPLACE,
+                wgpu::BlendDescriptor::REPLACE,
+            ),
+            Blending::Alpha => (
+                blend(
+                    wgpu::BlendFactor::SrcAlpha,
+                    wgpu::BlendFactor::OneMinusSrcAlpha,
+                ),
+                premultiplied,
+            ),
+            Blending::PremultipliedAlpha => (premultiplied.clone(), premultiplied),
+            Blending::MixByBlendColor => {
+                let mix = blend(
+                    wgpu::BlendFactor::OneMinusBlendColor,
+                    wgpu::BlendFactor::BlendColor,
+                );
+                (mix.clone(), mix)
+            }
+            Blending::MultiplyByBlendColor => (
+                blend(wgpu::BlendFactor::Zero, wgpu::BlendFactor::BlendColor),
+                blend(wgpu::BlendFactor::Zero, wgpu::BlendFactor::One),
+            ),
+        }
+    }
+}
+
+/// Create a pipeline which draws two triangles without vertex buffers, like the fullscreen quad
+/// of `shaders/vert.spv`.
+pub(crate) fn create_pipeline(
+    device: &wgpu::Device,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    vs_module: &wgpu::ShaderModule,
+    fs_module: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    blending: Blending,
+) -> wgpu::RenderPipeline {
+    create_pipeline_with_vertices(
+        device,
+        bind_group_layout,
+        vs_module,
+        fs_module,
+        format,
+        blending,
+        wgpu::PrimitiveTopology::TriangleList,
+        &[],
+    )
+}
+
+/// Create a pipeline which draws primitives from `vertex_buffers`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn create_pipeline_with_vertices(
+    device: &wgpu::Device,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    vs_module: &wgpu::ShaderModule,
+    fs_module: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    blending: Blending,
+    primitive_topology: wgpu::PrimitiveTopology,
+    vertex_buffers: &[wgpu::VertexBufferDescriptor<'_>],
+) -> wgpu::RenderPipeline {
+    let (color_blend, alpha_blend) = blending.descriptors();
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        bind_group_layouts: &[bind_group_layout],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        layout: &pipeline_layout,
+        vertex_stage: wgpu::ProgrammableStageDescriptor {
+            module: vs_module,
+            entry_point: "main",
+        },
+        fragment_stage: Some(wgpu::ProgrammableStageDescriptor {
+            module: fs_module,
+            entry_point: "main",
+        }),
+        rasterization_state: Some(wgpu::RasterizationStateDescriptor {
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: wgpu::CullMode::None,
+            depth_bias: 0,
+            depth_bias_slope_scale: 0.0,
+            depth_bias_clamp: 0.0,
+        }),
+        primitive_topology,
+        color_states: &[wgpu::ColorStateDescriptor {
+            format,
+            color_blend,
+            alpha_blend,
+            write_mask: wgpu::ColorWrite::ALL,
+        }],
+        depth_stencil_state: None,
+        vertex_state: wgpu::VertexStateDescriptor {
+            index_format: wgpu::IndexFormat::Uint16,
+            vertex_buffers,
+        },
+        sample_count: 1,
+        sample_mask: !0,
+        alpha_to_coverage_enabled: false,
+    })
+}
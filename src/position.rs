@@ -0,0 +1,40 @@
+/// How [`Pixels::window_pos_to_pixel_with`] handles positions outside of the pixel buffer, e.g.
+/// in the borders around the scaled image.
+///
+/// [`Pixels::window_pos_to_pixel_with`]: ./struct.Pixels.html#method.window_pos_to_pixel_with
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum EdgeBehavior {
+    /// Map the position to the nearest pixel on the edge of the pixel buffer, as
+    /// [`PixelPosition::Inside`]. Useful for drawing tools, where strokes should reach the edges.
+    ClampToEdge,
+    /// Report the position as [`PixelPosition::OutsideViewport`], e.g. for ignoring clicks in the
+    /// borders.
+    OutsideViewport,
+    /// Report the pixel coordinates as [`PixelPosition::Raw`], which may be negative or beyond the
+    /// size of the pixel buffer. Useful for dragging, where the distance matters.
+    Raw,
+}
+
+/// A window position converted to a pixel position by [`Pixels::window_pos_to_pixel_with`].
+///
+/// [`Pixels::window_pos_to_pixel_with`]: ./struct.Pixels.html#method.window_pos_to_pixel_with
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum PixelPosition {
+    /// A pixel within the pixel buffer.
+    Inside(usize, usize),
+    /// The position is outside of the pixel buffer, with [`EdgeBehavior::OutsideViewport`].
+    OutsideViewport,
+    /// The pixel coordinates of a position outside of the pixel buffer, with
+    /// [`EdgeBehavior::Raw`].
+    Raw(isize, isize),
+}
+
+impl PixelPosition {
+    /// The pixel within the pixel buffer, if the position is inside of it.
+    pub fn inside(self) -> Option<(usize, usize)> {
+        match self {
+            PixelPosition::Inside(x, y) => Some((x, y)),
+            PixelPosition::OutsideViewport | PixelPosition::Raw(..) => None,
+        }
+    }
+}
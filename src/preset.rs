@@ -0,0 +1,103 @@
+use crate::renderers::Overscan;
+
+/// Curated bundles of builder options for common kinds of applications.
+///
+/// Apply a preset with [`PixelsBuilder::preset`], then tweak individual options as needed. Options
+/// set before the preset are overwritten by it, so the preset should be applied first.
+///
+/// [`PixelsBuilder::preset`]: ./struct.PixelsBuilder.html#method.preset
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Preset {
+    /// Emulators care most about input latency and consistent frame pacing.
+    ///
+    /// * Vsync enabled
+    /// * Just-in-time presentation with a 4 ms margin, which applications apply by waiting for
+    ///   [`Pixels::time_until_present_deadline`] before handling input
+    /// * `sRGB` texture format
+    /// * [`ScalingMode::FitAspect`], to fill the window like a TV would
+    ///
+    /// [`Pixels::time_until_present_deadline`]: ./struct.Pixels.html#method.time_until_present_deadline
+    /// [`ScalingMode::FitAspect`]: ./enum.ScalingMode.html#variant.FitAspect
+    Emulator,
+
+    /// Pixel art games want crisp pixels and smooth, tear-free animation.
+    ///
+    /// * Vsync enabled
+    /// * `sRGB` texture format
+    /// * [`ScalingMode::IntegerPerfect`], so every pixel has the same size
+    ///
+    /// [`ScalingMode::IntegerPerfect`]: ./enum.ScalingMode.html#variant.IntegerPerfect
+    PixelArtGame,
+
+    /// Visualizations (plots, simulations, audio visualizers) display computed values and may
+    /// render far faster than the display refresh rate.
+    ///
+    /// * Vsync disabled
+    /// * Linear texture and surface formats, so values are displayed exactly as computed, without
+    ///   any `sRGB` conversion
+    /// * [`ScalingMode::FitAspect`]
+    ///
+    /// [`ScalingMode::FitAspect`]: ./enum.ScalingMode.html#variant.FitAspect
+    Visualization,
+}
+
+/// The pixel aspect ratio and overscan crop of common emulated systems.
+///
+/// Apply a preset with [`PixelsBuilder::aspect_preset`]. It sets
+/// [`PixelsBuilder::pixel_aspect_ratio`] and [`PixelsBuilder::overscan`], which can still be
+/// tweaked afterward.
+///
+/// [`PixelsBuilder::aspect_preset`]: ./struct.PixelsBuilder.html#method.aspect_preset
+/// [`PixelsBuilder::pixel_aspect_ratio`]: ./struct.PixelsBuilder.html#method.pixel_aspect_ratio
+/// [`PixelsBuilder::overscan`]: ./struct.PixelsBuilder.html#method.overscan
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum AspectPreset {
+    /// The NES and Famicom on an NTSC TV, for a 256x240 pixel buffer.
+    ///
+    /// * 8:7 pixel aspect ratio
+    /// * The top and bottom 8 rows are cropped, which most TVs never showed and many games fill
+    ///   with garbage
+    Nes,
+
+    /// The Super NES and Super Famicom on an NTSC TV, for a 256x224 pixel buffer.
+    ///
+    /// * 8:7 pixel aspect ratio
+    /// * No overscan crop
+    Snes,
+
+    /// The original Game Boy and Game Boy Color, for a 160x144 pixel buffer.
+    ///
+    /// * Square pixels
+    /// * No overscan crop
+    GameBoy,
+
+    /// Any system on a 4:3 CRT, for a pixel buffer of any size.
+    ///
+    /// * The pixel aspect ratio which stretches the entire pixel buffer to 4:3
+    /// * No overscan crop
+    Crt4x3,
+}
+
+impl AspectPreset {
+    /// The overscan crop for this preset.
+    pub(crate) fn overscan(self) -> Overscan {
+        match self {
+            AspectPreset::Nes => Overscan::new(0, 8, 0, 8),
+            AspectPreset::Snes | AspectPreset::GameBoy | AspectPreset::Crt4x3 => Overscan::NONE,
+        }
+    }
+
+    /// The pixel aspect ratio for this preset, on a pixel buffer with `visible_size` pixels left
+    /// after cropping.
+    pub(crate) fn pixel_aspect_ratio(self, visible_size: (u32, u32)) -> f64 {
+        match self {
+            AspectPreset::Nes | AspectPreset::Snes => 8.0 / 7.0,
+            AspectPreset::GameBoy => 1.0,
+            AspectPreset::Crt4x3 => {
+                let (width, height) = visible_size;
+
+                4.0 / 3.0 * f64::from(height) / f64::from(width)
+            }
+        }
+    }
+}
@@ -0,0 +1,40 @@
+/// A hint for how a pixel buffer should share the GPU with other work.
+///
+/// `wgpu` does not expose queue or device priorities, so the hint is mapped to the choices that
+/// are available: the adapter power preference and the present mode. Set the hint with
+/// [`PixelsBuilder::priority`].
+///
+/// [`PixelsBuilder::priority`]: ./struct.PixelsBuilder.html#method.priority
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Priority {
+    /// The main view of an application, which should render as fast as possible.
+    ///
+    /// * Requests a high performance adapter
+    Realtime,
+
+    /// No preference. This is the default.
+    Normal,
+
+    /// A secondary view, like a preview window, which should not compete with the main view.
+    ///
+    /// * Requests a low power adapter
+    /// * Vsync is always enabled, so the view never renders faster than the display
+    Background,
+}
+
+impl Default for Priority {
+    fn default() -> Priority {
+        Priority::Normal
+    }
+}
+
+impl Priority {
+    /// The power preference for this priority, or `None` to use the default.
+    pub(crate) fn power_preference(self) -> Option<wgpu::PowerPreference> {
+        match self {
+            Priority::Realtime => Some(wgpu::PowerPreference::HighPerformance),
+            Priority::Normal => None,
+            Priority::Background => Some(wgpu::PowerPreference::LowPower),
+        }
+    }
+}
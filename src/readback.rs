@@ -0,0 +1,251 @@
+use futures_util::task::noop_waker_ref;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::Error;
+
+/// `bytes_per_row` must be a multiple of this value when copying textures to buffers.
+const COPY_BYTES_PER_ROW_ALIGNMENT: u32 = 256;
+
+type MapFuture =
+    Pin<Box<dyn Future<Output = Result<wgpu::BufferReadMapping, wgpu::BufferAsyncErr>> + Send>>;
+type ScreenshotCallback = Box<dyn FnOnce(Result<Screenshot, Error>) + Send>;
+
+/// An image of a rendered frame that has been read back from the GPU.
+///
+/// The image data is always tightly packed `RGBA` with 8 bits per channel, regardless of the
+/// surface texture format.
+#[derive(Clone, Debug)]
+pub struct Screenshot {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+/// A buffer which receives a copy of a texture for reading on the CPU.
+#[derive(Debug)]
+pub(crate) struct Readback {
+    buffer: wgpu::Buffer,
+    width: u32,
+    height: u32,
+    bytes_per_pixel: u32,
+    padded_bytes_per_row: u32,
+    bgra: bool,
+}
+
+/// A [`Readback`] that is waiting for the GPU to finish copying.
+pub(crate) struct PendingReadback {
+    readback: Readback,
+    future: MapFuture,
+}
+
+/// Screenshots that have been requested, and those which are being read back from the GPU.
+#[derive(Default)]
+pub(crate) struct Screenshots {
+    requested: Vec<ScreenshotCallback>,
+    pending: Vec<(PendingReadback, Vec<ScreenshotCallback>)>,
+}
+
+impl Screenshot {
+    /// The width of the image in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of the image in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The `RGBA` image data.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Take ownership of the `RGBA` image data.
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+impl Readback {
+    /// Create a readback buffer large enough to hold a texture with the given dimensions.
+    ///
+    /// When `bgra` is `true`, the red and blue channels are swapped when reading the buffer.
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        bytes_per_pixel: u32,
+        bgra: bool,
+    ) -> Readback {
+        let unpadded_bytes_per_row = width * bytes_per_pixel;
+        let padding = (COPY_BYTES_PER_ROW_ALIGNMENT
+            - unpadded_bytes_per_row % COPY_BYTES_PER_ROW_ALIGNMENT)
+            % COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = unpadded_bytes_per_row + padding;
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("pixels_readback_buffer"),
+            size: u64::from(padded_bytes_per_row * height),
+            usage: wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST,
+        });
+
+        Readback {
+            buffer,
+            width,
+            height,
+            bytes_per_pixel,
+            padded_bytes_per_row,
+            bgra,
+        }
+    }
+
+    /// Encode a copy from the first mip level of `texture` into the readback buffer.
+    pub(crate) fn copy_from_texture(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+    ) {
+        encoder.copy_texture_to_buffer(
+            wgpu::TextureCopyView {
+                texture,
+                mip_level: 0,
+                array_layer: 0,
+                origin: wgpu::Origin3d { x: 0, y: 0, z: 0 },
+            },
+            wgpu::BufferCopyView {
+                buffer: &self.buffer,
+                offset: 0,
+                bytes_per_row: self.padded_bytes_per_row,
+                rows_per_image: self.height,
+            },
+            wgpu::Extent3d {
+                width: self.width,
+                height: self.height,
+                depth: 1,
+            },
+        );
+    }
+
+    /// Begin mapping the buffer. This must be called after the copy has been submitted.
+    pub(crate) fn map(self) -> PendingReadback {
+        let size = u64::from(self.padded_bytes_per_row * self.height);
+        let future = Box::pin(self.buffer.map_read(0, size));
+
+        PendingReadback {
+            readback: self,
+            future,
+        }
+    }
+
+    /// Remove the row padding from mapped data, and convert it to `RGBA` if necessary.
+    fn unpad(&self, mapped: &[u8]) -> Vec<u8> {
+        let bytes_per_row = (self.width * self.bytes_per_pixel) as usize;
+        let mut data = Vec::with_capacity(bytes_per_row * self.height as usize);
+        for row in mapped.chunks(self.padded_bytes_per_row as usize) {
+            data.extend_from_slice(&row[..bytes_per_row]);
+        }
+
+        if self.bgra {
+            for pixel in data.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+
+        data
+    }
+
+    fn finish_mapping(
+        &self,
+        result: Result<wgpu::BufferReadMapping, wgpu::BufferAsyncErr>,
+    ) -> Result<Vec<u8>, Error> {
+        result
+            .map(|mapping| self.unpad(mapping.as_slice()))
+            .map_err(|_| Error::ReadbackFailed)
+    }
+}
+
+impl PendingReadback {
+    /// Check whether the mapping has completed without blocking.
+    ///
+    /// The device must be polled for the mapping to make progress.
+    pub(crate) fn poll(&mut self) -> Option<Result<Vec<u8>, Error>> {
+        let mut context = Context::from_waker(noop_waker_ref());
+
+        match self.future.as_mut().poll(&mut context) {
+            Poll::Ready(result) => Some(self.readback.finish_mapping(result)),
+            Poll::Pending => None,
+        }
+    }
+
+    /// Block until the mapping has completed.
+    pub(crate) fn wait(mut self, device: &wgpu::Device) -> Result<Vec<u8>, Error> {
+        loop {
+            device.poll(wgpu::Maintain::Wait);
+            if let Some(result) = self.poll() {
+                return result;
+            }
+        }
+    }
+
+    fn size(&self) -> (u32, u32) {
+        (self.readback.width, self.readback.height)
+    }
+}
+
+impl Screenshots {
+    /// Add a callback to be invoked with the next rendered frame.
+    pub(crate) fn request(&mut self, callback: ScreenshotCallback) {
+        self.requested.push(callback);
+    }
+
+    /// Returns `true` when the next rendered frame needs to be captured.
+    pub(crate) fn is_requested(&self) -> bool {
+        !self.requested.is_empty()
+    }
+
+    /// Begin reading back a captured frame for all outstanding requests.
+    pub(crate) fn submit(&mut self, readback: Readback) {
+        let callbacks = self.requested.drain(..).collect();
+        self.pending.push((readback.map(), callbacks));
+    }
+
+    /// Invoke the callbacks for all screenshots which have finished reading back.
+    pub(crate) fn poll(&mut self) {
+        let mut i = 0;
+        while i < self.pending.len() {
+            let result = match self.pending[i].0.poll() {
+                Some(result) => result,
+                None => {
+                    i += 1;
+                    continue;
+                }
+            };
+
+            let (pending, callbacks) = self.pending.remove(i);
+            let (width, height) = pending.size();
+            for callback in callbacks {
+                callback(match &result {
+                    Ok(data) => Ok(Screenshot {
+                        width,
+                        height,
+                        data: data.clone(),
+                    }),
+                    Err(_) => Err(Error::ReadbackFailed),
+                });
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Screenshots {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Screenshots")
+            .field("requested", &self.requested.len())
+            .field("pending", &self.pending.len())
+            .finish()
+    }
+}
@@ -1,17 +1,265 @@
-use std::cell::RefCell;
 use std::fmt;
-use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use wgpu::{Extent3d, TextureView};
 
-/// A reference-counted [`wgpu::Device`]
-pub type Device = Rc<wgpu::Device>;
+use crate::{Pixels, Rect};
 
-/// A reference-counted [`wgpu::Queue`] (with interior mutability)
-pub type Queue = Rc<RefCell<wgpu::Queue>>;
+/// A reference-counted [`wgpu::Device`], which can be shared across threads
+pub type Device = Arc<wgpu::Device>;
+
+/// A reference-counted [`wgpu::Queue`] (with interior mutability), which can be shared across
+/// threads
+pub type Queue = Arc<Mutex<wgpu::Queue>>;
 
 /// The boxed render pass type for dynamic dispatch
 pub type BoxedRenderPass = Box<dyn RenderPass>;
 
+/// A reference-counted uniform buffer holding the per-frame user data, which can be shared by
+/// all render passes.
+///
+/// See [`RenderPassOptions::user_data`].
+pub type UserDataBuffer = Arc<wgpu::Buffer>;
+
+/// Options for adding a render pass with [`PixelsBuilder::add_render_pass_with`] or
+/// [`Pixels::add_render_pass_with`].
+///
+/// The options can be combined freely. Use struct update syntax to change only some of them:
+///
+/// ```
+/// use pixels::RenderPassOptions;
+///
+/// let options = RenderPassOptions {
+///     label: Some("trails".to_string()),
+///     previous_frame: true,
+///     ..RenderPassOptions::default()
+/// };
+/// ```
+///
+/// [`PixelsBuilder::add_render_pass_with`]: ./struct.PixelsBuilder.html#method.add_render_pass_with
+/// [`Pixels::add_render_pass_with`]: ./struct.Pixels.html#method.add_render_pass_with
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenderPassOptions {
+    /// The debug label for the GPU commands recorded by the render pass, which shows up in frame
+    /// captures from graphics debuggers like RenderDoc and Xcode.
+    ///
+    /// When `None`, render passes are labeled `pixels_render_pass_N` and background passes are
+    /// labeled `pixels_background_pass_N`, where `N` is unique for each pixel buffer. The built-in
+    /// scaling pass is labeled `pixels_scaling_renderer`.
+    pub label: Option<String>,
+    /// The sampler configuration passed to the factory in [`PassInputs::sampler`].
+    pub sampler: InputSampler,
+    /// With [`wgpu::LoadOp::Clear`], the render target is cleared to the clear color before the
+    /// pass is executed, so it starts from an empty frame. With [`wgpu::LoadOp::Load`], the
+    /// default, the pass draws over the output of the passes before it.
+    pub load_op: wgpu::LoadOp,
+    /// Pass a copy of the pixel buffer texture, as it was when the previous frame was rendered,
+    /// to the factory in [`PassInputs::previous_frame`].
+    ///
+    /// The copy has the same size and format as the input texture, and it is updated by `pixels`
+    /// after all render passes have executed. This enables feedback and trail effects, or
+    /// temporal anti-aliasing, without managing a copy pass. The copy is only made when at least
+    /// one render pass requests it.
+    pub previous_frame: bool,
+    /// Pass the per-frame user data to the factory in [`PassInputs::user_data`].
+    ///
+    /// The application fills the buffer with [`Pixels::render_with_data`], so game state like the
+    /// player position or the amount of screen shake can drive shader effects without each
+    /// render pass uploading its own copy. [`bindings::USER_DATA`] is the suggested binding. The
+    /// buffer is only created when at least one render pass requests it. Its size is set with
+    /// [`PixelsBuilder::user_data_size`], and it holds zeros until the first upload.
+    ///
+    /// [`Pixels::render_with_data`]: ./struct.Pixels.html#method.render_with_data
+    /// [`bindings::USER_DATA`]: ./bindings/constant.USER_DATA.html
+    /// [`PixelsBuilder::user_data_size`]: ./struct.PixelsBuilder.html#method.user_data_size
+    pub user_data: bool,
+    /// Draw underneath the pixel buffer, like a background image or a decorative bezel.
+    ///
+    /// Background passes are executed before the built-in scaling pass, in the order they were
+    /// added. The frame is cleared to the clear color before the first background pass, and the
+    /// scaling pass preserves what they drew around the pixel buffer.
+    pub background: bool,
+}
+
+impl Default for RenderPassOptions {
+    fn default() -> RenderPassOptions {
+        RenderPassOptions {
+            label: None,
+            sampler: InputSampler::default(),
+            load_op: wgpu::LoadOp::Load,
+            previous_frame: false,
+            user_data: false,
+            background: false,
+        }
+    }
+}
+
+/// Everything a render pass factory needs to create its render pass.
+///
+/// See [`PixelsBuilder::add_render_pass_with`].
+///
+/// [`PixelsBuilder::add_render_pass_with`]: ./struct.PixelsBuilder.html#method.add_render_pass_with
+#[derive(Debug)]
+pub struct PassInputs<'a> {
+    /// The texture input for the render pass. You will probably want to create a binding for it
+    /// so your shaders can sample from it.
+    pub texture: &'a TextureView,
+    /// The size of the input texture.
+    pub texture_size: &'a Extent3d,
+    /// The texture format of the surface, which is the format of every render target. Render
+    /// pipelines must target this format.
+    pub surface_format: wgpu::TextureFormat,
+    /// The sampler configuration requested with [`RenderPassOptions::sampler`]. Create the
+    /// sampler with [`InputSampler::create`].
+    pub sampler: InputSampler,
+    /// A copy of the input texture from the previous frame, when requested with
+    /// [`RenderPassOptions::previous_frame`].
+    pub previous_frame: Option<&'a TextureView>,
+    /// The per-frame user data, when requested with [`RenderPassOptions::user_data`].
+    pub user_data: Option<&'a UserDataBuffer>,
+}
+
+/// Addressing and filtering for the sampler that a render pass uses to read its input texture.
+///
+/// The default clamps to the edge of the texture and uses nearest-neighbor filtering, which
+/// preserves pixel edges. Wrap-around effects like an infinite starfield need
+/// [`wgpu::AddressMode::Repeat`].
+///
+/// See [`RenderPassOptions::sampler`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct InputSampler {
+    /// How texture coordinates outside of `0.0..=1.0` are handled.
+    pub address_mode: wgpu::AddressMode,
+    /// How the texture is filtered when it is magnified or minified.
+    pub filter: wgpu::FilterMode,
+}
+
+impl InputSampler {
+    /// Create a sampler configuration.
+    pub const fn new(address_mode: wgpu::AddressMode, filter: wgpu::FilterMode) -> InputSampler {
+        InputSampler {
+            address_mode,
+            filter,
+        }
+    }
+
+    /// Create the [`wgpu::Sampler`] described by this configuration.
+    pub fn create(&self, device: &wgpu::Device) -> wgpu::Sampler {
+        device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: self.address_mode,
+            address_mode_v: self.address_mode,
+            address_mode_w: self.address_mode,
+            mag_filter: self.filter,
+            min_filter: self.filter,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            lod_min_clamp: 0.0,
+            lod_max_clamp: 1.0,
+            compare: wgpu::CompareFunction::Always,
+        })
+    }
+}
+
+impl Default for InputSampler {
+    fn default() -> InputSampler {
+        InputSampler::new(wgpu::AddressMode::ClampToEdge, wgpu::FilterMode::Nearest)
+    }
+}
+
+/// Describes a change to the size of the surface.
+///
+/// See [`RenderPass::surface_resized`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SurfaceResize {
+    /// Surface size before the resize, in physical pixel units.
+    pub old_size: (u32, u32),
+    /// Surface size after the resize, in physical pixel units.
+    pub new_size: (u32, u32),
+    /// The factor by which the pixel buffer is scaled on the surface, horizontally and vertically.
+    pub scale: (f32, f32),
+    /// The rectangle on the surface where the pixel buffer is drawn.
+    pub destination: Rect,
+}
+
+/// The frame which is about to be rendered, for render passes which animate.
+///
+/// See [`RenderPass::update`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct FrameContext {
+    /// The number of frames rendered before this one.
+    pub frame_index: u64,
+    /// The time since the pixel buffer was built. See [`Pixels::time`].
+    ///
+    /// [`Pixels::time`]: ./struct.Pixels.html#method.time
+    pub time: Duration,
+    /// The time since the previous frame, which is zero for the first frame.
+    pub delta_time: Duration,
+    /// A seed for render passes which generate noise. See [`Pixels::seed`].
+    ///
+    /// [`Pixels::seed`]: ./struct.Pixels.html#method.seed
+    pub seed: u64,
+}
+
+/// Everything needed to draw over the surface with [`Pixels::render_with`].
+///
+/// [`Pixels::render_with`]: ./struct.Pixels.html#method.render_with
+#[derive(Debug)]
+pub struct PixelsContext<'a> {
+    /// The device used by the pixel buffer.
+    pub device: &'a Device,
+    /// The queue used by the pixel buffer.
+    pub queue: &'a Queue,
+    /// The pixel buffer texture.
+    pub texture: &'a wgpu::Texture,
+    /// The size of the pixel buffer texture.
+    pub texture_extent: Extent3d,
+    /// The size of the surface, in physical pixel units.
+    pub surface_size: (u32, u32),
+    /// The factor by which the pixel buffer is scaled on the surface, horizontally and vertically.
+    pub scale: (f32, f32),
+    /// The rectangle on the surface where the pixel buffer is drawn.
+    pub destination: Rect,
+}
+
+/// The render passes of a pixel buffer, executed by the application with
+/// [`Pixels::render_with_passes`].
+///
+/// [`Pixels::render_with_passes`]: ./struct.Pixels.html#method.render_with_passes
+pub struct RenderPasses<'a> {
+    pub(crate) pixels: &'a Pixels,
+}
+
+impl RenderPasses<'_> {
+    /// Execute all render passes, including the built-in scaling pass and the layers, in the
+    /// order they are executed by [`Pixels::render`].
+    ///
+    /// The commands are recorded into `encoder`, so commands recorded before and after this call
+    /// are executed before and after the render passes, in the same submission.
+    ///
+    /// [`Pixels::render`]: ./struct.Pixels.html#method.render
+    pub fn render(&mut self, encoder: &mut wgpu::CommandEncoder, render_target: &TextureView) {
+        let pixels = self.pixels;
+        for (i, renderer) in pixels.renderers.iter().enumerate() {
+            renderer.render(encoder, render_target, pixels.clear_color);
+            if i == pixels.scaling_renderer {
+                pixels.render_layers(encoder, render_target, None);
+            }
+        }
+    }
+
+    /// The device, queue, and scaling of the pixel buffer, for recording other commands.
+    pub fn context(&self) -> PixelsContext<'_> {
+        self.pixels.context()
+    }
+}
+
+impl fmt::Debug for RenderPasses<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RenderPasses")
+            .field("renderers", &self.pixels.renderers)
+            .finish()
+    }
+}
+
 /// Objects that implement this trait can be added to [`Pixels`] as a render pass.
 ///
 /// [`Pixels`] always has at least one render pass; a scaling pass that uses a nearest-neighbor
@@ -27,8 +275,11 @@ pub type BoxedRenderPass = Box<dyn RenderPass>;
 /// The render pass will also receive a reference to another [`wgpu::TextureView`] when the pass is
 /// executed. This texture view is the `render_target`.
 ///
+/// Render passes must be `Send`, because they are owned by the pixel buffer, which can be moved to
+/// a render thread.
+///
 /// [`Pixels`]: ./struct.Pixels.html
-pub trait RenderPass {
+pub trait RenderPass: Send {
     /// Called when it is time to execute this render pass. Use the `encoder` to encode all
     /// commands related to this render pass. The result must be stored to the `render_target`.
     ///
@@ -38,6 +289,21 @@ pub trait RenderPass {
     /// * `texels` - The byte slice passed to `Pixels::render`
     fn render(&self, encoder: &mut wgpu::CommandEncoder, render_target: &TextureView);
 
+    /// Called once per frame, before any render pass is executed, with the frame index and time.
+    ///
+    /// Render passes which animate, like rolling scanlines or film grain, should update their
+    /// uniforms here, e.g. with a buffer copy encoded into `encoder`. The time stands still in
+    /// deterministic mode. Render passes which are disabled or skipped in this frame are not
+    /// updated.
+    ///
+    /// The default implementation is a no-op.
+    ///
+    /// # Arguments
+    /// * `encoder` - Command encoder which is submitted before any render pass is executed
+    /// * `context` - The frame index and time
+    #[allow(unused_variables)]
+    fn update(&mut self, encoder: &mut wgpu::CommandEncoder, context: &FrameContext) {}
+
     /// This method will be called when the input [`wgpu::TextureView`] needs to be rebinded.
     ///
     /// A [`wgpu::TextureView`] is provided to the `RenderPass` factory as an input texture with
@@ -53,6 +319,23 @@ pub trait RenderPass {
     /// [`SurfaceTexture`]: ./struct.SurfaceTexture.html
     fn update_bindings(&mut self, input_texture: &TextureView, input_texture_size: &Extent3d);
 
+    /// Called when the pixel buffer is resized with [`Pixels::resize_buffer`], which recreates
+    /// the input texture and the copy of the previous frame.
+    ///
+    /// Render passes which requested [`RenderPassOptions::previous_frame`] should override this
+    /// to bind the new [`PassInputs::previous_frame`] along with the new input texture.
+    ///
+    /// The default implementation calls [`RenderPass::update_bindings`] with the new input
+    /// texture.
+    ///
+    /// # Arguments
+    /// * `inputs` - The same inputs that the factory received, for the new size
+    ///
+    /// [`Pixels::resize_buffer`]: ./struct.Pixels.html#method.resize_buffer
+    fn update_inputs(&mut self, inputs: &PassInputs<'_>) {
+        self.update_bindings(inputs.texture, inputs.texture_size);
+    }
+
     /// When the window is resized, this method will be called, allowing the render pass to
     /// customize itself to the display size.
     ///
@@ -65,6 +348,44 @@ pub trait RenderPass {
     #[allow(unused_variables)]
     fn resize(&mut self, encoder: &mut wgpu::CommandEncoder, width: u32, height: u32) {}
 
+    /// Called every time the surface is resized, with the old and new sizes and the updated
+    /// scaling information.
+    ///
+    /// This is called for every call to [`Pixels::resize`], even when the physical size did not
+    /// change (for instance, after a DPI change). Render passes that keep size-dependent resources,
+    /// like the mip chain for a bloom effect, should rebuild them here.
+    ///
+    /// The default implementation calls [`RenderPass::resize`] with the new size.
+    ///
+    /// # Arguments
+    /// * `encoder` - Command encoder for the render pass
+    /// * `resize` - The old and new surface sizes, and the updated scaling
+    ///
+    /// [`Pixels::resize`]: ./struct.Pixels.html#method.resize
+    fn surface_resized(&mut self, encoder: &mut wgpu::CommandEncoder, resize: &SurfaceResize) {
+        let (width, height) = resize.new_size;
+
+        self.resize(encoder, width, height);
+    }
+
+    /// Map a position on the render target back to where the undistorted image would show the
+    /// same pixel.
+    ///
+    /// Render passes that distort the image, like CRT curvature or barrel distortion, should
+    /// override this with the inverse of their distortion. [`Pixels::window_pos_to_pixel`]
+    /// consults every render pass, in reverse order, so that clicks land on the pixel the user
+    /// visually pointed at.
+    ///
+    /// The default implementation returns `position` unchanged.
+    ///
+    /// # Arguments
+    /// * `position` - A position on the render target in physical pixel units
+    ///
+    /// [`Pixels::window_pos_to_pixel`]: ./struct.Pixels.html#method.window_pos_to_pixel
+    fn unwarp_position(&self, position: (f32, f32)) -> (f32, f32) {
+        position
+    }
+
     /// This function implements [`Debug`](fmt::Debug) for trait objects.
     ///
     /// You are encouraged to override the default impl to provide better debug messages.
@@ -78,3 +399,72 @@ impl fmt::Debug for dyn RenderPass + 'static {
         self.debug(f)
     }
 }
+
+/// A render pass and the debug label for its GPU commands.
+///
+/// The label is attached to the command encoder for the pass, so it shows up in graphics
+/// debuggers like RenderDoc and Xcode.
+#[derive(Debug)]
+pub(crate) struct LabeledRenderPass {
+    pub(crate) label: String,
+    pub(crate) load_op: wgpu::LoadOp,
+    // The sampler configuration handed to the render pass with its inputs
+    pub(crate) sampler: InputSampler,
+    pub(crate) render_pass: BoxedRenderPass,
+    // Disabled passes are skipped, but still clear the render target
+    pub(crate) enabled: bool,
+    // Evaluated once per frame, which skips the pass like a disabled pass when it returns `false`
+    pub(crate) predicate: Option<PassPredicate>,
+    pub(crate) skipped: bool,
+}
+
+/// A closure which decides whether a render pass is executed in the next frame.
+pub(crate) struct PassPredicate(pub(crate) Box<dyn FnMut() -> bool + Send>);
+
+impl fmt::Debug for PassPredicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PassPredicate").finish()
+    }
+}
+
+impl LabeledRenderPass {
+    /// Evaluate the predicate for the next frame.
+    pub(crate) fn update_predicate(&mut self) {
+        self.skipped = match &mut self.predicate {
+            Some(PassPredicate(predicate)) => !predicate(),
+            None => false,
+        };
+    }
+
+    /// Returns `true` when the pass is executed in the next frame.
+    pub(crate) fn is_active(&self) -> bool {
+        self.enabled && !self.skipped
+    }
+
+    /// Execute the render pass, clearing the render target to `clear_color` first when its load
+    /// op is [`wgpu::LoadOp::Clear`]. Only the clear happens when the pass is disabled or skipped
+    /// by its predicate.
+    pub(crate) fn render(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        render_target: &TextureView,
+        clear_color: wgpu::Color,
+    ) {
+        if self.load_op == wgpu::LoadOp::Clear {
+            encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                    attachment: render_target,
+                    resolve_target: None,
+                    load_op: wgpu::LoadOp::Clear,
+                    store_op: wgpu::StoreOp::Store,
+                    clear_color,
+                }],
+                depth_stencil_attachment: None,
+            });
+        }
+
+        if self.is_active() {
+            self.render_pass.render(encoder, render_target);
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use ultraviolet::Mat4;
+use wgpu::TextureView;
+
+use crate::bindings;
+use crate::include_spv;
+use crate::is_bgra;
+use crate::pipeline::{create_pipeline, Blending};
+use crate::readback::Readback;
+use crate::render_pass::InputSampler;
+
+/// The most recently rendered frame, in a texture with the size and format of the surface.
+///
+/// Render passes draw into this texture, instead of the swap chain texture, while the frame must be
+/// read back or presented again for inserted frames, so the render passes are not executed a
+/// second time. Swap chain textures can only be drawn into, so window surfaces then receive a copy
+/// of the frame with a draw. Offscreen surfaces always draw into this texture.
+#[derive(Debug)]
+pub(crate) struct RenderedFrame {
+    texture: wgpu::Texture,
+    view: TextureView,
+    size: (u32, u32),
+    format: wgpu::TextureFormat,
+    bind_group: wgpu::BindGroup,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl RenderedFrame {
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+    ) -> RenderedFrame {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("pixels_rendered_frame_texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth: 1,
+            },
+            array_layer_count: 1,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT
+                | wgpu::TextureUsage::SAMPLED
+                | wgpu::TextureUsage::COPY_SRC
+                | wgpu::TextureUsage::COPY_DST,
+        });
+        let view = texture.create_default_view();
+
+        let vs_module = device.create_shader_module(include_spv!("../shaders/vert.spv"));
+        let fs_module = device.create_shader_module(include_spv!("../shaders/frag.spv"));
+
+        // The copy has the same size as the frame, so nearest neighbor sampling is exact
+        let sampler = InputSampler::default().create(device);
+
+        // Cover the entire render target, flipped so that the top row of the frame stays on top
+        #[rustfmt::skip]
+        let transform = Mat4::from([
+            1.0,  0.0, 0.0, 0.0,
+            0.0, -1.0, 0.0, 0.0,
+            0.0,  0.0, 1.0, 0.0,
+            0.0,  0.0, 0.0, 1.0,
+        ]);
+        let transform_buffer =
+            device.create_buffer_with_data(transform.as_byte_slice(), wgpu::BufferUsage::UNIFORM);
+
+        // Create bind group
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("pixels_rendered_frame_bind_group_layout"),
+            bindings: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: bindings::TEXTURE,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::SampledTexture {
+                        component_type: wgpu::TextureComponentType::Float,
+                        multisampled: false,
+                        dimension: wgpu::TextureViewDimension::D2,
+                    },
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: bindings::SAMPLER,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler { comparison: false },
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: bindings::TRANSFORM,
+                    visibility: wgpu::ShaderStage::VERTEX,
+                    ty: wgpu::BindingType::UniformBuffer { dynamic: false },
+                },
+            ],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("pixels_rendered_frame_bind_group"),
+            layout: &bind_group_layout,
+            bindings: &[
+                wgpu::Binding {
+                    binding: bindings::TEXTURE,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::Binding {
+                    binding: bindings::SAMPLER,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+                wgpu::Binding {
+                    binding: bindings::TRANSFORM,
+                    resource: wgpu::BindingResource::Buffer {
+                        buffer: &transform_buffer,
+                        range: 0..bindings::TRANSFORM_SIZE,
+                    },
+                },
+            ],
+        });
+
+        // Create pipeline
+        let pipeline = create_pipeline(
+            device,
+            &bind_group_layout,
+            &vs_module,
+            &fs_module,
+            format,
+            Blending::Replace,
+        );
+
+        RenderedFrame {
+            texture,
+            view,
+            size: (width, height),
+            format,
+            bind_group,
+            pipeline,
+        }
+    }
+
+    /// The texture which render passes draw into.
+    pub(crate) fn texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
+    /// A view of the texture which render passes draw into.
+    pub(crate) fn view(&self) -> &TextureView {
+        &self.view
+    }
+
+    /// The size of the frame, which is the size of the surface it was created for.
+    pub(crate) fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// Encode a draw which copies the frame over the entire `render_target`.
+    ///
+    /// The render target must have the same size and format as the frame, like the swap chain
+    /// texture of the surface.
+    pub(crate) fn copy_to(&self, encoder: &mut wgpu::CommandEncoder, render_target: &TextureView) {
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                attachment: render_target,
+                resolve_target: None,
+                load_op: wgpu::LoadOp::Clear,
+                store_op: wgpu::StoreOp::Store,
+                clear_color: wgpu::Color::BLACK,
+            }],
+            depth_stencil_attachment: None,
+        });
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, &self.bind_group, &[]);
+        rpass.draw(0..6, 0..1);
+    }
+
+    /// Encode a copy of the frame into a new readback buffer.
+    pub(crate) fn readback(
+        &self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+    ) -> Readback {
+        let (width, height) = self.size;
+        let readback = Readback::new(device, width, height, 4, is_bgra(self.format));
+        readback.copy_from_texture(encoder, &self.texture);
+
+        readback
+    }
+}
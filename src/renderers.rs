@@ -1,65 +1,157 @@
 use std::fmt;
-use std::rc::Rc;
+use std::sync::{Arc, Mutex};
 use ultraviolet::Mat4;
 use wgpu::{self, Extent3d, TextureView};
 
+use crate::bindings;
+use crate::crash::lock;
 use crate::include_spv;
-use crate::render_pass::{BoxedRenderPass, Device, Queue, RenderPass};
+use crate::pipeline::{create_pipeline, Blending};
+use crate::render_pass::{BoxedRenderPass, Device, InputSampler, Queue, RenderPass};
+use crate::swizzle::Swizzle;
+use crate::Rect;
+
+/// Custom SPIR-V shaders for the built-in scaling renderer.
+///
+/// Either shader can be replaced independently; `None` keeps the built-in shader. This makes it
+/// possible to tweak the final blit, e.g. to add a subtle sharpening filter, without duplicating
+/// the whole renderer as a [`RenderPass`].
+///
+/// # Shader interface
+///
+/// Custom shaders must be compatible with the renderer's pipeline. The vertex shader is invoked
+/// for 6 vertices (two triangles covering the quad) without any vertex buffers, and should use
+/// `gl_VertexIndex` like the built-in `shaders/shader.vert`. It passes the texture coordinates to
+/// the fragment shader at `layout(location = 0)`. The fragment shader writes one color to
+/// `layout(location = 0)`.
+///
+/// Both stages use bind group 0 with these bindings, which are also available as constants in the
+/// [`bindings`] module:
+///
+/// | Binding | Type                                            | Contents                      |
+/// |---------|-------------------------------------------------|-------------------------------|
+/// | 0       | `texture2D`                                     | The pixel buffer texture      |
+/// | 1       | `sampler`                                       | A nearest neighbor sampler    |
+/// | 2       | `uniform Locals { mat4 u_Transform; }`          | The scaling transform         |
+/// | 3       | `uniform { mat4 u_Swizzle; vec4 u_Constant; }`  | The [`Swizzle`], see below    |
+///
+/// The swizzle is applied by multiplying the sampled color with `u_Swizzle` and adding
+/// `u_Constant`. Shaders which ignore binding 3 also ignore the configured swizzle.
+///
+/// # Examples
+///
+/// ```no_run
+/// use pixels::{PixelsBuilder, ScalingShaders};
+///
+/// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+/// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+/// # fn load_spirv(_: &str) -> Vec<u32> { Vec::new() }
+/// // Compiled out-of-band with e.g. `glslangValidator -V sharpen.frag -o sharpen.spv`
+/// let sharpen = load_spirv("sharpen.spv");
+///
+/// let pixels = PixelsBuilder::new(320, 240, surface_texture)
+///     .scaling_shaders(ScalingShaders {
+///         vertex: None,
+///         fragment: Some(sharpen),
+///     })
+///     .build()?;
+/// # Ok::<(), pixels::Error>(())
+/// ```
+///
+/// [`bindings`]: ./bindings/index.html
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct ScalingShaders {
+    /// SPIR-V for the vertex shader, replacing `shaders/vert.spv`.
+    pub vertex: Option<Vec<u32>>,
+    /// SPIR-V for the fragment shader, replacing `shaders/frag.spv` (or `shaders/swizzle.spv`).
+    pub fragment: Option<Vec<u32>>,
+}
 
 /// Renderer implements [`RenderPass`].
 #[derive(Debug)]
 pub(crate) struct Renderer {
-    device: Rc<wgpu::Device>,
-    uniform_buffer: wgpu::Buffer,
-    bind_group: wgpu::BindGroup,
+    device: Device,
+    sampler: wgpu::Sampler,
+    // One transform and bind group for each image drawn, e.g. one per eye for side-by-side
+    uniform_buffers: Vec<wgpu::Buffer>,
+    swizzle_buffer: wgpu::Buffer,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_groups: Vec<wgpu::BindGroup>,
     render_pipeline: wgpu::RenderPipeline,
+    format: wgpu::TextureFormat,
+    // Draws over the render target with the alpha channel of the pixel buffer, when enabled
+    blend_pipeline: Option<wgpu::RenderPipeline>,
+    blending: Blending,
+    // The intermediate stage for `ScalingMode::SharpBilinear`
+    prescale: Option<Prescale>,
+    scaling: Scaling,
+    filter: wgpu::FilterMode,
+    // Changes made by `Pixels` after the renderer was created
+    options: Arc<Mutex<ScalingOptions>>,
     width: f32,
     height: f32,
+    screen_width: f32,
+    screen_height: f32,
 }
 
 impl Renderer {
     /// Factory function for generating `RenderPass` trait objects.
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn factory(
         device: Device,
         _queue: Queue,
         texture_view: &TextureView,
         texture_size: &Extent3d,
+        swizzle: Swizzle,
+        shaders: &ScalingShaders,
+        scaling: Scaling,
+        options: Arc<Mutex<ScalingOptions>>,
+        format: wgpu::TextureFormat,
+        blending: Blending,
     ) -> BoxedRenderPass {
-        let vs_module = device.create_shader_module(include_spv!("../shaders/vert.spv"));
-        let fs_module = device.create_shader_module(include_spv!("../shaders/frag.spv"));
+        let vs_module = match &shaders.vertex {
+            Some(spirv) => device.create_shader_module(spirv),
+            None => device.create_shader_module(include_spv!("../shaders/vert.spv")),
+        };
+        let fs_module = match &shaders.fragment {
+            Some(spirv) => device.create_shader_module(spirv),
+            None if swizzle == Swizzle::IDENTITY => {
+                device.create_shader_module(include_spv!("../shaders/frag.spv"))
+            }
+            None => device.create_shader_module(include_spv!("../shaders/swizzle.spv")),
+        };
 
-        // Create a texture sampler with nearest neighbor
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Nearest,
-            min_filter: wgpu::FilterMode::Nearest,
-            mipmap_filter: wgpu::FilterMode::Nearest,
-            lod_min_clamp: 0.0,
-            lod_max_clamp: 1.0,
-            compare: wgpu::CompareFunction::Always,
-        });
+        // Create a texture sampler with the configured filter
+        let filter = lock(&options).filter;
+        let sampler = InputSampler::new(wgpu::AddressMode::ClampToEdge, filter).create(&device);
 
-        // Create uniform buffer
+        // Create uniform buffers
         // TODO: This should also have the width / height of the of the window surface,
         // so that it won't break when the window is created with a different size.
-        let matrix = ScalingMatrix::new(
-            (texture_size.width as f32, texture_size.height as f32),
-            (texture_size.width as f32, texture_size.height as f32),
-        );
-        let transform_bytes = matrix.as_bytes();
-        let uniform_buffer = device.create_buffer_with_data(
-            &transform_bytes,
-            wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
-        );
+        let size = (texture_size.width as f32, texture_size.height as f32);
+        let uniform_buffers: Vec<_> = (0..scaling.eyes())
+            .map(|eye| {
+                let matrix = scaling.eye_matrix(size, size, eye);
+                let transform_bytes = matrix.as_bytes();
+
+                device.create_buffer_with_data(
+                    &transform_bytes,
+                    wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+                )
+            })
+            .collect();
+
+        // The swizzle uniform is only read by the swizzle fragment shader (or a custom shader)
+        let swizzle_bytes = swizzle.to_bytes();
+        let swizzle_buffer =
+            device.create_buffer_with_data(&swizzle_bytes, wgpu::BufferUsage::UNIFORM);
 
         // Create bind group
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: None,
+            label: Some("pixels_scaling_renderer_bind_group_layout"),
             bindings: &[
                 wgpu::BindGroupLayoutEntry {
-                    binding: 0,
+                    binding: bindings::TEXTURE,
                     visibility: wgpu::ShaderStage::FRAGMENT,
                     ty: wgpu::BindingType::SampledTexture {
                         component_type: wgpu::TextureComponentType::Uint,
@@ -68,128 +160,759 @@ impl Renderer {
                     },
                 },
                 wgpu::BindGroupLayoutEntry {
-                    binding: 1,
+                    binding: bindings::SAMPLER,
                     visibility: wgpu::ShaderStage::FRAGMENT,
                     ty: wgpu::BindingType::Sampler { comparison: false },
                 },
                 wgpu::BindGroupLayoutEntry {
-                    binding: 2,
-                    visibility: wgpu::ShaderStage::VERTEX,
+                    binding: bindings::TRANSFORM,
+                    visibility: wgpu::ShaderStage::VERTEX | wgpu::ShaderStage::FRAGMENT,
                     ty: wgpu::BindingType::UniformBuffer { dynamic: false },
                 },
-            ],
-        });
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: None,
-            layout: &bind_group_layout,
-            bindings: &[
-                wgpu::Binding {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(texture_view),
-                },
-                wgpu::Binding {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&sampler),
-                },
-                wgpu::Binding {
-                    binding: 2,
-                    resource: wgpu::BindingResource::Buffer {
-                        buffer: &uniform_buffer,
-                        range: 0..64,
-                    },
+                wgpu::BindGroupLayoutEntry {
+                    binding: bindings::SWIZZLE,
+                    visibility: wgpu::ShaderStage::VERTEX | wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::UniformBuffer { dynamic: false },
                 },
             ],
         });
+        let bind_groups = uniform_buffers
+            .iter()
+            .map(|uniform_buffer| {
+                create_bind_group(
+                    &device,
+                    &bind_group_layout,
+                    texture_view,
+                    &sampler,
+                    uniform_buffer,
+                    &swizzle_buffer,
+                )
+            })
+            .collect();
 
         // Create pipeline
-        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            bind_group_layouts: &[&bind_group_layout],
-        });
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            layout: &pipeline_layout,
-            vertex_stage: wgpu::ProgrammableStageDescriptor {
-                module: &vs_module,
-                entry_point: "main",
-            },
-            fragment_stage: Some(wgpu::ProgrammableStageDescriptor {
-                module: &fs_module,
-                entry_point: "main",
-            }),
-            rasterization_state: Some(wgpu::RasterizationStateDescriptor {
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: wgpu::CullMode::None,
-                depth_bias: 0,
-                depth_bias_slope_scale: 0.0,
-                depth_bias_clamp: 0.0,
-            }),
-            primitive_topology: wgpu::PrimitiveTopology::TriangleList,
-            color_states: &[wgpu::ColorStateDescriptor {
-                format: wgpu::TextureFormat::Bgra8UnormSrgb,
-                color_blend: wgpu::BlendDescriptor::REPLACE,
-                alpha_blend: wgpu::BlendDescriptor::REPLACE,
-                write_mask: wgpu::ColorWrite::ALL,
-            }],
-            depth_stencil_state: None,
-            vertex_state: wgpu::VertexStateDescriptor {
-                index_format: wgpu::IndexFormat::Uint16,
-                vertex_buffers: &[],
-            },
-            sample_count: 1,
-            sample_mask: !0,
-            alpha_to_coverage_enabled: false,
-        });
+        let render_pipeline = create_pipeline(
+            &device,
+            &bind_group_layout,
+            &vs_module,
+            &fs_module,
+            format,
+            Blending::Replace,
+        );
+        // The intermediate stage of sharp bilinear scaling keeps the alpha channel as it is, so
+        // only the final draw blends
+        let blend_pipeline = if blending != Blending::Replace {
+            Some(create_pipeline(
+                &device,
+                &bind_group_layout,
+                &vs_module,
+                &fs_module,
+                format,
+                blending,
+            ))
+        } else {
+            None
+        };
+
+        // Sharp bilinear scaling draws through an integer-scaled intermediate texture
+        let prescale = if scaling.mode == ScalingMode::SharpBilinear {
+            Some(Prescale::new(
+                &device,
+                &bind_group_layout,
+                texture_view,
+                *texture_size,
+                &swizzle_buffer,
+                format,
+                blending,
+            ))
+        } else {
+            None
+        };
+        let bind_groups = match &prescale {
+            Some(prescale) => prescale.create_bind_groups(
+                &device,
+                &bind_group_layout,
+                &uniform_buffers,
+                &swizzle_buffer,
+            ),
+            None => bind_groups,
+        };
 
         Box::new(Renderer {
             device,
-            uniform_buffer,
-            bind_group,
+            sampler,
+            uniform_buffers,
+            swizzle_buffer,
+            bind_group_layout,
+            bind_groups,
             render_pipeline,
-            width: texture_size.width as f32,
-            height: texture_size.height as f32,
+            format,
+            blend_pipeline,
+            blending,
+            prescale,
+            scaling,
+            filter,
+            options,
+            width: size.0,
+            height: size.1,
+            screen_width: size.0,
+            screen_height: size.1,
         })
     }
+
+    /// Apply the scaling mode and filter most recently set on `Pixels`. Only the sampler and the
+    /// intermediate stage are replaced; the transforms are updated by the next resize.
+    fn apply_options(&mut self, input_texture: &TextureView, input_texture_size: &Extent3d) {
+        let options = *lock(&self.options);
+        if options.filter != self.filter {
+            self.sampler = InputSampler::new(wgpu::AddressMode::ClampToEdge, options.filter)
+                .create(&self.device);
+            self.filter = options.filter;
+        }
+
+        self.scaling.mode = options.mode;
+        self.scaling.viewport = options.viewport;
+        self.scaling.offset = options.offset;
+        self.scaling.crop = options.crop;
+        if options.mode != ScalingMode::SharpBilinear {
+            self.prescale = None;
+        } else if self.prescale.is_none() {
+            self.prescale = Some(Prescale::new(
+                &self.device,
+                &self.bind_group_layout,
+                input_texture,
+                *input_texture_size,
+                &self.swizzle_buffer,
+                self.format,
+                self.blending,
+            ));
+        }
+    }
+
+    /// Reallocate the intermediate texture when the integer factor for the current surface size
+    /// has changed, or when `input_texture` is replaced.
+    fn update_prescale(&mut self, input_texture: Option<&TextureView>) {
+        let prescale = match &mut self.prescale {
+            Some(prescale) => prescale,
+            None => return,
+        };
+
+        let matrix = self.scaling.scaling_matrix(
+            (self.width, self.height),
+            (self.screen_width, self.screen_height),
+        );
+        let texture_size = (self.width as u32, self.height as u32);
+        let factor = Prescale::factor(texture_size, matrix.scale);
+        if factor == prescale.factor
+            && texture_size == prescale.texture_size
+            && input_texture.is_none()
+        {
+            return;
+        }
+
+        prescale.reallocate(
+            &self.device,
+            &self.bind_group_layout,
+            input_texture,
+            texture_size,
+            factor,
+            &self.swizzle_buffer,
+        );
+        self.bind_groups = prescale.create_bind_groups(
+            &self.device,
+            &self.bind_group_layout,
+            &self.uniform_buffers,
+            &self.swizzle_buffer,
+        );
+    }
 }
 
 impl RenderPass for Renderer {
     fn render(&self, encoder: &mut wgpu::CommandEncoder, render_target: &TextureView) {
-        // Draw the updated texture to the render target
+        // Scale the pixel buffer by an integer into the intermediate texture first
+        if let Some(prescale) = &self.prescale {
+            prescale.render(encoder, &self.render_pipeline);
+        }
+
+        // Draw the updated texture to the render target. The target is cleared before this pass
+        // unless background passes were drawn first.
         let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
                 attachment: render_target,
                 resolve_target: None,
-                load_op: wgpu::LoadOp::Clear,
+                load_op: wgpu::LoadOp::Load,
                 store_op: wgpu::StoreOp::Store,
                 clear_color: wgpu::Color::BLACK,
             }],
             depth_stencil_attachment: None,
         });
-        rpass.set_pipeline(&self.render_pipeline);
-        rpass.set_bind_group(0, &self.bind_group, &[]);
-        rpass.draw(0..6, 0..1);
+        match (&self.prescale, &self.blend_pipeline) {
+            (Some(prescale), _) => rpass.set_pipeline(&prescale.pipeline),
+            (None, Some(blend_pipeline)) => rpass.set_pipeline(blend_pipeline),
+            (None, None) => rpass.set_pipeline(&self.render_pipeline),
+        }
+        for (eye, bind_group) in self.bind_groups.iter().enumerate() {
+            // Keep each eye in its own half of the render target, and hide the overscan
+            if let Some((x, y, width, height)) = self.scaling.scissor(
+                (self.width, self.height),
+                (self.screen_width, self.screen_height),
+                eye,
+            ) {
+                rpass.set_scissor_rect(x, y, width, height);
+            }
+            rpass.set_bind_group(0, bind_group, &[]);
+            rpass.draw(0..6, 0..1);
+        }
     }
 
     fn resize(&mut self, encoder: &mut wgpu::CommandEncoder, width: u32, height: u32) {
-        let matrix = ScalingMatrix::new((self.width, self.height), (width as f32, height as f32));
-        let transform_bytes = matrix.as_bytes();
+        self.screen_width = width as f32;
+        self.screen_height = height as f32;
+
+        for (eye, uniform_buffer) in self.uniform_buffers.iter().enumerate() {
+            let matrix = self.scaling.eye_matrix(
+                (self.width, self.height),
+                (self.screen_width, self.screen_height),
+                eye,
+            );
+            let transform_bytes = matrix.as_bytes();
 
-        let temp_buf = self
-            .device
-            .create_buffer_with_data(&transform_bytes, wgpu::BufferUsage::COPY_SRC);
-        encoder.copy_buffer_to_buffer(&temp_buf, 0, &self.uniform_buffer, 0, 64);
+            let temp_buf = self
+                .device
+                .create_buffer_with_data(&transform_bytes, wgpu::BufferUsage::COPY_SRC);
+            encoder.copy_buffer_to_buffer(&temp_buf, 0, uniform_buffer, 0, 64);
+        }
+
+        self.update_prescale(None);
     }
 
-    // We don't actually have to rebind the TextureView here.
-    // It's guaranteed that the initial texture never changes.
-    fn update_bindings(&mut self, _input_texture: &TextureView, _input_texture_size: &Extent3d) {}
+    // The texture is only replaced when the pixel buffer is resized or the scaling options change,
+    // and the transform is updated by the `resize` that follows.
+    fn update_bindings(&mut self, input_texture: &TextureView, input_texture_size: &Extent3d) {
+        self.apply_options(input_texture, input_texture_size);
+
+        let device = &self.device;
+        let bind_group_layout = &self.bind_group_layout;
+        let sampler = &self.sampler;
+        let swizzle_buffer = &self.swizzle_buffer;
+        self.bind_groups = self
+            .uniform_buffers
+            .iter()
+            .map(|uniform_buffer| {
+                create_bind_group(
+                    device,
+                    bind_group_layout,
+                    input_texture,
+                    sampler,
+                    uniform_buffer,
+                    swizzle_buffer,
+                )
+            })
+            .collect();
+        self.width = input_texture_size.width as f32;
+        self.height = input_texture_size.height as f32;
+
+        self.update_prescale(Some(input_texture));
+    }
 
     fn debug(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{:?}", self)
     }
 }
 
+fn create_bind_group(
+    device: &wgpu::Device,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    texture_view: &TextureView,
+    sampler: &wgpu::Sampler,
+    uniform_buffer: &wgpu::Buffer,
+    swizzle_buffer: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("pixels_scaling_renderer_bind_group"),
+        layout: bind_group_layout,
+        bindings: &[
+            wgpu::Binding {
+                binding: bindings::TEXTURE,
+                resource: wgpu::BindingResource::TextureView(texture_view),
+            },
+            wgpu::Binding {
+                binding: bindings::SAMPLER,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+            wgpu::Binding {
+                binding: bindings::TRANSFORM,
+                resource: wgpu::BindingResource::Buffer {
+                    buffer: uniform_buffer,
+                    range: 0..bindings::TRANSFORM_SIZE,
+                },
+            },
+            wgpu::Binding {
+                binding: bindings::SWIZZLE,
+                resource: wgpu::BindingResource::Buffer {
+                    buffer: swizzle_buffer,
+                    range: 0..bindings::SWIZZLE_SIZE,
+                },
+            },
+        ],
+    })
+}
+
 #[derive(Debug)]
 pub(crate) struct ScalingMatrix {
     pub(crate) transform: Mat4,
+    pub(crate) scale: (f32, f32),
+    pub(crate) destination: Rect,
+}
+
+/// The shape of the pixel buffer, which determines how it is scaled to the surface.
+///
+/// Set the dimension with [`PixelsBuilder::buffer_dimension`].
+///
+/// [`PixelsBuilder::buffer_dimension`]: ./struct.PixelsBuilder.html#method.buffer_dimension
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum BufferDimension {
+    /// A two-dimensional image, scaled with the configured [`ScalingMode`]. This is the default.
+    D2,
+    /// A one-dimensional strip of pixels with a height of 1, like the bars of an audio visualizer
+    /// or an oscilloscope trace.
+    ///
+    /// The strip is stretched to fill the entire surface, regardless of the [`ScalingMode`].
+    /// Columns are scaled by a non-integer factor when the surface width is not a multiple of the
+    /// strip width.
+    D1,
+}
+
+/// How the pixel buffer is scaled to fit the surface.
+///
+/// Set the scaling mode with [`PixelsBuilder::scaling_mode`].
+///
+/// [`PixelsBuilder::scaling_mode`]: ./struct.PixelsBuilder.html#method.scaling_mode
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum ScalingMode {
+    /// Scale by the largest integer factor that fits the surface, and center the buffer. Every
+    /// pixel is drawn with exactly the same size, at the cost of black borders when the surface
+    /// size is not a multiple of the buffer size. This is the default.
+    IntegerPerfect,
+    /// Scale by the largest factor that fits the surface while preserving the aspect ratio, and
+    /// center the buffer. Borders are only added along one axis, but pixels are not all drawn
+    /// with the same size when the factor is not an integer.
+    FitAspect,
+    /// Stretch the buffer to fill the entire surface, without borders. The aspect ratio is not
+    /// preserved.
+    Stretch,
+    /// Fit the surface like [`ScalingMode::FitAspect`], but without uneven pixel sizes.
+    ///
+    /// The buffer is first scaled by the nearest integer factor into an intermediate texture,
+    /// with nearest neighbor sampling, and then filtered bilinearly to its final size. Pixel
+    /// edges are slightly soft, but every pixel keeps the same size and moving images do not
+    /// shimmer. The intermediate texture costs one extra texture of about the destination size,
+    /// which is reallocated when the surface is resized.
+    ///
+    /// Custom [`ScalingShaders`] and the [`Swizzle`] are applied in the first, integer-scaled
+    /// stage.
+    ///
+    /// [`Swizzle`]: ./struct.Swizzle.html
+    SharpBilinear,
+    /// Scale each axis by its own integer factor, like [`ScalingMode::IntegerPerfect`] with
+    /// pixels that are `x` by `y` units in size.
+    ///
+    /// This suits systems with non-square native modes, like 640x200 CGA content shown with
+    /// `x: 1, y: 2`. The factors are multiplied by the largest integer that fits the surface, so
+    /// their proportion is kept at every surface size. Factors of 0 are treated as 1.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pixels::{PixelsBuilder, ScalingMode};
+    ///
+    /// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+    /// # let surface_texture = pixels::SurfaceTexture::new(1920, 1080, surface);
+    /// // Scaled by 2 horizontally and 4 vertically to 1280x800
+    /// let pixels = PixelsBuilder::new(640, 200, surface_texture)
+    ///     .scaling_mode(ScalingMode::IntegerPerAxis { x: 1, y: 2 })
+    ///     .build()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    IntegerPerAxis {
+        /// Horizontal scale factor.
+        x: u32,
+        /// Vertical scale factor.
+        y: u32,
+    },
+}
+
+impl Default for BufferDimension {
+    fn default() -> BufferDimension {
+        BufferDimension::D2
+    }
+}
+
+impl BufferDimension {
+    /// The scaling mode used for a buffer with this dimension.
+    pub(crate) fn scaling_mode(self, mode: ScalingMode) -> ScalingMode {
+        match self {
+            BufferDimension::D2 => mode,
+            BufferDimension::D1 => ScalingMode::Stretch,
+        }
+    }
+}
+
+/// Side-by-side output, for simple stereoscopic or dual-view setups.
+///
+/// The surface is split into a left and a right half, one for each eye, and the pixel buffer is
+/// scaled into each half with the [`ScalingMode`]. Each eye is shown either the entire buffer, or
+/// its own half of a buffer holding both images side by side.
+///
+/// Mouse coordinates from [`Pixels::window_pos_to_pixel`] are mapped through the left eye.
+///
+/// Enable side-by-side output with [`PixelsBuilder::side_by_side`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use pixels::{PixelsBuilder, SideBySide};
+///
+/// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+/// # let surface_texture = pixels::SurfaceTexture::new(1920, 1080, surface);
+/// // Both eyes are rendered into one 640x360 buffer, 320x360 each
+/// let pixels = PixelsBuilder::new(640, 360, surface_texture)
+///     .side_by_side(SideBySide {
+///         split_buffer: true,
+///         left_offset: (-16.0, 0.0),
+///         right_offset: (16.0, 0.0),
+///     })
+///     .build()?;
+/// # Ok::<(), pixels::Error>(())
+/// ```
+///
+/// [`Pixels::window_pos_to_pixel`]: ./struct.Pixels.html#method.window_pos_to_pixel
+/// [`PixelsBuilder::side_by_side`]: ./struct.PixelsBuilder.html#method.side_by_side
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SideBySide {
+    /// When `true`, the left half of the pixel buffer is shown to the left eye, and the right
+    /// half to the right eye. When `false`, both eyes are shown the entire buffer.
+    pub split_buffer: bool,
+    /// Offset of the left eye's image from its scaled position, in physical pixels.
+    pub left_offset: (f32, f32),
+    /// Offset of the right eye's image from its scaled position, in physical pixels.
+    pub right_offset: (f32, f32),
+}
+
+/// Edges of the pixel buffer which are hidden, like the overscan area that a CRT TV never showed.
+///
+/// Each field is a number of pixels cropped from that edge. The rest of the pixel buffer is scaled
+/// to the surface as if it was the entire buffer, and mouse coordinates from
+/// [`Pixels::window_pos_to_pixel`] still refer to the uncropped buffer. The crop stays
+/// proportional when the pixel buffer is resized with [`Pixels::resize_buffer`], so a 256x224
+/// buffer with 8 rows of overscan becomes a 512x448 buffer with 16.
+///
+/// Set the overscan with [`PixelsBuilder::overscan`] or an [`AspectPreset`].
+///
+/// [`Pixels::window_pos_to_pixel`]: ./struct.Pixels.html#method.window_pos_to_pixel
+/// [`Pixels::resize_buffer`]: ./struct.Pixels.html#method.resize_buffer
+/// [`PixelsBuilder::overscan`]: ./struct.PixelsBuilder.html#method.overscan
+/// [`AspectPreset`]: ./enum.AspectPreset.html
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Overscan {
+    /// Columns cropped from the left edge.
+    pub left: u32,
+    /// Rows cropped from the top edge.
+    pub top: u32,
+    /// Columns cropped from the right edge.
+    pub right: u32,
+    /// Rows cropped from the bottom edge.
+    pub bottom: u32,
+}
+
+impl Overscan {
+    /// No cropping. This is the default.
+    pub const NONE: Overscan = Overscan::new(0, 0, 0, 0);
+
+    /// Create an overscan crop, in pixels from each edge.
+    pub const fn new(left: u32, top: u32, right: u32, bottom: u32) -> Overscan {
+        Overscan {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+}
+
+/// The options of the scaling renderer which can be changed after it is created.
+///
+/// `Pixels` shares these with the scaling renderer, which applies them when its bindings are
+/// updated.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct ScalingOptions {
+    pub(crate) mode: ScalingMode,
+    pub(crate) filter: wgpu::FilterMode,
+    pub(crate) viewport: Option<Rect>,
+    pub(crate) offset: (f32, f32),
+    pub(crate) crop: Crop,
+}
+
+/// Everything which determines where the pixel buffer is drawn on the surface.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct Scaling {
+    pub(crate) mode: ScalingMode,
+    pub(crate) side_by_side: Option<SideBySide>,
+    // Width of each pixel relative to its height
+    pub(crate) pixel_aspect_ratio: f32,
+    // Edges of the pixel buffer which are not shown
+    pub(crate) crop: Crop,
+    // The part of the screen which the pixel buffer is drawn into, or `None` for the entire screen
+    pub(crate) viewport: Option<Rect>,
+    // Translation of the image in pixel buffer units, clipped to where it is drawn without one
+    pub(crate) offset: (f32, f32),
+}
+
+/// The fraction of the pixel buffer's width or height cropped from each edge.
+///
+/// Fractions keep the crop proportional when the pixel buffer is resized.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub(crate) struct Crop {
+    pub(crate) left: f32,
+    pub(crate) top: f32,
+    pub(crate) right: f32,
+    pub(crate) bottom: f32,
+}
+
+impl Crop {
+    /// The crop for `overscan` on a pixel buffer of `width` by `height` pixels.
+    pub(crate) fn new(overscan: Overscan, width: u32, height: u32) -> Crop {
+        Crop {
+            left: overscan.left as f32 / width as f32,
+            top: overscan.top as f32 / height as f32,
+            right: overscan.right as f32 / width as f32,
+            bottom: overscan.bottom as f32 / height as f32,
+        }
+    }
+}
+
+impl Scaling {
+    /// The number of images drawn: two for side-by-side output, otherwise one.
+    pub(crate) fn eyes(&self) -> usize {
+        if self.side_by_side.is_some() {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// The scaling matrix used for mapping surface positions to the pixel buffer. This is the
+    /// left eye for side-by-side output.
+    pub(crate) fn scaling_matrix(
+        &self,
+        texture_size: (f32, f32),
+        screen_size: (f32, f32),
+    ) -> ScalingMatrix {
+        self.eye_matrix(texture_size, screen_size, 0)
+    }
+
+    /// The scaling matrix for the image at index `eye`, relative to the entire screen.
+    pub(crate) fn eye_matrix(
+        &self,
+        texture_size: (f32, f32),
+        screen_size: (f32, f32),
+        eye: usize,
+    ) -> ScalingMatrix {
+        self.eye_placement(texture_size, screen_size, eye).0
+    }
+
+    /// The scissor rectangle `(x, y, width, height)` for the image at index `eye`, or `None` when
+    /// the image does not need to be clipped.
+    pub(crate) fn scissor(
+        &self,
+        texture_size: (f32, f32),
+        screen_size: (f32, f32),
+        eye: usize,
+    ) -> Option<(u32, u32, u32, u32)> {
+        if self.side_by_side.is_none()
+            && self.crop == Crop::default()
+            && self.viewport.is_none()
+            && self.offset == (0.0, 0.0)
+        {
+            return None;
+        }
+
+        let (_, visible) = self.eye_placement(texture_size, screen_size, eye);
+        let area = self.eye_area(screen_size, eye);
+
+        // Clip the visible image to its area of the screen
+        let left = visible.x.max(area.x).round().max(0.0);
+        let top = visible.y.max(area.y).round().max(0.0);
+        let right = (visible.x + visible.width)
+            .min(area.x + area.width)
+            .round()
+            .max(left);
+        let bottom = (visible.y + visible.height)
+            .min(area.y + area.height)
+            .round()
+            .max(top);
+
+        Some((
+            left as u32,
+            top as u32,
+            (right - left) as u32,
+            (bottom - top) as u32,
+        ))
+    }
+
+    /// The part of the screen which the pixel buffer is drawn into: the viewport, clipped to the
+    /// screen, or the entire screen.
+    pub(crate) fn viewport_area(&self, screen_size: (f32, f32)) -> Rect {
+        let (screen_width, screen_height) = screen_size;
+        match self.viewport {
+            Some(viewport) => {
+                let x = viewport.x.max(0.0).min(screen_width);
+                let y = viewport.y.max(0.0).min(screen_height);
+                Rect {
+                    x,
+                    y,
+                    width: viewport.width.min(screen_width - x).max(0.0),
+                    height: viewport.height.min(screen_height - y).max(0.0),
+                }
+            }
+            None => Rect {
+                x: 0.0,
+                y: 0.0,
+                width: screen_width,
+                height: screen_height,
+            },
+        }
+    }
+
+    /// The area of the screen for the image at `eye`, within the viewport.
+    fn eye_area(&self, screen_size: (f32, f32), eye: usize) -> Rect {
+        let area = self.viewport_area(screen_size);
+        if self.side_by_side.is_none() {
+            return area;
+        }
+
+        let half_width = (area.width / 2.0).floor();
+        if eye == 0 {
+            Rect {
+                width: half_width,
+                ..area
+            }
+        } else {
+            Rect {
+                x: area.x + half_width,
+                width: area.width - half_width,
+                ..area
+            }
+        }
+    }
+
+    /// The scaling matrix for the image at `eye`, and the rectangle on the screen where the
+    /// uncropped part of the image is drawn.
+    fn eye_placement(
+        &self,
+        texture_size: (f32, f32),
+        screen_size: (f32, f32),
+        eye: usize,
+    ) -> (ScalingMatrix, Rect) {
+        let (texture_width, texture_height) = texture_size;
+        let split_buffer = self
+            .side_by_side
+            .map_or(false, |side_by_side| side_by_side.split_buffer);
+
+        // Scale the uncropped part of the image into its area of the screen
+        let area = self.eye_area(screen_size, eye);
+        let eye_texture_width = if split_buffer {
+            texture_width / 2.0
+        } else {
+            texture_width
+        };
+        let crop = self.crop;
+        let visible_size = (
+            eye_texture_width * (1.0 - crop.left - crop.right),
+            texture_height * (1.0 - crop.top - crop.bottom),
+        );
+        let matrix = self.mode.scaling_matrix(
+            visible_size,
+            (area.width, area.height),
+            self.pixel_aspect_ratio,
+        );
+        let scale = matrix.scale;
+        let offset = match self.side_by_side {
+            Some(side_by_side) if eye == 0 => side_by_side.left_offset,
+            Some(side_by_side) => side_by_side.right_offset,
+            None => (0.0, 0.0),
+        };
+        let visible = Rect {
+            x: area.x + matrix.destination.x + offset.0,
+            y: area.y + matrix.destination.y + offset.1,
+            width: matrix.destination.width,
+            height: matrix.destination.height,
+        };
+
+        // The entire texture starts at the cropped edges, and the right half of a split buffer
+        // starts half of the texture further to the left. The render offset moves the image
+        // within the visible rectangle, which stays in place to clip it.
+        let mut x = visible.x - crop.left * eye_texture_width * scale.0 + self.offset.0 * scale.0;
+        if split_buffer && eye == 1 {
+            x -= eye_texture_width * scale.0;
+        }
+        let destination = Rect {
+            x,
+            y: visible.y - crop.top * texture_height * scale.1 + self.offset.1 * scale.1,
+            width: texture_width * scale.0,
+            height: texture_height * scale.1,
+        };
+
+        (
+            ScalingMatrix::from_destination(screen_size, scale, destination),
+            visible,
+        )
+    }
+}
+
+impl Default for ScalingMode {
+    fn default() -> ScalingMode {
+        ScalingMode::IntegerPerfect
+    }
+}
+
+impl ScalingMode {
+    /// The scaling matrix for this mode, with pixels `pixel_aspect_ratio` times as wide as they
+    /// are tall.
+    pub(crate) fn scaling_matrix(
+        self,
+        texture_size: (f32, f32),
+        screen_size: (f32, f32),
+        pixel_aspect_ratio: f32,
+    ) -> ScalingMatrix {
+        // Fit the texture as if it was already stretched horizontally
+        let (texture_width, texture_height) = texture_size;
+        let stretched_size = (texture_width * pixel_aspect_ratio, texture_height);
+        let scale = match self {
+            ScalingMode::IntegerPerfect => ScalingMatrix::new(stretched_size, screen_size),
+            ScalingMode::FitAspect | ScalingMode::SharpBilinear => {
+                ScalingMatrix::fit(stretched_size, screen_size)
+            }
+            ScalingMode::Stretch => ScalingMatrix::stretch(stretched_size, screen_size),
+            ScalingMode::IntegerPerAxis { x, y } => {
+                // Fit the texture as if each pixel was already `x` by `y`
+                let (x, y) = (x.max(1) as f32, y.max(1) as f32);
+                let factor =
+                    ScalingMatrix::new((stretched_size.0 * x, stretched_size.1 * y), screen_size)
+                        .scale
+                        .0;
+
+                ScalingMatrix::with_scale(stretched_size, screen_size, (factor * x, factor * y))
+            }
+        }
+        .scale;
+
+        ScalingMatrix::with_scale(
+            texture_size,
+            screen_size,
+            (scale.0 * pixel_aspect_ratio, scale.1),
+        )
+    }
 }
 
 impl ScalingMatrix {
@@ -205,23 +928,461 @@ impl ScalingMatrix {
             .max(1.0)
             .floor();
 
+        ScalingMatrix::with_scale(texture_size, screen_size, (scale, scale))
+    }
+
+    /// Scale the texture by the largest factor that fits the screen, preserving its aspect ratio.
+    pub(crate) fn fit(texture_size: (f32, f32), screen_size: (f32, f32)) -> ScalingMatrix {
+        let (screen_width, screen_height) = screen_size;
+        let (texture_width, texture_height) = texture_size;
+        let scale = (screen_width / texture_width).min(screen_height / texture_height);
+
+        ScalingMatrix::with_scale(texture_size, screen_size, (scale, scale))
+    }
+
+    /// Stretch the texture to fill the entire screen.
+    pub(crate) fn stretch(texture_size: (f32, f32), screen_size: (f32, f32)) -> ScalingMatrix {
+        let (screen_width, screen_height) = screen_size;
+        let (texture_width, texture_height) = texture_size;
+        let scale = (screen_width / texture_width, screen_height / texture_height);
+
+        ScalingMatrix::with_scale(texture_size, screen_size, scale)
+    }
+
+    /// Scale the texture by `scale` horizontally and vertically, centered on the screen.
+    fn with_scale(
+        texture_size: (f32, f32),
+        screen_size: (f32, f32),
+        scale: (f32, f32),
+    ) -> ScalingMatrix {
+        let (screen_width, screen_height) = screen_size;
+        let (texture_width, texture_height) = texture_size;
+
+        // The texture is centered on the screen
+        let width = texture_width * scale.0;
+        let height = texture_height * scale.1;
+        let destination = Rect {
+            x: (screen_width - width) / 2.0,
+            y: (screen_height - height) / 2.0,
+            width,
+            height,
+        };
+
+        ScalingMatrix::from_destination(screen_size, scale, destination)
+    }
+
+    /// Draw the texture scaled by `scale` into the `destination` rectangle on the screen.
+    pub(crate) fn from_destination(
+        screen_size: (f32, f32),
+        scale: (f32, f32),
+        destination: Rect,
+    ) -> ScalingMatrix {
+        let (screen_width, screen_height) = screen_size;
+
         // Update transformation matrix
-        let sw = texture_width * scale / screen_width;
-        let sh = texture_height * scale / screen_height;
+        let sw = destination.width / screen_width;
+        let sh = destination.height / screen_height;
+        let tx = (destination.x * 2.0 + destination.width) / screen_width - 1.0;
+        let ty = 1.0 - (destination.y * 2.0 + destination.height) / screen_height;
         #[rustfmt::skip]
         let transform: [f32; 16] = [
             sw,  0.0, 0.0, 0.0,
             0.0, -sh, 0.0, 0.0,
             0.0, 0.0, 1.0, 0.0,
-            0.0, 0.0, 0.0, 1.0,
+            tx,  ty,  0.0, 1.0,
         ];
 
         ScalingMatrix {
             transform: Mat4::from(transform),
+            scale,
+            destination,
         }
     }
 
-    fn as_bytes(&self) -> &[u8] {
+    pub(crate) fn as_bytes(&self) -> &[u8] {
         self.transform.as_byte_slice()
     }
 }
+
+/// The integer-scaled intermediate stage of [`ScalingMode::SharpBilinear`].
+#[derive(Debug)]
+struct Prescale {
+    // Drawn into with the pipeline of the renderer, so it has the render target format
+    texture: wgpu::Texture,
+    format: wgpu::TextureFormat,
+    texture_size: (u32, u32),
+    factor: (u32, u32),
+    // Draws the pixel buffer over the entire intermediate texture
+    transform_buffer: wgpu::Buffer,
+    // Samples the pixel buffer with nearest neighbor filtering, regardless of the user filter
+    input_sampler: wgpu::Sampler,
+    input_bind_group: wgpu::BindGroup,
+    // Filters the intermediate texture to the render target
+    sampler: wgpu::Sampler,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl Prescale {
+    /// The largest intermediate texture allocated, in either dimension.
+    const MAX_SIZE: u32 = 8192;
+
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        input_texture: &TextureView,
+        input_size: Extent3d,
+        swizzle_buffer: &wgpu::Buffer,
+        format: wgpu::TextureFormat,
+        blending: Blending,
+    ) -> Prescale {
+        let vs_module = device.create_shader_module(include_spv!("../shaders/vert.spv"));
+        let fs_module = device.create_shader_module(include_spv!("../shaders/frag.spv"));
+        let pipeline = create_pipeline(
+            device,
+            bind_group_layout,
+            &vs_module,
+            &fs_module,
+            format,
+            blending,
+        );
+        let input_sampler = InputSampler::default().create(device);
+        let sampler = InputSampler::new(wgpu::AddressMode::ClampToEdge, wgpu::FilterMode::Linear)
+            .create(device);
+
+        // Cover the entire texture, flipped so that the top row of the source stays on top
+        #[rustfmt::skip]
+        let transform = Mat4::from([
+            1.0,  0.0, 0.0, 0.0,
+            0.0, -1.0, 0.0, 0.0,
+            0.0,  0.0, 1.0, 0.0,
+            0.0,  0.0, 0.0, 1.0,
+        ]);
+        let transform_buffer =
+            device.create_buffer_with_data(transform.as_byte_slice(), wgpu::BufferUsage::UNIFORM);
+        let input_bind_group = create_bind_group(
+            device,
+            bind_group_layout,
+            input_texture,
+            &input_sampler,
+            &transform_buffer,
+            swizzle_buffer,
+        );
+
+        let texture_size = (input_size.width, input_size.height);
+        let factor = (1, 1);
+
+        Prescale {
+            texture: create_prescale_texture(device, texture_size, factor, format),
+            format,
+            texture_size,
+            factor,
+            transform_buffer,
+            input_sampler,
+            input_bind_group,
+            sampler,
+            pipeline,
+        }
+    }
+
+    /// The integer factor nearest to `scale` on each axis, limited by the maximum texture size.
+    fn factor(texture_size: (u32, u32), scale: (f32, f32)) -> (u32, u32) {
+        let factor = |size: u32, scale: f32| {
+            let max = (Prescale::MAX_SIZE / size.max(1)).max(1);
+
+            (scale.round().max(1.0) as u32).min(max)
+        };
+
+        (
+            factor(texture_size.0, scale.0),
+            factor(texture_size.1, scale.1),
+        )
+    }
+
+    /// Replace the intermediate texture, and the input texture when it is given.
+    fn reallocate(
+        &mut self,
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        input_texture: Option<&TextureView>,
+        texture_size: (u32, u32),
+        factor: (u32, u32),
+        swizzle_buffer: &wgpu::Buffer,
+    ) {
+        if let Some(input_texture) = input_texture {
+            self.input_bind_group = create_bind_group(
+                device,
+                bind_group_layout,
+                input_texture,
+                &self.input_sampler,
+                &self.transform_buffer,
+                swizzle_buffer,
+            );
+        }
+
+        self.texture = create_prescale_texture(device, texture_size, factor, self.format);
+        self.texture_size = texture_size;
+        self.factor = factor;
+    }
+
+    /// Create one bind group for each of `uniform_buffers` which samples the intermediate
+    /// texture.
+    fn create_bind_groups(
+        &self,
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        uniform_buffers: &[wgpu::Buffer],
+        swizzle_buffer: &wgpu::Buffer,
+    ) -> Vec<wgpu::BindGroup> {
+        let view = self.texture.create_default_view();
+
+        uniform_buffers
+            .iter()
+            .map(|uniform_buffer| {
+                create_bind_group(
+                    device,
+                    bind_group_layout,
+                    &view,
+                    &self.sampler,
+                    uniform_buffer,
+                    swizzle_buffer,
+                )
+            })
+            .collect()
+    }
+
+    /// Encode the integer-scaled draw of the input texture with `pipeline`.
+    fn render(&self, encoder: &mut wgpu::CommandEncoder, pipeline: &wgpu::RenderPipeline) {
+        let view = self.texture.create_default_view();
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                attachment: &view,
+                resolve_target: None,
+                load_op: wgpu::LoadOp::Clear,
+                store_op: wgpu::StoreOp::Store,
+                clear_color: wgpu::Color::TRANSPARENT,
+            }],
+            depth_stencil_attachment: None,
+        });
+        rpass.set_pipeline(pipeline);
+        rpass.set_bind_group(0, &self.input_bind_group, &[]);
+        rpass.draw(0..6, 0..1);
+    }
+}
+
+fn create_prescale_texture(
+    device: &wgpu::Device,
+    texture_size: (u32, u32),
+    factor: (u32, u32),
+    format: wgpu::TextureFormat,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("pixels_prescale_texture"),
+        size: Extent3d {
+            width: texture_size.0 * factor.0,
+            height: texture_size.1 * factor.1,
+            depth: 1,
+        },
+        array_layer_count: 1,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scaling(mode: ScalingMode) -> Scaling {
+        Scaling {
+            mode,
+            side_by_side: None,
+            pixel_aspect_ratio: 1.0,
+            crop: Crop::default(),
+            viewport: None,
+            offset: (0.0, 0.0),
+        }
+    }
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn assert_rect_eq(actual: Rect, expected: Rect) {
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-3;
+        assert!(
+            close(actual.x, expected.x)
+                && close(actual.y, expected.y)
+                && close(actual.width, expected.width)
+                && close(actual.height, expected.height),
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    /// The destination of the entire pixel buffer, and the visible part of it.
+    fn placement(
+        scaling: &Scaling,
+        texture_size: (f32, f32),
+        screen_size: (f32, f32),
+        eye: usize,
+    ) -> (Rect, Rect) {
+        let (matrix, visible) = scaling.eye_placement(texture_size, screen_size, eye);
+
+        (matrix.destination, visible)
+    }
+
+    #[test]
+    fn integer_perfect_is_centered() {
+        let scaling = scaling(ScalingMode::IntegerPerfect);
+        let (destination, visible) = placement(&scaling, (320.0, 240.0), (1000.0, 800.0), 0);
+
+        assert_rect_eq(destination, rect(20.0, 40.0, 960.0, 720.0));
+        assert_rect_eq(visible, destination);
+        assert_eq!(scaling.scissor((320.0, 240.0), (1000.0, 800.0), 0), None);
+    }
+
+    #[test]
+    fn fit_aspect_fills_one_axis() {
+        let scaling = scaling(ScalingMode::FitAspect);
+        let (destination, _) = placement(&scaling, (320.0, 240.0), (1000.0, 800.0), 0);
+
+        assert_rect_eq(destination, rect(0.0, 25.0, 1000.0, 750.0));
+    }
+
+    #[test]
+    fn stretch_fills_the_screen() {
+        let scaling = scaling(ScalingMode::Stretch);
+        let (destination, _) = placement(&scaling, (320.0, 240.0), (1000.0, 800.0), 0);
+
+        assert_rect_eq(destination, rect(0.0, 0.0, 1000.0, 800.0));
+    }
+
+    #[test]
+    fn side_by_side_split_buffer() {
+        let scaling = Scaling {
+            side_by_side: Some(SideBySide {
+                split_buffer: true,
+                left_offset: (-16.0, 0.0),
+                right_offset: (16.0, 0.0),
+            }),
+            ..scaling(ScalingMode::IntegerPerfect)
+        };
+        let (texture_size, screen_size) = ((640.0, 360.0), (1920.0, 1080.0));
+
+        // Each eye shows its half of the buffer, scaled by 3 into its half of the screen
+        let (destination, visible) = placement(&scaling, texture_size, screen_size, 0);
+        assert_rect_eq(visible, rect(-16.0, 0.0, 960.0, 1080.0));
+        assert_rect_eq(destination, rect(-16.0, 0.0, 1920.0, 1080.0));
+        assert_eq!(
+            scaling.scissor(texture_size, screen_size, 0),
+            Some((0, 0, 944, 1080))
+        );
+
+        // The right half of the buffer starts at the left edge of the right eye's image
+        let (destination, visible) = placement(&scaling, texture_size, screen_size, 1);
+        assert_rect_eq(visible, rect(976.0, 0.0, 960.0, 1080.0));
+        assert_rect_eq(destination, rect(16.0, 0.0, 1920.0, 1080.0));
+        assert_eq!(
+            scaling.scissor(texture_size, screen_size, 1),
+            Some((976, 0, 944, 1080))
+        );
+    }
+
+    #[test]
+    fn overscan_crop() {
+        let scaling = Scaling {
+            crop: Crop::new(Overscan::new(0, 8, 0, 8), 256, 240),
+            ..scaling(ScalingMode::IntegerPerfect)
+        };
+        let (texture_size, screen_size) = ((256.0, 240.0), (512.0, 448.0));
+
+        // The 256x224 visible rows fill the screen, and the cropped rows are drawn outside of it
+        let (destination, visible) = placement(&scaling, texture_size, screen_size, 0);
+        assert_rect_eq(visible, rect(0.0, 0.0, 512.0, 448.0));
+        assert_rect_eq(destination, rect(0.0, -16.0, 512.0, 480.0));
+        assert_eq!(
+            scaling.scissor(texture_size, screen_size, 0),
+            Some((0, 0, 512, 448))
+        );
+    }
+
+    #[test]
+    fn viewport() {
+        let scaling = Scaling {
+            viewport: Some(rect(100.0, 50.0, 640.0, 480.0)),
+            ..scaling(ScalingMode::IntegerPerfect)
+        };
+        let (texture_size, screen_size) = ((320.0, 240.0), (1000.0, 800.0));
+
+        let (destination, visible) = placement(&scaling, texture_size, screen_size, 0);
+        assert_rect_eq(destination, rect(100.0, 50.0, 640.0, 480.0));
+        assert_rect_eq(visible, destination);
+        assert_eq!(
+            scaling.scissor(texture_size, screen_size, 0),
+            Some((100, 50, 640, 480))
+        );
+    }
+
+    #[test]
+    fn viewport_is_clipped_to_the_screen() {
+        let scaling = Scaling {
+            viewport: Some(rect(-100.0, 0.0, 800.0, 480.0)),
+            ..scaling(ScalingMode::Stretch)
+        };
+        let (texture_size, screen_size) = ((320.0, 240.0), (640.0, 480.0));
+
+        let (destination, _) = placement(&scaling, texture_size, screen_size, 0);
+        assert_rect_eq(destination, rect(0.0, 0.0, 640.0, 480.0));
+        assert_eq!(
+            scaling.scissor(texture_size, screen_size, 0),
+            Some((0, 0, 640, 480))
+        );
+    }
+
+    #[test]
+    fn render_offset_is_clipped() {
+        let scaling = Scaling {
+            offset: (4.0, -2.0),
+            ..scaling(ScalingMode::IntegerPerfect)
+        };
+        let (texture_size, screen_size) = ((320.0, 240.0), (640.0, 480.0));
+
+        // The image moves by the offset in buffer pixels, but is clipped where it was
+        let (destination, visible) = placement(&scaling, texture_size, screen_size, 0);
+        assert_rect_eq(visible, rect(0.0, 0.0, 640.0, 480.0));
+        assert_rect_eq(destination, rect(8.0, -4.0, 640.0, 480.0));
+        assert_eq!(
+            scaling.scissor(texture_size, screen_size, 0),
+            Some((0, 0, 640, 480))
+        );
+    }
+
+    #[test]
+    fn source_rect() {
+        // A 320x240 region at (256, 128) of a 1024x1024 buffer
+        let overscan = Overscan::new(256, 128, 1024 - 256 - 320, 1024 - 128 - 240);
+        let scaling = Scaling {
+            crop: Crop::new(overscan, 1024, 1024),
+            ..scaling(ScalingMode::IntegerPerfect)
+        };
+        let (texture_size, screen_size) = ((1024.0, 1024.0), (640.0, 480.0));
+
+        let (destination, visible) = placement(&scaling, texture_size, screen_size, 0);
+        assert_rect_eq(visible, rect(0.0, 0.0, 640.0, 480.0));
+        assert_rect_eq(destination, rect(-512.0, -256.0, 2048.0, 2048.0));
+        assert_eq!(
+            scaling.scissor(texture_size, screen_size, 0),
+            Some((0, 0, 640, 480))
+        );
+    }
+}
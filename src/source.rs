@@ -0,0 +1,101 @@
+/// The byte order of pixels produced by an [`ExternalFrameSource`].
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum SourceFormat {
+    /// 4 bytes per pixel in `RGBA` order. This is the format of the pixel buffer, so it needs no
+    /// conversion.
+    Rgba8,
+    /// 4 bytes per pixel in `BGRA` order.
+    Bgra8,
+    /// 3 bytes per pixel in `RGB` order.
+    Rgb8,
+    /// 3 bytes per pixel in `BGR` order.
+    Bgr8,
+}
+
+/// The layout of a frame produced by an [`ExternalFrameSource`].
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct FrameInfo {
+    /// Width of the frame in pixels.
+    pub width: u32,
+    /// Height of the frame in pixels.
+    pub height: u32,
+    /// Number of bytes from the start of one row to the start of the next. This is often larger
+    /// than the width of a row, because capture hardware pads rows for alignment.
+    pub stride: usize,
+    /// The byte order of each pixel.
+    pub format: SourceFormat,
+}
+
+/// A producer of frames which are displayed by [`Pixels::drive_from`], like a video capture card
+/// or a webcam.
+///
+/// [`Pixels::drive_from`]: ./struct.Pixels.html#method.drive_from
+pub trait ExternalFrameSource {
+    /// Choose the layout of the frames this source produces.
+    ///
+    /// `preferred` is the layout of the pixel buffer, which can be filled without any conversion.
+    /// Sources should return it unchanged if they can produce it; otherwise they return the
+    /// layout they actually produce, and `pixels` converts each frame on the CPU.
+    ///
+    /// The default implementation accepts the preferred layout.
+    fn negotiate(&mut self, preferred: FrameInfo) -> FrameInfo {
+        preferred
+    }
+
+    /// Write the next frame into `buffer`, which is large enough to hold a frame with the
+    /// negotiated layout.
+    ///
+    /// Returns the layout of the frame that was written, or `None` when no new frame is available
+    /// yet.
+    fn next_frame(&mut self, buffer: &mut [u8]) -> Option<FrameInfo>;
+}
+
+impl FrameInfo {
+    /// The number of bytes for a frame with this layout.
+    pub(crate) fn frame_size(&self) -> usize {
+        self.stride * self.height as usize
+    }
+}
+
+impl SourceFormat {
+    /// The number of bytes per pixel.
+    pub(crate) fn bytes_per_pixel(self) -> usize {
+        match self {
+            SourceFormat::Rgba8 | SourceFormat::Bgra8 => 4,
+            SourceFormat::Rgb8 | SourceFormat::Bgr8 => 3,
+        }
+    }
+}
+
+/// Copy a frame described by `info` from `src` into an `RGBA` frame of `width` by `height` pixels.
+///
+/// Only the region covered by both frames is copied; the rest of `dst` is left untouched.
+pub(crate) fn copy_frame(src: &[u8], info: &FrameInfo, dst: &mut [u8], width: u32, height: u32) {
+    let columns = info.width.min(width) as usize;
+    let rows = info.height.min(height) as usize;
+    let bytes_per_pixel = info.format.bytes_per_pixel();
+    let dst_stride = width as usize * 4;
+
+    for y in 0..rows {
+        let src_row = &src[y * info.stride..y * info.stride + columns * bytes_per_pixel];
+        let dst_row = &mut dst[y * dst_stride..y * dst_stride + columns * 4];
+
+        if info.format == SourceFormat::Rgba8 {
+            dst_row.copy_from_slice(src_row);
+            continue;
+        }
+
+        for (pixel, source) in dst_row
+            .chunks_exact_mut(4)
+            .zip(src_row.chunks_exact(bytes_per_pixel))
+        {
+            let rgba = match info.format {
+                SourceFormat::Rgba8 => [source[0], source[1], source[2], source[3]],
+                SourceFormat::Bgra8 => [source[2], source[1], source[0], source[3]],
+                SourceFormat::Rgb8 => [source[0], source[1], source[2], 0xff],
+                SourceFormat::Bgr8 => [source[2], source[1], source[0], 0xff],
+            };
+            pixel.copy_from_slice(&rgba);
+        }
+    }
+}
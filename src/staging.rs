@@ -0,0 +1,142 @@
+use futures_util::task::noop_waker_ref;
+use std::fmt;
+use std::future::Future;
+use std::mem;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+type MapFuture =
+    Pin<Box<dyn Future<Output = Result<wgpu::BufferWriteMapping, wgpu::BufferAsyncErr>> + Send>>;
+
+/// The number of staging buffers kept for reuse. Frames which find all of them in flight get a
+/// buffer of their own, which is dropped after submission.
+const RING_SIZE: usize = 3;
+
+/// A staging buffer and where it is in its cycle.
+struct StagingBuffer {
+    buffer: wgpu::Buffer,
+    size: u64,
+    state: State,
+}
+
+enum State {
+    // Copied from by commands which have not been submitted yet
+    InUse,
+    // Waiting for the GPU to finish copying from the buffer
+    Mapping(MapFuture),
+    // Ready to be written by the CPU
+    Mapped(wgpu::BufferWriteMapping),
+}
+
+/// A ring of staging buffers for uploads to the GPU, reused across frames instead of allocating
+/// a new buffer for every upload.
+///
+/// `wgpu` has no queue uploads, so every buffer is mapped for writing again once the commands
+/// which copy from it have been submitted, and becomes available when the GPU is done with it.
+#[derive(Default)]
+pub(crate) struct StagingRing {
+    buffers: Vec<StagingBuffer>,
+    // Buffers allocated when the ring is exhausted, kept until the next recall
+    transient: Vec<wgpu::Buffer>,
+}
+
+impl StagingBuffer {
+    fn is_mapped(&self) -> bool {
+        if let State::Mapped(_) = self.state {
+            true
+        } else {
+            false
+        }
+    }
+
+    fn poll(&mut self, context: &mut Context<'_>) {
+        if let State::Mapping(future) = &mut self.state {
+            if let Poll::Ready(result) = future.as_mut().poll(context) {
+                self.state = match result {
+                    Ok(mapping) => State::Mapped(mapping),
+                    // Mapping is requested again after the next submission
+                    Err(_) => State::InUse,
+                };
+            }
+        }
+    }
+}
+
+impl StagingRing {
+    /// A staging buffer with at least `size` bytes, of which the first `size` are written by
+    /// `fill`.
+    ///
+    /// The buffer must only be used by commands which are submitted before the next call to
+    /// [`StagingRing::recall`].
+    pub(crate) fn write<F>(&mut self, device: &wgpu::Device, size: u64, fill: F) -> &wgpu::Buffer
+    where
+        F: FnOnce(&mut [u8]),
+    {
+        let mut context = Context::from_waker(noop_waker_ref());
+        for staging in self.buffers.iter_mut() {
+            staging.poll(&mut context);
+        }
+
+        // Buffers which are too small, e.g. after the pixel buffer grew, are replaced
+        self.buffers.retain(|staging| staging.size >= size);
+
+        if let Some(i) = self.buffers.iter().position(StagingBuffer::is_mapped) {
+            let staging = &mut self.buffers[i];
+            if let State::Mapped(mut mapping) = mem::replace(&mut staging.state, State::InUse) {
+                fill(&mut mapping.as_slice()[..size as usize]);
+            }
+            staging.buffer.unmap();
+
+            return &self.buffers[i].buffer;
+        }
+
+        let reusable = self.buffers.len() < RING_SIZE;
+        let usage = if reusable {
+            wgpu::BufferUsage::MAP_WRITE | wgpu::BufferUsage::COPY_SRC
+        } else {
+            wgpu::BufferUsage::COPY_SRC
+        };
+        let mapped = device.create_buffer_mapped(&wgpu::BufferDescriptor {
+            label: Some("pixels_staging_buffer"),
+            size,
+            usage,
+        });
+        fill(mapped.data);
+        let buffer = mapped.finish();
+
+        if reusable {
+            self.buffers.push(StagingBuffer {
+                buffer,
+                size,
+                state: State::InUse,
+            });
+            &self.buffers.last().unwrap().buffer
+        } else {
+            self.transient.push(buffer);
+            self.transient.last().unwrap()
+        }
+    }
+
+    /// Map every buffer written since the last recall, so it can be reused once the GPU is done
+    /// with it.
+    ///
+    /// Call this after submitting the commands which copy from the buffers.
+    pub(crate) fn recall(&mut self) {
+        self.transient.clear();
+        for staging in self.buffers.iter_mut() {
+            if let State::InUse = staging.state {
+                staging.state = State::Mapping(Box::pin(staging.buffer.map_write(0, staging.size)));
+            }
+        }
+    }
+}
+
+impl fmt::Debug for StagingRing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mapped = self.buffers.iter().filter(|staging| staging.is_mapped());
+        f.debug_struct("StagingRing")
+            .field("buffers", &self.buffers.len())
+            .field("mapped", &mapped.count())
+            .finish()
+    }
+}
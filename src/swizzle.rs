@@ -0,0 +1,132 @@
+/// The source of one component in a [`Swizzle`].
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum SwizzleComponent {
+    /// The red (first) component of the source texture.
+    R,
+    /// The green (second) component of the source texture.
+    G,
+    /// The blue (third) component of the source texture.
+    B,
+    /// The alpha (fourth) component of the source texture.
+    A,
+    /// The constant `0.0`.
+    Zero,
+    /// The constant `1.0`.
+    One,
+}
+
+/// Reorders the components of the source texture when it is drawn.
+///
+/// This allows frames with an unusual byte order to be displayed correctly without swapping
+/// channels on the CPU. For example, frames from Windows GDI captures are stored as `BGRA` (or
+/// `BGRX`, with an undefined alpha byte) and can be displayed with [`Swizzle::BGRX`].
+///
+/// The swizzle is applied after the texture is sampled, so `sRGB` decoding still applies to the
+/// first three bytes of each pixel in the source data.
+///
+/// # Examples
+///
+/// ```no_run
+/// use pixels::{PixelsBuilder, Swizzle};
+///
+/// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+/// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+/// let pixels = PixelsBuilder::new(320, 240, surface_texture)
+///     .swizzle(Swizzle::BGRA)
+///     .build()?;
+/// # Ok::<(), pixels::Error>(())
+/// ```
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Swizzle {
+    /// The source of the red component.
+    pub r: SwizzleComponent,
+    /// The source of the green component.
+    pub g: SwizzleComponent,
+    /// The source of the blue component.
+    pub b: SwizzleComponent,
+    /// The source of the alpha component.
+    pub a: SwizzleComponent,
+}
+
+impl Swizzle {
+    /// Components are used as they are. This is the default.
+    pub const IDENTITY: Swizzle = Swizzle::new(
+        SwizzleComponent::R,
+        SwizzleComponent::G,
+        SwizzleComponent::B,
+        SwizzleComponent::A,
+    );
+
+    /// Source bytes are in `BGRA` order.
+    pub const BGRA: Swizzle = Swizzle::new(
+        SwizzleComponent::B,
+        SwizzleComponent::G,
+        SwizzleComponent::R,
+        SwizzleComponent::A,
+    );
+
+    /// Source bytes are in `BGRX` order, where the last byte is ignored and the result is opaque.
+    pub const BGRX: Swizzle = Swizzle::new(
+        SwizzleComponent::B,
+        SwizzleComponent::G,
+        SwizzleComponent::R,
+        SwizzleComponent::One,
+    );
+
+    /// Source bytes are in `ARGB` order.
+    pub const ARGB: Swizzle = Swizzle::new(
+        SwizzleComponent::G,
+        SwizzleComponent::B,
+        SwizzleComponent::A,
+        SwizzleComponent::R,
+    );
+
+    /// Source bytes are in `ABGR` order.
+    pub const ABGR: Swizzle = Swizzle::new(
+        SwizzleComponent::A,
+        SwizzleComponent::B,
+        SwizzleComponent::G,
+        SwizzleComponent::R,
+    );
+
+    /// Create a swizzle from the sources of each output component.
+    pub const fn new(
+        r: SwizzleComponent,
+        g: SwizzleComponent,
+        b: SwizzleComponent,
+        a: SwizzleComponent,
+    ) -> Swizzle {
+        Swizzle { r, g, b, a }
+    }
+
+    /// The shader uniform for this swizzle: a column-major matrix which selects the source
+    /// components, followed by a vector of constants.
+    pub(crate) fn to_bytes(self) -> Vec<u8> {
+        let mut matrix = [[0.0f32; 4]; 4];
+        let mut constant = [0.0f32; 4];
+
+        for (row, component) in [self.r, self.g, self.b, self.a].iter().enumerate() {
+            match component {
+                SwizzleComponent::R => matrix[0][row] = 1.0,
+                SwizzleComponent::G => matrix[1][row] = 1.0,
+                SwizzleComponent::B => matrix[2][row] = 1.0,
+                SwizzleComponent::A => matrix[3][row] = 1.0,
+                SwizzleComponent::Zero => (),
+                SwizzleComponent::One => constant[row] = 1.0,
+            }
+        }
+
+        let mut bytes = Vec::with_capacity(80);
+        for value in matrix.iter().flatten().chain(constant.iter()) {
+            bytes.extend_from_slice(&value.to_ne_bytes());
+        }
+
+        bytes
+    }
+}
+
+impl Default for Swizzle {
+    fn default() -> Swizzle {
+        Swizzle::IDENTITY
+    }
+}
@@ -0,0 +1,154 @@
+use ultraviolet::Mat4;
+use wgpu::TextureView;
+
+use crate::bindings;
+use crate::include_spv;
+use crate::pipeline::{create_pipeline, Blending};
+use crate::readback::Readback;
+use crate::render_pass::InputSampler;
+
+/// Encode a downscaled copy of `source` with the given `size`, and a copy of it into a readback
+/// buffer.
+///
+/// The texture is halved with bilinear filtering until it is within a factor of two of the
+/// thumbnail size, so every source pixel contributes to the result, and then filtered to the
+/// exact size. The textures drawn into are returned so that they outlive the command buffer
+/// submission.
+pub(crate) fn render_thumbnail(
+    device: &wgpu::Device,
+    encoder: &mut wgpu::CommandEncoder,
+    source: &TextureView,
+    source_size: (u32, u32),
+    size: (u32, u32),
+) -> (Vec<wgpu::Texture>, Readback) {
+    let vs_module = device.create_shader_module(include_spv!("../shaders/vert.spv"));
+    let fs_module = device.create_shader_module(include_spv!("../shaders/frag.spv"));
+    let sampler =
+        InputSampler::new(wgpu::AddressMode::ClampToEdge, wgpu::FilterMode::Linear).create(device);
+
+    // Cover the entire texture, flipped so that the top row of the source stays on top
+    #[rustfmt::skip]
+        let transform = Mat4::from([
+            1.0,  0.0, 0.0, 0.0,
+            0.0, -1.0, 0.0, 0.0,
+            0.0,  0.0, 1.0, 0.0,
+            0.0,  0.0, 0.0, 1.0,
+        ]);
+    let transform_buffer =
+        device.create_buffer_with_data(transform.as_byte_slice(), wgpu::BufferUsage::UNIFORM);
+
+    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("pixels_thumbnail_bind_group_layout"),
+        bindings: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: bindings::TEXTURE,
+                visibility: wgpu::ShaderStage::FRAGMENT,
+                ty: wgpu::BindingType::SampledTexture {
+                    component_type: wgpu::TextureComponentType::Uint,
+                    multisampled: false,
+                    dimension: wgpu::TextureViewDimension::D2,
+                },
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: bindings::SAMPLER,
+                visibility: wgpu::ShaderStage::FRAGMENT,
+                ty: wgpu::BindingType::Sampler { comparison: false },
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: bindings::TRANSFORM,
+                visibility: wgpu::ShaderStage::VERTEX,
+                ty: wgpu::BindingType::UniformBuffer { dynamic: false },
+            },
+        ],
+    });
+    let pipeline = create_pipeline(
+        device,
+        &bind_group_layout,
+        &vs_module,
+        &fs_module,
+        wgpu::TextureFormat::Rgba8UnormSrgb,
+        Blending::Replace,
+    );
+
+    let mut textures: Vec<wgpu::Texture> = Vec::new();
+    let mut current = source_size;
+    loop {
+        let done = current.0 <= size.0 * 2 && current.1 <= size.1 * 2;
+        let next = if done {
+            size
+        } else {
+            ((current.0 / 2).max(size.0), (current.1 / 2).max(size.1))
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("pixels_thumbnail_texture"),
+            size: wgpu::Extent3d {
+                width: next.0,
+                height: next.1,
+                depth: 1,
+            },
+            array_layer_count: 1,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsage::SAMPLED
+                | wgpu::TextureUsage::OUTPUT_ATTACHMENT
+                | wgpu::TextureUsage::COPY_SRC,
+        });
+
+        // Each pass samples the texture drawn by the previous pass
+        let input = textures
+            .last()
+            .map(|previous| previous.create_default_view());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("pixels_thumbnail_bind_group"),
+            layout: &bind_group_layout,
+            bindings: &[
+                wgpu::Binding {
+                    binding: bindings::TEXTURE,
+                    resource: wgpu::BindingResource::TextureView(input.as_ref().unwrap_or(source)),
+                },
+                wgpu::Binding {
+                    binding: bindings::SAMPLER,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+                wgpu::Binding {
+                    binding: bindings::TRANSFORM,
+                    resource: wgpu::BindingResource::Buffer {
+                        buffer: &transform_buffer,
+                        range: 0..bindings::TRANSFORM_SIZE,
+                    },
+                },
+            ],
+        });
+
+        let view = texture.create_default_view();
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                    attachment: &view,
+                    resolve_target: None,
+                    load_op: wgpu::LoadOp::Clear,
+                    store_op: wgpu::StoreOp::Store,
+                    clear_color: wgpu::Color::TRANSPARENT,
+                }],
+                depth_stencil_attachment: None,
+            });
+            rpass.set_pipeline(&pipeline);
+            rpass.set_bind_group(0, &bind_group, &[]);
+            rpass.draw(0..6, 0..1);
+        }
+
+        textures.push(texture);
+        current = next;
+        if done {
+            break;
+        }
+    }
+
+    let readback = Readback::new(device, size.0, size.1, 4, false);
+    readback.copy_from_texture(encoder, textures.last().unwrap());
+
+    (textures, readback)
+}
@@ -0,0 +1,139 @@
+use std::sync::{Arc, Mutex};
+
+use crate::color;
+use crate::crash::lock;
+use crate::render_pass::{Device, Queue};
+use crate::FrameFormat;
+
+/// A handle for uploading whole frames to the pixel buffer texture from another thread.
+///
+/// Each upload copies the frame into a staging buffer, and submits the copy to the texture on
+/// the shared queue, all on the calling thread. [`Pixels::render`] then only records the render
+/// passes, so a worker thread can upload one frame while the render thread draws the last one.
+/// Uploaded frames are drawn by the next call to [`Pixels::render`] or [`Pixels::present_partial`],
+/// and [`Pixels::needs_redraw`] returns `true` until then.
+///
+/// Uploads replace the texture directly: the pixel buffer returned by [`Pixels::get_frame`] is not
+/// changed, and frames blended by [`PixelsBuilder::interpolate_frames`] are not kept. Writing to
+/// [`Pixels::get_frame`] still uploads the changed rows on the render thread, over the last frame
+/// uploaded by the handle.
+///
+/// The handle keeps uploading to the right texture when the pixel buffer is resized with
+/// [`Pixels::resize_buffer`], and follows [`Pixels::set_bottom_up_rows`]. Handles are cheap to
+/// clone.
+///
+/// # Example
+///
+/// ```no_run
+/// # use pixels::Pixels;
+/// # let surface = wgpu::Surface::create(&pixels_mocks::RWH);
+/// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, surface);
+/// let mut pixels = Pixels::new(256, 240, surface_texture)?;
+/// let uploader = pixels.texture_uploader();
+///
+/// std::thread::spawn(move || loop {
+///     let frame = vec![0; 256 * 240 * 4];
+///     // ... Emulate one frame
+///     uploader.upload(&frame);
+/// });
+///
+/// // On the render thread, for every redraw
+/// if pixels.needs_redraw() {
+///     pixels.render()?;
+/// }
+/// # Ok::<(), pixels::Error>(())
+/// ```
+///
+/// [`Pixels::render`]: ./struct.Pixels.html#method.render
+/// [`Pixels::present_partial`]: ./struct.Pixels.html#method.present_partial
+/// [`Pixels::needs_redraw`]: ./struct.Pixels.html#method.needs_redraw
+/// [`Pixels::get_frame`]: ./struct.Pixels.html#method.get_frame
+/// [`Pixels::resize_buffer`]: ./struct.Pixels.html#method.resize_buffer
+/// [`Pixels::set_bottom_up_rows`]: ./struct.Pixels.html#method.set_bottom_up_rows
+/// [`PixelsBuilder::interpolate_frames`]: ./struct.PixelsBuilder.html#method.interpolate_frames
+#[derive(Clone, Debug)]
+pub struct TextureUploader {
+    device: Device,
+    queue: Queue,
+    target: Arc<Mutex<UploadTarget>>,
+}
+
+/// The texture which frames are uploaded to, and how, shared with [`Pixels`].
+///
+/// [`Pixels`]: ./struct.Pixels.html
+#[derive(Debug)]
+pub(crate) struct UploadTarget {
+    pub(crate) texture: Arc<wgpu::Texture>,
+    pub(crate) extent: wgpu::Extent3d,
+    pub(crate) bytes_per_pixel: u32,
+    pub(crate) bottom_up_rows: bool,
+    pub(crate) premultiply_alpha: bool,
+    pub(crate) srgb: bool,
+    // Set by each upload, and cleared when the texture is drawn
+    pub(crate) uploaded: bool,
+}
+
+impl TextureUploader {
+    pub(crate) fn new(
+        device: Device,
+        queue: Queue,
+        target: Arc<Mutex<UploadTarget>>,
+    ) -> TextureUploader {
+        TextureUploader {
+            device,
+            queue,
+            target,
+        }
+    }
+
+    /// Upload a whole frame, in the texture format of the pixel buffer, and submit it to the GPU.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `frame` does not have the size of the pixel buffer.
+    pub fn upload(&self, frame: &[u8]) {
+        // Hold the target until the copy is submitted, so a resize cannot replace the texture
+        // in between
+        let mut target = lock(&self.target);
+        let bytes_per_row = target.extent.width * target.bytes_per_pixel;
+        assert_eq!(frame.len(), (bytes_per_row * target.extent.height) as usize);
+
+        let buffer = if target.bottom_up_rows || target.premultiply_alpha {
+            let mut data = frame.to_vec();
+            if target.bottom_up_rows {
+                FrameFormat::Texture.flip_rows(frame, &mut data, target.extent.height);
+            }
+            if target.premultiply_alpha {
+                color::premultiply_alpha(&mut data, target.srgb);
+            }
+            self.device
+                .create_buffer_with_data(&data, wgpu::BufferUsage::COPY_SRC)
+        } else {
+            self.device
+                .create_buffer_with_data(frame, wgpu::BufferUsage::COPY_SRC)
+        };
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("pixels_texture_uploader"),
+            });
+        encoder.copy_buffer_to_texture(
+            wgpu::BufferCopyView {
+                buffer: &buffer,
+                offset: 0,
+                bytes_per_row,
+                rows_per_image: target.extent.height,
+            },
+            wgpu::TextureCopyView {
+                texture: &target.texture,
+                mip_level: 0,
+                array_layer: 0,
+                origin: wgpu::Origin3d { x: 0, y: 0, z: 0 },
+            },
+            target.extent,
+        );
+        lock(&self.queue).submit(&[encoder.finish()]);
+        target.uploaded = true;
+    }
+}